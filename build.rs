@@ -0,0 +1,24 @@
+fn main() {
+  #[cfg(feature = "ffi")]
+  header();
+}
+
+// the C api's header, into OUT_DIR since build scripts mustn't touch the source tree.
+// include/g6502.h is the checked-in copy for C users, a test fails when it's stale
+#[cfg(feature = "ffi")]
+fn header() {
+  let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+  let out_dir = std::env::var("OUT_DIR").unwrap();
+  let config = cbindgen::Config::from_file(format!("{}/cbindgen.toml", crate_dir))
+    .expect("can't read cbindgen.toml");
+
+  cbindgen::Builder::new()
+    .with_crate(&crate_dir)
+    .with_config(config)
+    .generate()
+    .expect("can't generate the C header")
+    .write_to_file(format!("{}/g6502.h", out_dir));
+
+  println!("cargo:rerun-if-changed=src/ffi");
+  println!("cargo:rerun-if-changed=cbindgen.toml");
+}
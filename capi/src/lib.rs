@@ -0,0 +1,2 @@
+// the C api lives in g6502::ffi, this only links it into a C library
+pub use g6502::ffi::*;
@@ -0,0 +1,33 @@
+// links the static library from C, CI runs it after building capi/
+#include <stdio.h>
+
+#include "g6502.h"
+
+int main(void) {
+  // LDA #$42 ; STA $10 ; loop: JMP loop
+  const uint8_t program[] = {0xA9, 0x42, 0x85, 0x10, 0x4C, 0x04, 0x02};
+  const uint8_t reset[] = {0x00, 0x02};
+
+  Vm *vm = vm_new();
+  if (vm_load(vm, program, sizeof program, 0x0200) != VM_STATUS_OK ||
+      vm_load(vm, reset, sizeof reset, 0xFFFC) != VM_STATUS_OK) {
+    fprintf(stderr, "load failed\n");
+    return 1;
+  }
+  vm_reset(vm);
+
+  uint16_t stopped = 0;
+  vm_add_breakpoint(vm, 0x0204);
+  if (vm_run(vm, 1000, &stopped) != VM_STATUS_BREAKPOINT || stopped != 0x0204) {
+    fprintf(stderr, "didn't stop at the breakpoint\n");
+    return 1;
+  }
+  if (vm_read(vm, 0x0010) != 0x42) {
+    fprintf(stderr, "STA didn't store\n");
+    return 1;
+  }
+
+  vm_free(vm);
+  puts("ok");
+  return 0;
+}
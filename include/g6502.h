@@ -0,0 +1,100 @@
+#ifndef G6502_H
+#define G6502_H
+
+/* generated by cbindgen from src/ffi, don't edit */
+
+#include <stdbool.h>
+#include <stdint.h>
+#include <stddef.h>
+
+// what a call did, the errors are negative. the illegal opcode and stack errors only
+// come after vm_set_strict, by default the cpu runs through both
+typedef enum VmStatus {
+  VM_STATUS_OK = 0,
+  // vm_run stopped before the instruction at a breakpoint
+  VM_STATUS_BREAKPOINT = 1,
+  // the pc is left on the opcode
+  VM_STATUS_ILLEGAL_OPCODE = -1,
+  VM_STATUS_STACK_OVERFLOW = -2,
+  VM_STATUS_STACK_UNDERFLOW = -3,
+  // any other error
+  VM_STATUS_ERROR = -4,
+  // vm_load's data would run past $FFFF
+  VM_STATUS_OUT_OF_BOUNDS = -5,
+  // a null pointer where data was expected
+  VM_STATUS_NULL_POINTER = -6,
+} VmStatus;
+
+typedef struct Vm Vm;
+
+typedef struct VmRegs {
+  uint16_t pc;
+  uint8_t a;
+  uint8_t x;
+  uint8_t y;
+  uint8_t sp;
+  uint8_t status;
+  // cycles since vm_new, ignored by vm_set_regs
+  uint64_t cycles;
+} VmRegs;
+
+// a memory-mapped device implemented in C, addresses are relative to the start of the mapping.
+// `peek` is for debugger reads and mustn't have side effects, without it they see `read`
+typedef struct VmBusCallbacks {
+  uint8_t (*read)(void *user, uint16_t addr);
+  void (*write)(void *user, uint16_t addr, uint8_t data);
+  uint8_t (*peek)(void *user, uint16_t addr);
+  void *user;
+} VmBusCallbacks;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// 64K of RAM with the cpu held in reset, load a program and call vm_reset
+struct Vm *vm_new(void);
+
+void vm_free(struct Vm *vm);
+
+void vm_reset(struct Vm *vm);
+
+// a single clock cycle, VM_STATUS_OK or an error
+enum VmStatus vm_step(struct Vm *vm);
+
+// finishes the current instruction and runs the next one, VM_STATUS_OK or an error
+enum VmStatus vm_step_instruction(struct Vm *vm);
+
+// runs for up to `max_cycles`. VM_STATUS_BREAKPOINT writes the address to `breakpoint`,
+// which may be null, VM_STATUS_OK means the cycles ran out
+enum VmStatus vm_run(struct Vm *vm, uint64_t max_cycles, uint16_t *breakpoint);
+
+// `strict` stops the cpu with an error on illegal opcodes and the stack wrapping around,
+// otherwise they run as NOPs and wrap like the hardware
+void vm_set_strict(struct Vm *vm, bool strict);
+
+void vm_add_breakpoint(struct Vm *vm, uint16_t addr);
+
+void vm_remove_breakpoint(struct Vm *vm, uint16_t addr);
+
+void vm_get_regs(const struct Vm *vm, struct VmRegs *regs);
+
+void vm_set_regs(struct Vm *vm, const struct VmRegs *regs);
+
+// copies `len` bytes to `addr`, straight into RAM underneath any devices. data that
+// doesn't fit below $10000 isn't loaded at all and returns VM_STATUS_OUT_OF_BOUNDS
+enum VmStatus vm_load(struct Vm *vm, const uint8_t *data, uintptr_t len, uint16_t addr);
+
+// what the cpu would read, without side effects on devices
+uint8_t vm_read(const struct Vm *vm, uint16_t addr);
+
+// a write on the bus, devices see it like a cpu write
+void vm_write(struct Vm *vm, uint16_t addr, uint8_t data);
+
+// maps `len` bytes from `start` to C callbacks, like any device they take priority over RAM
+void vm_map_callbacks(struct Vm *vm, uint16_t start, uint32_t len, struct VmBusCallbacks callbacks);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* G6502_H */
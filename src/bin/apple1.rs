@@ -0,0 +1,124 @@
+use std::{
+  env, fs,
+  io::{self, Write},
+  process,
+  time::Duration,
+};
+
+use crossterm::{
+  event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+  terminal,
+};
+use g6502::vm::{machines::Apple1, Speed, Throttle};
+
+const USAGE: &str = "usage: apple1 <wozmon.rom> [--ram <KiB>] [--basic <basic.rom>]
+              [--speed <2x | 0.5x | unlimited>]";
+
+fn read_file(path: &str) -> Vec<u8> {
+  fs::read(path).unwrap_or_else(|err| {
+    eprintln!("can't read {}: {}", path, err);
+    process::exit(1);
+  })
+}
+
+fn fail(msg: &str) -> ! {
+  eprintln!("{}", msg);
+  process::exit(2);
+}
+
+fn main() -> io::Result<()> {
+  let mut args = env::args().skip(1);
+  let mut wozmon = None;
+  let mut basic = None;
+  let mut ram_kib = 8;
+  let mut speed = Speed::default();
+
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "--ram" => {
+        let kib = args.next().unwrap_or_else(|| fail(USAGE));
+        ram_kib = kib
+          .parse()
+          .unwrap_or_else(|_| fail(&format!("bad RAM size: {}", kib)));
+      }
+      "--basic" => basic = Some(args.next().unwrap_or_else(|| fail(USAGE))),
+      "--speed" => {
+        let parsed = args.next().unwrap_or_else(|| fail(USAGE));
+        speed = parsed.parse().unwrap_or_else(|err: String| fail(&err));
+      }
+      _ if wozmon.is_none() => wozmon = Some(arg),
+      _ => fail(USAGE),
+    }
+  }
+
+  let wozmon = read_file(&wozmon.unwrap_or_else(|| fail(USAGE)));
+
+  let mut apple1 = Apple1::new(ram_kib, &wozmon).unwrap_or_else(|err| {
+    eprintln!("{}", err);
+    process::exit(1);
+  });
+  if let Some(path) = basic {
+    if let Err(err) = apple1.load_basic(&read_file(&path)) {
+      eprintln!("{}: {}", path, err);
+      process::exit(1);
+    }
+  }
+
+  let mut throttle = Throttle::new(Apple1::CLOCK_HZ as f64)?;
+  throttle.set_speed(speed);
+
+  println!("Apple-1, ctrl+r resets, ctrl+c quits\r");
+  terminal::enable_raw_mode()?;
+  let result = run(&mut apple1, &mut throttle);
+  terminal::disable_raw_mode()?;
+  println!();
+  eprintln!("ran at {}", throttle);
+
+  result
+}
+
+fn run(apple1: &mut Apple1, throttle: &mut Throttle) -> io::Result<()> {
+  let mut stdout = io::stdout();
+
+  loop {
+    let cycles = throttle.slice();
+    for _ in 0..cycles {
+      apple1.step()?;
+    }
+
+    for ch in apple1.take_output() {
+      match ch {
+        b'\r' => stdout.write_all(b"\r\n")?,
+        0x20..=0x7E => stdout.write_all(&[ch])?,
+        _ => {}
+      }
+    }
+    stdout.flush()?;
+
+    while event::poll(Duration::ZERO)? {
+      let key = match event::read()? {
+        Event::Key(key) if key.kind != KeyEventKind::Release => key,
+        _ => continue,
+      };
+
+      if key.modifiers.contains(KeyModifiers::CONTROL) {
+        match key.code {
+          KeyCode::Char('c') | KeyCode::Char('d') => return Ok(()),
+          KeyCode::Char('r') => apple1.reset(),
+          _ => {}
+        }
+        continue;
+      }
+
+      match key.code {
+        KeyCode::Char(ch) if ch.is_ascii() => apple1.key_press(ch as u8),
+        KeyCode::Enter => apple1.key_press(b'\r'),
+        KeyCode::Backspace => apple1.key_press(0x08),
+        KeyCode::Esc => apple1.key_press(0x1B),
+        _ => {}
+      }
+    }
+
+    throttle.sync(cycles);
+  }
+}
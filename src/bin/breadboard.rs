@@ -0,0 +1,141 @@
+use std::{
+  env, fs,
+  io::{self, Write},
+  process,
+  time::Duration,
+};
+
+use crossterm::{
+  cursor,
+  event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+  queue,
+  style::{Attribute, Print, SetAttribute},
+  terminal::{self, ClearType},
+};
+use g6502::vm::{devices::Hd44780, machines::Breadboard, Speed, Throttle};
+
+const USAGE: &str = "usage: breadboard <rom.bin> [--speed <2x | 0.5x | unlimited>]";
+
+fn main() -> io::Result<()> {
+  let mut path = None;
+  let mut speed = Speed::default();
+  let mut args = env::args().skip(1);
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "--speed" => match args.next().map(|speed| speed.parse()) {
+        Some(Ok(parsed)) => speed = parsed,
+        Some(Err(err)) => {
+          eprintln!("{}", err);
+          process::exit(2);
+        }
+        None => path = None,
+      },
+      _ if path.is_none() => path = Some(arg),
+      _ => path = None,
+    }
+  }
+
+  let path = path.unwrap_or_else(|| {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+  });
+  let rom = fs::read(&path).unwrap_or_else(|err| {
+    eprintln!("can't read {}: {}", path, err);
+    process::exit(1);
+  });
+  let mut breadboard = Breadboard::new(&rom).unwrap_or_else(|err| {
+    eprintln!("{}: {}", path, err);
+    process::exit(1);
+  });
+
+  let mut throttle = Throttle::new(Breadboard::CLOCK_HZ as f64)?;
+  throttle.set_speed(speed);
+
+  let mut stdout = io::stdout();
+  terminal::enable_raw_mode()?;
+  queue!(stdout, terminal::EnterAlternateScreen, cursor::Hide)?;
+  let result = run(&mut breadboard, &mut throttle, &mut stdout);
+  queue!(stdout, cursor::Show, terminal::LeaveAlternateScreen)?;
+  stdout.flush()?;
+  terminal::disable_raw_mode()?;
+  eprintln!("ran at {}", throttle);
+
+  result
+}
+
+fn run(
+  breadboard: &mut Breadboard,
+  throttle: &mut Throttle,
+  stdout: &mut io::Stdout,
+) -> io::Result<()> {
+  let mut shown = None;
+
+  loop {
+    let cycles = throttle.slice();
+    for _ in 0..cycles {
+      breadboard.step()?;
+    }
+
+    let lcd = breadboard.lcd();
+    let frame = (lcd.lines(), lcd.cursor());
+    if shown.as_ref() != Some(&frame) {
+      draw(stdout, lcd)?;
+      shown = Some(frame);
+    }
+
+    while event::poll(Duration::ZERO)? {
+      let key = match event::read()? {
+        Event::Key(key) if key.kind != KeyEventKind::Release => key,
+        _ => continue,
+      };
+
+      match key.code {
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+        KeyCode::Char('q') => return Ok(()),
+        KeyCode::Char('r') => breadboard.reset(),
+        _ => {}
+      }
+    }
+
+    throttle.sync(cycles);
+  }
+}
+
+// the glass in a frame, the cursor position is underlined
+fn draw(stdout: &mut io::Stdout, lcd: &Hd44780) -> io::Result<()> {
+  let border = "─".repeat(lcd.cols() + 2);
+  queue!(
+    stdout,
+    terminal::Clear(ClearType::All),
+    cursor::MoveTo(0, 0),
+    Print(format!("┌{}┐", border)),
+  )?;
+
+  for (row, line) in lcd.lines().iter().enumerate() {
+    queue!(stdout, cursor::MoveTo(0, row as u16 + 1), Print("│ "))?;
+    for (col, ch) in line.chars().enumerate() {
+      if lcd.cursor() == Some((row, col)) {
+        queue!(
+          stdout,
+          SetAttribute(Attribute::Underlined),
+          Print(ch),
+          SetAttribute(Attribute::NoUnderline)
+        )?;
+      } else {
+        queue!(stdout, Print(ch))?;
+      }
+    }
+    queue!(stdout, Print(" │"))?;
+  }
+
+  let bottom = lcd.rows() as u16 + 1;
+  queue!(
+    stdout,
+    cursor::MoveTo(0, bottom),
+    Print(format!("└{}┘", border)),
+    cursor::MoveTo(0, bottom + 2),
+    Print("r resets, q quits"),
+  )?;
+
+  stdout.flush()
+}
@@ -0,0 +1,114 @@
+use std::{
+  env, fs,
+  io::{self, Read, Write},
+  process,
+  sync::mpsc::{self, Receiver},
+  thread,
+};
+
+use g6502::vm::machines::{petscii_to_ascii, C64};
+
+const CYCLES_PER_SLICE: usize = 10_000;
+
+const USAGE: &str = "usage: c64 <basic.rom> <kernal.rom> <chargen.rom> [program.prg] [--no-echo]";
+
+fn read_file(path: &str) -> Vec<u8> {
+  fs::read(path).unwrap_or_else(|err| {
+    eprintln!("can't read {}: {}", path, err);
+    process::exit(1);
+  })
+}
+
+// stdin on its own thread, so programs polling GETIN keep running
+fn spawn_input() -> Receiver<u8> {
+  let (tx, rx) = mpsc::channel();
+  thread::spawn(move || {
+    for byte in io::stdin().lock().bytes() {
+      match byte {
+        Ok(byte) if tx.send(byte).is_ok() => {}
+        _ => break,
+      }
+    }
+  });
+
+  rx
+}
+
+// runs headless, screen output goes to stdout and stdin is typed in, it exits once
+// BASIC wants a line and stdin is at its end
+fn main() -> io::Result<()> {
+  let mut paths = Vec::new();
+  let mut echo = true;
+  for arg in env::args().skip(1) {
+    match arg.as_str() {
+      "--no-echo" => echo = false,
+      _ => paths.push(arg),
+    }
+  }
+
+  if !(3..=4).contains(&paths.len()) {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+  }
+
+  let roms: Vec<Vec<u8>> = paths[..3].iter().map(|path| read_file(path)).collect();
+  let mut prg = paths.get(3).map(|path| read_file(path));
+
+  let mut c64 = C64::new(&roms[0], &roms[1], &roms[2]).unwrap_or_else(|err| {
+    eprintln!(
+      "{}, they should be 8K BASIC, 8K KERNAL and 4K character ROM",
+      err
+    );
+    process::exit(1);
+  });
+  c64.set_echo(echo);
+  let input = spawn_input();
+  let mut stdout = io::stdout();
+
+  loop {
+    for _ in 0..CYCLES_PER_SLICE {
+      c64.step()?;
+      if c64.waiting_for_input() {
+        break;
+      }
+    }
+
+    let output: String = c64
+      .take_output()
+      .into_iter()
+      .filter_map(petscii_to_ascii)
+      .collect();
+    stdout.write_all(output.as_bytes())?;
+    stdout.flush()?;
+
+    let mut typed = false;
+    while let Ok(key) = input.try_recv() {
+      c64.key_press(key);
+      typed = true;
+    }
+
+    if typed || !c64.waiting_for_input() {
+      continue;
+    }
+
+    // BASIC is up, load the program and run it
+    if let Some(data) = prg.take() {
+      if let Err(err) = c64.load_prg(&data) {
+        eprintln!("{}: {}", paths[3], err);
+        process::exit(1);
+      }
+      for &key in b"RUN\r" {
+        c64.key_press(key);
+      }
+      continue;
+    }
+
+    match input.recv() {
+      Ok(key) => c64.key_press(key),
+      Err(_) => break,
+    }
+  }
+
+  println!();
+  Ok(())
+}
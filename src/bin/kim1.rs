@@ -0,0 +1,206 @@
+use std::{
+  env, fs,
+  io::{self, Write},
+  process,
+  time::Duration,
+};
+
+use crossterm::{
+  cursor,
+  event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+  queue,
+  style::Print,
+  terminal::{self, ClearType},
+};
+use g6502::vm::{
+  machines::{Kim1, KEY_AD, KEY_DA, KEY_GO, KEY_PC, KEY_PLUS},
+  Speed, Throttle,
+};
+
+const USAGE: &str = "usage: kim1 <monitor.rom> [--tty] [--speed <2x | 0.5x | unlimited>]";
+
+const HELP: &str = "0-f hex, ^A AD, ^D DA, + or space +, ^G GO, ^P PC, \
+^T ST, ^R RS, ^S SST, esc quits";
+
+fn main() -> io::Result<()> {
+  let mut path = None;
+  let mut tty = false;
+  let mut speed = Speed::default();
+  let mut args = env::args().skip(1);
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "--tty" => tty = true,
+      "--speed" => match args.next().map(|speed| speed.parse()) {
+        Some(Ok(parsed)) => speed = parsed,
+        Some(Err(err)) => {
+          eprintln!("{}", err);
+          process::exit(2);
+        }
+        None => path = None,
+      },
+      _ if path.is_none() => path = Some(arg),
+      _ => path = None,
+    }
+  }
+
+  let path = path.unwrap_or_else(|| {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+  });
+  let rom = fs::read(&path).unwrap_or_else(|err| {
+    eprintln!("can't read {}: {}", path, err);
+    process::exit(1);
+  });
+  let mut kim1 = Kim1::new(&rom, tty).unwrap_or_else(|err| {
+    eprintln!("{}: {}", path, err);
+    process::exit(1);
+  });
+  let mut throttle = Throttle::new(Kim1::CLOCK_HZ as f64)?;
+  throttle.set_speed(speed);
+  let mut stdout = io::stdout();
+
+  terminal::enable_raw_mode()?;
+  let result = if tty {
+    println!("KIM-1 TTY, ^T ST, ^R RS, ^C quits\r");
+    run_tty(&mut kim1, &mut throttle, &mut stdout)
+  } else {
+    queue!(stdout, terminal::EnterAlternateScreen, cursor::Hide)?;
+    let result = run_keypad(&mut kim1, &mut throttle, &mut stdout);
+    queue!(stdout, cursor::Show, terminal::LeaveAlternateScreen)?;
+    stdout.flush()?;
+    result
+  };
+  terminal::disable_raw_mode()?;
+  println!();
+  eprintln!("ran at {}", throttle);
+
+  result
+}
+
+// a batch of cycles and the sleep that keeps them to the clock
+fn run_slice(kim1: &mut Kim1, throttle: &mut Throttle) -> io::Result<()> {
+  let cycles = throttle.slice();
+  for _ in 0..cycles {
+    kim1.step()?;
+  }
+  throttle.sync(cycles);
+  Ok(())
+}
+
+// keys without a byte to send
+fn front_panel(kim1: &mut Kim1, key: &KeyEvent) -> bool {
+  if !key.modifiers.contains(KeyModifiers::CONTROL) {
+    return false;
+  }
+
+  match key.code {
+    KeyCode::Char('t') => kim1.stop(),
+    KeyCode::Char('r') => kim1.reset(),
+    KeyCode::Char('s') => kim1.set_single_step(!kim1.single_step()),
+    _ => return false,
+  }
+
+  true
+}
+
+fn key_event() -> io::Result<Option<KeyEvent>> {
+  if !event::poll(Duration::ZERO)? {
+    return Ok(None);
+  }
+
+  match event::read()? {
+    Event::Key(key) if key.kind != KeyEventKind::Release => Ok(Some(key)),
+    _ => key_event(),
+  }
+}
+
+fn run_keypad(kim1: &mut Kim1, throttle: &mut Throttle, stdout: &mut io::Stdout) -> io::Result<()> {
+  let mut shown = None;
+
+  loop {
+    run_slice(kim1, throttle)?;
+
+    let frame = (kim1.display(), kim1.single_step());
+    if shown != Some(frame) {
+      draw(stdout, frame.0, frame.1)?;
+      shown = Some(frame);
+    }
+
+    while let Some(key) = key_event()? {
+      if front_panel(kim1, &key) {
+        continue;
+      }
+
+      let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+      let pad = match key.code {
+        KeyCode::Esc => return Ok(()),
+        KeyCode::Char('c') if ctrl => return Ok(()),
+        KeyCode::Char('a') if ctrl => KEY_AD,
+        KeyCode::Char('d') if ctrl => KEY_DA,
+        KeyCode::Char('g') if ctrl => KEY_GO,
+        KeyCode::Char('p') if ctrl => KEY_PC,
+        KeyCode::Char('+') | KeyCode::Char(' ') => KEY_PLUS,
+        KeyCode::Char(ch) if ch.is_ascii_hexdigit() => ch.to_digit(16).unwrap_or(0) as u8,
+        _ => continue,
+      };
+      kim1.key_press(pad);
+    }
+  }
+}
+
+fn run_tty(kim1: &mut Kim1, throttle: &mut Throttle, stdout: &mut io::Stdout) -> io::Result<()> {
+  loop {
+    run_slice(kim1, throttle)?;
+
+    let output: Vec<u8> = kim1
+      .take_output()
+      .into_iter()
+      .filter(|ch| matches!(ch, b'\r' | b'\n' | 0x20..=0x7E))
+      .collect();
+    stdout.write_all(&output)?;
+    stdout.flush()?;
+
+    while let Some(key) = key_event()? {
+      if front_panel(kim1, &key) {
+        continue;
+      }
+
+      match key.code {
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+        KeyCode::Char(ch) if ch.is_ascii() => kim1.tty_send(ch.to_ascii_uppercase() as u8),
+        KeyCode::Enter => kim1.tty_send(b'\r'),
+        KeyCode::Backspace => kim1.tty_send(0x7F),
+        _ => {}
+      }
+    }
+  }
+}
+
+// each digit is three rows of a seven segment display
+fn draw(stdout: &mut io::Stdout, display: [u8; 6], single_step: bool) -> io::Result<()> {
+  let mut rows = [String::new(), String::new(), String::new()];
+
+  for (i, segments) in display.iter().enumerate() {
+    let lit = |bit: u8, ch: char| if segments & (1 << bit) != 0 { ch } else { ' ' };
+    // a gap between the address and data digits
+    let gap = if i == 4 { "   " } else { " " };
+
+    rows[0] += &format!("{} {} ", gap, lit(0, '_'));
+    rows[1] += &format!("{}{}{}{}", gap, lit(5, '|'), lit(6, '_'), lit(1, '|'));
+    rows[2] += &format!("{}{}{}{}", gap, lit(4, '|'), lit(3, '_'), lit(2, '|'));
+  }
+
+  queue!(stdout, terminal::Clear(ClearType::All))?;
+  for (y, row) in rows.iter().enumerate() {
+    queue!(stdout, cursor::MoveTo(0, y as u16 + 1), Print(row))?;
+  }
+  queue!(
+    stdout,
+    cursor::MoveTo(0, 5),
+    Print(if single_step { "SST on" } else { "SST off" }),
+    cursor::MoveTo(0, 7),
+    Print(HELP),
+  )?;
+
+  stdout.flush()
+}
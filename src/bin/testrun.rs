@@ -0,0 +1,210 @@
+use std::{
+  env, fs,
+  io::{self, Write},
+  process,
+};
+
+use g6502::vm::{
+  machines::{TestMachine, TestReport},
+  trace_line, Access, IllegalOpcodePolicy, StackPolicy, Symbols,
+};
+
+const USAGE: &str = "usage: testrun <program> [--org ADDR | --prg] [--max-cycles N] [--input FILE]
+               [--trace] [--symbols FILE]... [--profile FILE] [--folded FILE]
+               [--lcov FILE] [--strict] [--illegal undocumented|nop|halt|error]
+
+runs a 6502 test program on the headless test machine, the exit status is the program's,
+1 when an assertion failed, 3 when the cpu halted or --strict stopped it on an illegal
+opcode or the stack wrapping around and 124 when it ran out of cycles.
+
+  --org ADDR        load the program at ADDR and start there, $0200 by default
+  --prg             take the load address from the program's first two bytes
+  --max-cycles N    give up after N cycles, 100000000 by default
+  --input FILE      bytes the program reads from the host input register
+  --trace           log every instruction to stderr, labelled from the symbol files
+  --symbols FILE    ld65 debug info, VICE labels or label = $addr lines
+  --profile FILE    write the hot spots and subroutine timings
+  --folded FILE     write the call stacks for flamegraph.pl or inferno
+  --lcov FILE       write the source lines that ran, read or wrote memory, which
+                    needs ld65 debug info from --symbols
+  --strict          stop on illegal opcodes and the stack wrapping around
+  --illegal POLICY  run illegal opcodes as undocumented, nop, halt or error, nop by default";
+
+// rows in each section of the profile
+const PROFILE_ROWS: usize = 40;
+
+fn fail(msg: &str) -> ! {
+  eprintln!("{}", msg);
+  process::exit(2);
+}
+
+fn read_file(path: &str) -> Vec<u8> {
+  fs::read(path).unwrap_or_else(|err| fail(&format!("can't read {}: {}", path, err)))
+}
+
+fn write_file(path: &str, data: &[u8]) {
+  fs::write(path, data).unwrap_or_else(|err| fail(&format!("can't write {}: {}", path, err)))
+}
+
+// $1234, 0x1234 or plain decimal
+fn parse_number(arg: Option<String>) -> u64 {
+  let arg = arg.unwrap_or_else(|| fail(USAGE));
+  let parsed = match arg.strip_prefix('$').or_else(|| arg.strip_prefix("0x")) {
+    Some(hex) => u64::from_str_radix(hex, 16),
+    None => arg.parse(),
+  };
+  parsed.unwrap_or_else(|_| fail(&format!("bad number: {}", arg)))
+}
+
+fn main() -> io::Result<()> {
+  let mut args = env::args().skip(1);
+  let mut path = None;
+  let mut org = Some(0x0200);
+  let mut max_cycles = 100_000_000;
+  let mut input = Vec::new();
+  let mut trace = false;
+  let mut profile = None;
+  let mut folded = None;
+  let mut lcov = None;
+  let mut strict = false;
+  let mut illegal = None;
+  let mut symbols = Symbols::new();
+
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "--org" => org = Some(parse_number(args.next())),
+      "--prg" => org = None,
+      "--max-cycles" => max_cycles = parse_number(args.next()),
+      "--input" => input = read_file(&args.next().unwrap_or_else(|| fail(USAGE))),
+      "--trace" => trace = true,
+      "--profile" => profile = Some(args.next().unwrap_or_else(|| fail(USAGE))),
+      "--folded" => folded = Some(args.next().unwrap_or_else(|| fail(USAGE))),
+      "--lcov" => lcov = Some(args.next().unwrap_or_else(|| fail(USAGE))),
+      "--strict" => strict = true,
+      "--illegal" => {
+        let policy = args.next().unwrap_or_else(|| fail(USAGE));
+        illegal = Some(policy.parse().unwrap_or_else(|err: String| fail(&err)));
+      }
+      "--symbols" => {
+        let path = args.next().unwrap_or_else(|| fail(USAGE));
+        let text = String::from_utf8_lossy(&read_file(&path)).into_owned();
+        match Symbols::parse(&text) {
+          Ok(parsed) => symbols.merge(&parsed),
+          Err(err) => fail(&format!("{}: {}", path, err)),
+        }
+      }
+      _ if path.is_none() => path = Some(arg),
+      _ => fail(USAGE),
+    }
+  }
+
+  let program = read_file(&path.unwrap_or_else(|| fail(USAGE)));
+  let (program, org) = match org {
+    Some(org) => (&program[..], org),
+    // the load address is in the first two bytes
+    None if program.len() >= 2 => (&program[2..], program[0] as u64 | (program[1] as u64) << 8),
+    None => fail("PRG file is missing its load address"),
+  };
+  if org > 0xFFFF {
+    fail("program doesn't fit in memory");
+  }
+
+  let mut machine = TestMachine::new();
+  if let Err(err) = machine.load(program, org as u16) {
+    fail(&err.to_string());
+  }
+  machine.input(&input);
+  if strict {
+    machine.cpu.on_illegal_opcode = IllegalOpcodePolicy::Error;
+    machine.cpu.on_stack_wrap = StackPolicy::Error;
+  }
+  if let Some(policy) = illegal {
+    machine.cpu.on_illegal_opcode = policy;
+  }
+  if profile.is_some() || folded.is_some() {
+    machine.cpu.start_profiling();
+  }
+  if lcov.is_some() {
+    machine.cpu.mem.start_coverage();
+  }
+  let report = if trace {
+    let mut error = None;
+    while !machine.exited() && !machine.cpu.halted() && machine.cycles() < max_cycles {
+      // at an instruction boundary
+      if machine.cpu.cycles == 0 {
+        eprintln!(
+          "{}",
+          trace_line(&machine.cpu, machine.cycles(), Some(&symbols))
+        );
+      }
+      if let Err(err) = machine.step() {
+        error = Some(err);
+        break;
+      }
+    }
+    TestReport {
+      error,
+      ..machine.report()
+    }
+  } else {
+    machine.run(max_cycles)
+  };
+
+  if let Some(profiler) = machine.cpu.profiler() {
+    if let Some(path) = &profile {
+      let report = profiler.report(&machine.cpu.mem, Some(&symbols), PROFILE_ROWS);
+      write_file(path, report.as_bytes());
+    }
+    if let Some(path) = &folded {
+      write_file(path, profiler.folded(Some(&symbols)).as_bytes());
+    }
+  }
+
+  if let (Some(coverage), Some(path)) = (machine.cpu.mem.coverage(), &lcov) {
+    if symbols.lines().is_empty() {
+      eprintln!("no source lines in the symbols, {} will be empty", path);
+    }
+    write_file(path, coverage.lcov(&symbols, "testrun").as_bytes());
+    eprintln!(
+      "coverage: {} opcodes, {} operands, {} bytes read, {} written",
+      coverage.count(Access::OPCODE),
+      coverage.count(Access::OPERAND),
+      coverage.count(Access::READ),
+      coverage.count(Access::WRITTEN)
+    );
+  }
+
+  let mut stdout = io::stdout();
+  stdout.write_all(&report.output)?;
+  stdout.flush()?;
+
+  for assertion in &report.assertions {
+    let result = if assertion.passed { "ok" } else { "FAILED" };
+    eprintln!("{}: {}", assertion.name, result);
+  }
+  let failed = report.failures().count();
+  eprintln!(
+    "{} assertions, {} failed, {} cycles",
+    report.assertions.len(),
+    failed,
+    report.cycles
+  );
+
+  let status = match (report.exit, &report.error) {
+    (None, Some(err)) => {
+      eprintln!("stopped: {}", err);
+      3
+    }
+    (None, None) if report.halted => {
+      eprintln!("halted at ${:04X}", machine.cpu.pc);
+      3
+    }
+    (None, None) => {
+      eprintln!("didn't exit after {} cycles", max_cycles);
+      124
+    }
+    (Some(0), _) if failed > 0 => 1,
+    (Some(status), _) => status as i32,
+  };
+  process::exit(status);
+}
@@ -0,0 +1,236 @@
+// every function takes a vm from vm_new and must not be called with a freed one,
+// the header is generated from this file with cbindgen
+#![allow(clippy::missing_safety_doc)]
+
+use std::{cell::RefCell, ffi::c_void, rc::Rc, slice};
+
+use crate::vm::{
+  devices::Device, Byte, CpuStatus, Error, IllegalOpcodePolicy, StackPolicy, Vm, Word,
+};
+
+mod test;
+
+/// what a call did, the errors are negative. the illegal opcode and stack errors only
+/// come after vm_set_strict, by default the cpu runs through both
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VmStatus {
+  Ok = 0,
+  /// vm_run stopped before the instruction at a breakpoint
+  Breakpoint = 1,
+  /// the pc is left on the opcode
+  IllegalOpcode = -1,
+  StackOverflow = -2,
+  StackUnderflow = -3,
+  /// any other error
+  Error = -4,
+  /// vm_load's data would run past $FFFF
+  OutOfBounds = -5,
+  /// a null pointer where data was expected
+  NullPointer = -6,
+}
+
+impl From<Result<(), Error>> for VmStatus {
+  fn from(result: Result<(), Error>) -> Self {
+    match result {
+      Ok(()) => VmStatus::Ok,
+      Err(Error::IllegalOpcode { .. }) => VmStatus::IllegalOpcode,
+      Err(Error::StackOverflow { .. }) => VmStatus::StackOverflow,
+      Err(Error::StackUnderflow { .. }) => VmStatus::StackUnderflow,
+      Err(Error::OutOfBounds { .. }) => VmStatus::OutOfBounds,
+      Err(_) => VmStatus::Error,
+    }
+  }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VmRegs {
+  pub pc: u16,
+  pub a: u8,
+  pub x: u8,
+  pub y: u8,
+  pub sp: u8,
+  pub status: u8,
+  /// cycles since vm_new, ignored by vm_set_regs
+  pub cycles: u64,
+}
+
+/// a memory-mapped device implemented in C, addresses are relative to the start of the mapping.
+/// `peek` is for debugger reads and mustn't have side effects, without it they see `read`
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct VmBusCallbacks {
+  pub read: Option<unsafe extern "C" fn(user: *mut c_void, addr: u16) -> u8>,
+  pub write: Option<unsafe extern "C" fn(user: *mut c_void, addr: u16, data: u8)>,
+  pub peek: Option<unsafe extern "C" fn(user: *mut c_void, addr: u16) -> u8>,
+  pub user: *mut c_void,
+}
+
+struct CallbackDevice {
+  callbacks: VmBusCallbacks,
+}
+
+impl Device for CallbackDevice {
+  fn read(&mut self, addr: Word) -> Byte {
+    match self.callbacks.read {
+      Some(read) => unsafe { read(self.callbacks.user, addr) },
+      None => 0xFF,
+    }
+  }
+
+  fn write(&mut self, addr: Word, data: Byte) {
+    if let Some(write) = self.callbacks.write {
+      unsafe { write(self.callbacks.user, addr, data) }
+    }
+  }
+
+  fn peek(&self, addr: Word) -> Byte {
+    match self.callbacks.peek.or(self.callbacks.read) {
+      Some(peek) => unsafe { peek(self.callbacks.user, addr) },
+      None => 0xFF,
+    }
+  }
+}
+
+/* ------- lifetime -------- */
+/// 64K of RAM with the cpu held in reset, load a program and call vm_reset
+#[no_mangle]
+pub extern "C" fn vm_new() -> *mut Vm {
+  Box::into_raw(Box::new(Vm::new()))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn vm_free(vm: *mut Vm) {
+  if !vm.is_null() {
+    drop(Box::from_raw(vm));
+  }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn vm_reset(vm: *mut Vm) {
+  (*vm).reset();
+}
+/* ------- lifetime -------- */
+
+/* ------- execution -------- */
+/// a single clock cycle, VM_STATUS_OK or an error
+#[no_mangle]
+pub unsafe extern "C" fn vm_step(vm: *mut Vm) -> VmStatus {
+  (*vm).step().into()
+}
+
+/// finishes the current instruction and runs the next one, VM_STATUS_OK or an error
+#[no_mangle]
+pub unsafe extern "C" fn vm_step_instruction(vm: *mut Vm) -> VmStatus {
+  (*vm).step_instruction().into()
+}
+
+/// runs for up to `max_cycles`. VM_STATUS_BREAKPOINT writes the address to `breakpoint`,
+/// which may be null, VM_STATUS_OK means the cycles ran out
+#[no_mangle]
+pub unsafe extern "C" fn vm_run(vm: *mut Vm, max_cycles: u64, breakpoint: *mut u16) -> VmStatus {
+  match (*vm).run(max_cycles) {
+    Ok(Some(addr)) => {
+      if !breakpoint.is_null() {
+        *breakpoint = addr;
+      }
+      VmStatus::Breakpoint
+    }
+    Ok(None) => VmStatus::Ok,
+    Err(err) => Err(err).into(),
+  }
+}
+
+/// `strict` stops the cpu with an error on illegal opcodes and the stack wrapping around,
+/// otherwise they run as NOPs and wrap like the hardware
+#[no_mangle]
+pub unsafe extern "C" fn vm_set_strict(vm: *mut Vm, strict: bool) {
+  let cpu = &mut (*vm).cpu;
+  (cpu.on_illegal_opcode, cpu.on_stack_wrap) = if strict {
+    (IllegalOpcodePolicy::Error, StackPolicy::Error)
+  } else {
+    (IllegalOpcodePolicy::default(), StackPolicy::default())
+  };
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn vm_add_breakpoint(vm: *mut Vm, addr: u16) {
+  (*vm).add_breakpoint(addr);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn vm_remove_breakpoint(vm: *mut Vm, addr: u16) {
+  (*vm).remove_breakpoint(addr);
+}
+/* ------- execution -------- */
+
+/* ------- registers -------- */
+#[no_mangle]
+pub unsafe extern "C" fn vm_get_regs(vm: *const Vm, regs: *mut VmRegs) {
+  let vm = &*vm;
+  *regs = VmRegs {
+    pc: vm.cpu.pc,
+    a: vm.cpu.reg_a,
+    x: vm.cpu.reg_x,
+    y: vm.cpu.reg_y,
+    sp: vm.cpu.sp,
+    status: vm.cpu.status.bits(),
+    cycles: vm.cycles(),
+  };
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn vm_set_regs(vm: *mut Vm, regs: *const VmRegs) {
+  let (cpu, regs) = (&mut (*vm).cpu, &*regs);
+  cpu.pc = regs.pc;
+  cpu.reg_a = regs.a;
+  cpu.reg_x = regs.x;
+  cpu.reg_y = regs.y;
+  cpu.sp = regs.sp;
+  cpu.status = CpuStatus::from_bits_truncate(regs.status);
+}
+/* ------- registers -------- */
+
+/* ------- memory -------- */
+/// copies `len` bytes to `addr`, straight into RAM underneath any devices. data that
+/// doesn't fit below $10000 isn't loaded at all and returns VM_STATUS_OUT_OF_BOUNDS
+#[no_mangle]
+pub unsafe extern "C" fn vm_load(vm: *mut Vm, data: *const u8, len: usize, addr: u16) -> VmStatus {
+  if len == 0 {
+    return VmStatus::Ok;
+  }
+  if data.is_null() {
+    return VmStatus::NullPointer;
+  }
+  // before the slice, a bogus len mustn't make one
+  if len > 0x10000 - addr as usize {
+    return VmStatus::OutOfBounds;
+  }
+  (*vm).load(slice::from_raw_parts(data, len), addr).into()
+}
+
+/// what the cpu would read, without side effects on devices
+#[no_mangle]
+pub unsafe extern "C" fn vm_read(vm: *const Vm, addr: u16) -> u8 {
+  (*vm).cpu.mem.peek(addr)
+}
+
+/// a write on the bus, devices see it like a cpu write
+#[no_mangle]
+pub unsafe extern "C" fn vm_write(vm: *mut Vm, addr: u16, data: u8) {
+  (*vm).cpu.mem.write(addr, data);
+}
+
+/// maps `len` bytes from `start` to C callbacks, like any device they take priority over RAM
+#[no_mangle]
+pub unsafe extern "C" fn vm_map_callbacks(
+  vm: *mut Vm,
+  start: u16,
+  len: u32,
+  callbacks: VmBusCallbacks,
+) {
+  let device = Rc::new(RefCell::new(CallbackDevice { callbacks }));
+  (*vm).cpu.mem.map_device(start, len as usize, device);
+}
+/* ------- memory -------- */
@@ -0,0 +1,180 @@
+#[cfg(test)]
+mod tests {
+  use std::{ffi::c_void, ptr};
+
+  use crate::ffi::*;
+
+  unsafe extern "C" fn record_write(user: *mut c_void, addr: u16, data: u8) {
+    (*(user as *mut Vec<(u16, u8)>)).push((addr, data));
+  }
+
+  unsafe extern "C" fn read_status(_user: *mut c_void, addr: u16) -> u8 {
+    0x80 | addr as u8
+  }
+
+  #[test]
+  fn c_api_drives_the_vm() {
+    let mut writes: Vec<(u16, u8)> = Vec::new();
+    // LDA $D011 ; STA $D020 ; loop: JMP loop
+    let program = [0xAD, 0x11, 0xD0, 0x8D, 0x20, 0xD0, 0x4C, 0x06, 0x02];
+
+    unsafe {
+      let vm = vm_new();
+      vm_load(vm, program.as_ptr(), program.len(), 0x0200);
+      vm_load(vm, [0x00, 0x02].as_ptr(), 2, 0xFFFC);
+      vm_map_callbacks(
+        vm,
+        0xD000,
+        0x100,
+        VmBusCallbacks {
+          read: Some(read_status),
+          write: Some(record_write),
+          peek: None,
+          user: &mut writes as *mut _ as *mut c_void,
+        },
+      );
+      vm_reset(vm);
+
+      vm_add_breakpoint(vm, 0x0206);
+      let mut stopped = 0;
+      assert_eq!(
+        vm_run(vm, 1000, &mut stopped),
+        VmStatus::Breakpoint,
+        "didn't stop at the breakpoint"
+      );
+      assert_eq!(stopped, 0x0206);
+
+      let mut regs = VmRegs::default();
+      vm_get_regs(vm, &mut regs);
+      assert_eq!(regs.pc, 0x0206);
+      assert_eq!(regs.a, 0x91, "LDA didn't read through the callback");
+
+      regs.x = 0x42;
+      vm_set_regs(vm, &regs);
+      vm_write(vm, 0x0010, 0x55);
+      assert_eq!(vm_read(vm, 0x0010), 0x55);
+      assert_eq!(vm_read(vm, 0xD005), 0x85, "peek didn't fall back to read");
+
+      vm_remove_breakpoint(vm, 0x0206);
+      assert_eq!(
+        vm_run(vm, 100, ptr::null_mut()),
+        VmStatus::Ok,
+        "stopped at a removed breakpoint"
+      );
+      vm_get_regs(vm, &mut regs);
+      assert_eq!(regs.x, 0x42, "vm_set_regs didn't stick");
+
+      vm_free(vm);
+    }
+
+    assert_eq!(
+      writes,
+      [(0x20, 0x91)],
+      "STA didn't reach the write callback"
+    );
+  }
+
+  // the stack pointer at $FF, so the next pull wraps
+  unsafe fn empty_stack(vm: *mut Vm) {
+    let mut regs = VmRegs::default();
+    vm_get_regs(vm, &mut regs);
+    regs.sp = 0xFF;
+    vm_set_regs(vm, &regs);
+  }
+
+  #[test]
+  fn c_api_reports_errors() {
+    // PLA ; JAM
+    let program = [0x68, 0x02];
+
+    unsafe {
+      let vm = vm_new();
+      vm_load(vm, program.as_ptr(), program.len(), 0x0200);
+      vm_load(vm, [0x00, 0x02].as_ptr(), 2, 0xFFFC);
+      vm_reset(vm);
+      empty_stack(vm);
+      assert_eq!(
+        vm_step_instruction(vm),
+        VmStatus::Ok,
+        "the stack wrapping stopped the cpu without vm_set_strict"
+      );
+
+      vm_reset(vm);
+      vm_set_strict(vm, true);
+      empty_stack(vm);
+      assert_eq!(
+        vm_step_instruction(vm),
+        VmStatus::StackUnderflow,
+        "pulling from an empty stack wasn't reported"
+      );
+      assert_eq!(
+        vm_step_instruction(vm),
+        VmStatus::IllegalOpcode,
+        "the JAM wasn't reported"
+      );
+      assert_eq!(
+        vm_step(vm),
+        VmStatus::IllegalOpcode,
+        "vm_step didn't report the error"
+      );
+      assert_eq!(
+        vm_run(vm, 100, ptr::null_mut()),
+        VmStatus::IllegalOpcode,
+        "vm_run didn't report the error"
+      );
+
+      vm_free(vm);
+    }
+  }
+
+  #[test]
+  fn c_api_rejects_bad_loads() {
+    let data = [0x11, 0x22, 0x33];
+
+    unsafe {
+      let vm = vm_new();
+      assert_eq!(
+        vm_load(vm, data.as_ptr(), data.len(), 0xFFFD),
+        VmStatus::Ok,
+        "data that just fits wasn't loaded"
+      );
+      assert_eq!(vm_read(vm, 0xFFFF), 0x33);
+
+      assert_eq!(
+        vm_load(vm, [0x44; 3].as_ptr(), 3, 0xFFFE),
+        VmStatus::OutOfBounds,
+        "data past $FFFF was loaded"
+      );
+      assert_eq!(vm_read(vm, 0xFFFE), 0x22, "a rejected load was cut off");
+      assert_eq!(
+        vm_load(vm, data.as_ptr(), usize::MAX, 0x0000),
+        VmStatus::OutOfBounds,
+        "a huge len was loaded"
+      );
+
+      assert_eq!(
+        vm_load(vm, ptr::null(), 3, 0x0200),
+        VmStatus::NullPointer,
+        "null data was loaded"
+      );
+      assert_eq!(
+        vm_load(vm, ptr::null(), 0, 0x0200),
+        VmStatus::Ok,
+        "loading nothing failed"
+      );
+
+      vm_free(vm);
+    }
+  }
+
+  #[test]
+  fn header_is_current() {
+    let generated = include_str!(concat!(env!("OUT_DIR"), "/g6502.h"));
+    let checked_in = include_str!("../../include/g6502.h");
+    assert!(
+      generated == checked_in,
+      "include/g6502.h is stale, regenerate it with \
+       `cbindgen --config cbindgen.toml --output include/g6502.h`"
+    );
+  }
+}
@@ -1,3 +1,16 @@
 #![warn(clippy::all, rust_2018_idioms)]
+#![cfg_attr(not(feature = "std"), no_std)]
 
-mod vm;
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+pub mod vm;
+
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "script")]
+pub mod script;
+#[cfg(feature = "wasm")]
+pub mod wasm;
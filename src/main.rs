@@ -0,0 +1,582 @@
+use std::{
+  collections::VecDeque,
+  env, fs,
+  io::{self, Write},
+  process,
+  time::Duration,
+};
+
+use crossterm::{
+  cursor,
+  event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+  queue,
+  style::{Attribute, Color, Print, ResetColor, SetAttribute, SetForegroundColor},
+  terminal::{self, ClearType},
+};
+#[cfg(feature = "script")]
+use g6502::script::Scripts;
+use g6502::vm::{
+  disassemble, disassemble_with, CpuStatus, Error, IllegalOpcodePolicy, Symbols, Vm, Word,
+};
+
+mod test;
+
+// 1MHz at 60 frames a second
+const CYCLES_PER_FRAME: u64 = 16_667;
+const FRAME: Duration = Duration::from_millis(16);
+
+const DISASM_LINES: usize = 16;
+const MEMORY_LINES: usize = 8;
+const STACK_LINES: usize = 7;
+// instructions kept above the pc after stepping
+const HISTORY: usize = 4;
+
+const USAGE: &str = "usage: g6502 [program] [--org ADDR | --prg] [--symbols FILE]...
+             [--illegal undocumented|nop|halt|error]";
+const HELP: &str =
+  "s step  r run/pause  b break at pc  : command  ↑↓ PgUp PgDn memory  x reset  q quit";
+
+fn parse_addr(text: &str) -> Option<Word> {
+  let text = text.trim_start_matches('$').trim_start_matches("0x");
+  Word::from_str_radix(text, 16).ok()
+}
+
+fn main() -> io::Result<()> {
+  let mut args = env::args().skip(1);
+  let mut program = None;
+  let mut org = Some(0x0200);
+  let mut symbols = Symbols::new();
+  let mut illegal = IllegalOpcodePolicy::default();
+
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "--org" => match args.next().as_deref().and_then(parse_addr) {
+        Some(addr) => org = Some(addr),
+        None => usage(),
+      },
+      // the first two bytes are the load address
+      "--prg" => org = None,
+      // ca65 debug info, VICE labels or label = $addr lines
+      "--symbols" => match args.next() {
+        Some(path) => symbols.merge(&load_symbols(&path)),
+        None => usage(),
+      },
+      // what illegal opcodes do
+      "--illegal" => match args.next().map(|policy| policy.parse()) {
+        Some(Ok(policy)) => illegal = policy,
+        _ => usage(),
+      },
+      _ if program.is_none() => program = Some(arg),
+      _ => usage(),
+    }
+  }
+
+  let mut vm = Vm::new();
+  vm.cpu.on_illegal_opcode = illegal;
+  if let Some(path) = program {
+    let mut data = fs::read(&path).unwrap_or_else(|err| {
+      eprintln!("can't read {}: {}", path, err);
+      process::exit(1);
+    });
+    let addr = match org {
+      Some(addr) => addr,
+      None if data.len() >= 2 => {
+        let addr = data[0] as Word | (data[1] as Word) << 8;
+        data.drain(..2);
+        addr
+      }
+      None => usage(),
+    };
+    if let Err(err) = vm.load(&data, addr) {
+      eprintln!("{}: {}", path, err);
+      process::exit(1);
+    }
+    // images that don't bring their own vectors start where they were loaded
+    if vm.cpu.mem.peek(0xFFFC) == 0 && vm.cpu.mem.peek(0xFFFD) == 0 {
+      vm.load(&[addr as u8, (addr >> 8) as u8], 0xFFFC)?;
+    }
+  }
+  vm.reset();
+  vm.cpu.mem.set_access_counting(true);
+
+  let mut stdout = io::stdout();
+  terminal::enable_raw_mode()?;
+  queue!(stdout, terminal::EnterAlternateScreen, cursor::Hide)?;
+  let result = Monitor::new(vm, symbols).run(&mut stdout);
+  queue!(stdout, cursor::Show, terminal::LeaveAlternateScreen)?;
+  stdout.flush()?;
+  terminal::disable_raw_mode()?;
+
+  result
+}
+
+fn load_symbols(path: &str) -> Symbols {
+  let text = fs::read_to_string(path).unwrap_or_else(|err| {
+    eprintln!("can't read {}: {}", path, err);
+    process::exit(1);
+  });
+  Symbols::parse(&text).unwrap_or_else(|err| {
+    eprintln!("{}: {}", path, err);
+    process::exit(1);
+  })
+}
+
+fn usage() -> ! {
+  eprintln!("{}", USAGE);
+  process::exit(2);
+}
+
+struct Monitor {
+  vm: Vm,
+  running: bool,
+  // recently executed instructions, oldest first
+  history: VecDeque<Word>,
+  memory_top: Word,
+  // decaying access counts for the zero page
+  heat: [u32; 0x100],
+  // the command being typed after ':'
+  prompt: Option<String>,
+  message: String,
+  symbols: Symbols,
+  #[cfg(feature = "script")]
+  scripts: Scripts,
+}
+
+impl Monitor {
+  fn new(vm: Vm, symbols: Symbols) -> Self {
+    #[cfg(feature = "script")]
+    let mut scripts = Scripts::new();
+    #[cfg(feature = "script")]
+    scripts.set_symbols(symbols.clone());
+
+    Self {
+      vm,
+      running: false,
+      history: VecDeque::new(),
+      memory_top: 0x0200,
+      heat: [0; 0x100],
+      prompt: None,
+      message: String::new(),
+      symbols,
+      #[cfg(feature = "script")]
+      scripts,
+    }
+  }
+
+  fn run(&mut self, stdout: &mut io::Stdout) -> io::Result<()> {
+    queue!(stdout, terminal::Clear(ClearType::All))?;
+
+    loop {
+      if self.running {
+        if let Some(addr) = self.run_frame() {
+          self.running = false;
+          self.history.clear();
+          // keep what a breakpoint script printed
+          let at = match self.symbols.label(addr) {
+            Some(label) => format!("${:04X} ({})", addr, label),
+            None => format!("${:04X}", addr),
+          };
+          self.message = match self.message.as_str() {
+            "" => format!("break at {}", at),
+            line => format!("break at {}: {}", at, line),
+          };
+        }
+      }
+      if self.running && self.vm.cpu.halted() {
+        self.running = false;
+        self.message = format!("halted at ${:04X}", self.vm.cpu.pc);
+      }
+      self.cool();
+      self.draw(stdout)?;
+
+      if !event::poll(FRAME)? {
+        continue;
+      }
+      while event::poll(Duration::ZERO)? {
+        match event::read()? {
+          Event::Key(key) if key.kind != KeyEventKind::Release => {
+            if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+              return Ok(());
+            }
+            if self.prompt.is_some() {
+              self.prompt_key(key.code);
+            } else if !self.key(key.code) {
+              return Ok(());
+            }
+          }
+          Event::Resize(..) => queue!(stdout, terminal::Clear(ClearType::All))?,
+          _ => {}
+        }
+      }
+    }
+  }
+
+  // false to quit
+  fn key(&mut self, code: KeyCode) -> bool {
+    self.message.clear();
+    match code {
+      KeyCode::Char('q') => return false,
+      KeyCode::Char('s') | KeyCode::Char(' ') if !self.running => self.step(),
+      KeyCode::Char('r') => {
+        self.running = !self.running;
+        if self.running {
+          self.history.clear();
+          // get off the breakpoint we might be sitting on
+          if let Err(err) = self.vm.step_instruction() {
+            self.stop(err);
+          }
+        }
+      }
+      KeyCode::Char('b') => self.toggle_breakpoint(self.vm.cpu.pc),
+      KeyCode::Char('x') => {
+        self.vm.reset();
+        self.history.clear();
+      }
+      KeyCode::Char(':') => self.prompt = Some(String::new()),
+      KeyCode::Up => self.scroll(-0x10),
+      KeyCode::Down => self.scroll(0x10),
+      KeyCode::PageUp => self.scroll(-0x80),
+      KeyCode::PageDown => self.scroll(0x80),
+      _ => {}
+    }
+
+    true
+  }
+
+  fn prompt_key(&mut self, code: KeyCode) {
+    let prompt = self.prompt.as_mut().unwrap();
+    match code {
+      KeyCode::Char(ch) => prompt.push(ch),
+      KeyCode::Backspace => {
+        prompt.pop();
+      }
+      KeyCode::Esc => self.prompt = None,
+      KeyCode::Enter => {
+        let command = self.prompt.take().unwrap();
+        self.command(&command);
+      }
+      _ => {}
+    }
+  }
+
+  #[cfg(not(feature = "script"))]
+  fn run_frame(&mut self) -> Option<Word> {
+    self.vm.run(CYCLES_PER_FRAME).unwrap_or_else(|err| {
+      self.stop(err);
+      None
+    })
+  }
+
+  // breakpoint scripts decide whether to stop, their last printed line is shown
+  #[cfg(feature = "script")]
+  fn run_frame(&mut self) -> Option<Word> {
+    let stopped = self.scripts.run(&mut self.vm, CYCLES_PER_FRAME);
+    if let Some(line) = self.scripts.take_output().pop() {
+      self.message = line;
+    }
+
+    match stopped {
+      Ok(stopped) => stopped,
+      Err(err) => {
+        self.running = false;
+        self.message = format!("script error: {}", err);
+        None
+      }
+    }
+  }
+
+  // b ADDR toggles a breakpoint, m ADDR shows memory from ADDR and pc ADDR jumps,
+  // ADDR is hex, a label or a sum of them like table+10
+  // with scripting, bs ADDR CODE runs CODE at a breakpoint and e CODE evaluates CODE
+  fn command(&mut self, command: &str) {
+    #[cfg(feature = "script")]
+    if self.script_command(command) {
+      return;
+    }
+
+    let mut words = command.split_whitespace();
+    let name = words.next().unwrap_or("");
+    let addr = words.next().and_then(|addr| self.symbols.resolve(addr));
+
+    match (name, addr) {
+      ("b", Some(addr)) => self.toggle_breakpoint(addr),
+      ("m", Some(addr)) => self.memory_top = addr & 0xFFF0,
+      ("pc", Some(addr)) => {
+        self.vm.cpu.pc = addr;
+        self.history.clear();
+      }
+      ("", _) => {}
+      _ => self.message = format!("? {}", command),
+    }
+  }
+
+  // true when `command` was a script command
+  #[cfg(feature = "script")]
+  fn script_command(&mut self, command: &str) -> bool {
+    let command = command.trim_start();
+    let result = if let Some(code) = command.strip_prefix("e ") {
+      self
+        .scripts
+        .eval(&mut self.vm, code)
+        .map(|value| value.to_string())
+    } else if let Some(rest) = command.strip_prefix("bs ") {
+      let (addr, code) = rest.trim_start().split_once(' ').unwrap_or((rest, ""));
+      match self.symbols.resolve(addr) {
+        Some(addr) if code.trim().is_empty() => {
+          self.scripts.remove_breakpoint_script(&mut self.vm, addr);
+          Ok(format!("script cleared at ${:04X}", addr))
+        }
+        Some(addr) => self
+          .scripts
+          .set_breakpoint_script(&mut self.vm, addr, code)
+          .map(|_| format!("script set at ${:04X}", addr)),
+        None => Ok(format!("? {}", command)),
+      }
+    } else {
+      return false;
+    };
+
+    self.message = match result {
+      Ok(text) => self.scripts.take_output().pop().unwrap_or(text),
+      Err(err) => format!("script error: {}", err),
+    };
+    true
+  }
+
+  fn step(&mut self) {
+    if self.history.len() == HISTORY {
+      self.history.pop_front();
+    }
+    self.history.push_back(self.vm.cpu.pc);
+    if let Err(err) = self.vm.step_instruction() {
+      self.stop(err);
+    }
+  }
+
+  // the cpu stopped itself, on an illegal opcode or the stack wrapping
+  fn stop(&mut self, err: Error) {
+    self.running = false;
+    self.message = err.to_string();
+  }
+
+  fn toggle_breakpoint(&mut self, addr: Word) {
+    let set = self.vm.toggle_breakpoint(addr);
+    self.message = format!(
+      "breakpoint {} ${:04X}",
+      if set { "set at" } else { "cleared at" },
+      addr
+    );
+  }
+
+  fn scroll(&mut self, by: i32) {
+    self.memory_top = (self.memory_top as i32 + by).rem_euclid(0x10000) as Word;
+  }
+
+  // folds the accesses since the last frame into the heat map
+  fn cool(&mut self) {
+    let mem = &mut self.vm.cpu.mem;
+    for (addr, heat) in self.heat.iter_mut().enumerate() {
+      *heat = *heat / 2 + mem.access_count(addr as Word);
+    }
+    mem.clear_access_counts();
+  }
+
+  /* ------- drawing -------- */
+  fn draw(&self, stdout: &mut io::Stdout) -> io::Result<()> {
+    let state = if self.vm.cpu.halted() {
+      "halted"
+    } else if self.running {
+      "running"
+    } else {
+      "stopped"
+    };
+    let title = format!("g6502  {}  {} cycles", state, self.vm.cycles());
+    line(stdout, 0, 0, 100, &title)?;
+
+    self.draw_disassembly(stdout, 0, 2)?;
+    self.draw_registers(stdout, 36, 2)?;
+    self.draw_stack(stdout, 36, 10)?;
+    self.draw_heat(stdout, 58, 2)?;
+    self.draw_memory(stdout, 0, 20)?;
+
+    let bottom = 21 + MEMORY_LINES as u16;
+    match &self.prompt {
+      Some(prompt) => line(stdout, 0, bottom + 1, 100, &format!(":{}", prompt))?,
+      None => line(stdout, 0, bottom + 1, 100, &self.message)?,
+    }
+    line(stdout, 0, bottom + 2, 100, HELP)?;
+
+    stdout.flush()
+  }
+
+  fn draw_disassembly(&self, stdout: &mut io::Stdout, x: u16, y: u16) -> io::Result<()> {
+    line(stdout, x, y, 34, "── disassembly ──")?;
+
+    let pc = self.vm.cpu.pc;
+    let mem = &self.vm.cpu.mem;
+    let breakpoints: Vec<Word> = self.vm.breakpoints().collect();
+
+    // what just ran, then straight on from the pc
+    let mut ahead = pc;
+    let addrs = self.history.iter().copied().chain(std::iter::from_fn(|| {
+      let addr = ahead;
+      ahead = disassemble(mem, addr).next();
+      Some(addr)
+    }));
+
+    // labelled addresses get a row of their own above them
+    let mut rows = Vec::new();
+    for addr in addrs {
+      if let Some(label) = self.symbols.label(addr) {
+        rows.push((None, format!("      {}:", label)));
+      }
+      let ins = disassemble_with(mem, addr, Some(&self.symbols));
+      let bytes: Vec<String> = ins.bytes.iter().map(|b| format!("{:02X}", b)).collect();
+      let mark = if breakpoints.contains(&addr) {
+        '*'
+      } else {
+        ' '
+      };
+      let text = format!("{}{:04X}  {:<9} {}", mark, addr, bytes.join(" "), ins.text);
+      rows.push((Some(addr), text));
+
+      if rows.len() >= DISASM_LINES {
+        break;
+      }
+    }
+
+    for (row, (addr, text)) in rows.iter().take(DISASM_LINES).enumerate() {
+      let row = y + 1 + row as u16;
+      if *addr == Some(pc) {
+        queue!(stdout, SetAttribute(Attribute::Reverse))?;
+        line(stdout, x, row, 34, text)?;
+        queue!(stdout, SetAttribute(Attribute::NoReverse))?;
+      } else {
+        line(stdout, x, row, 34, text)?;
+      }
+    }
+
+    Ok(())
+  }
+
+  fn draw_registers(&self, stdout: &mut io::Stdout, x: u16, y: u16) -> io::Result<()> {
+    let cpu = &self.vm.cpu;
+    let flags: String = [
+      (CpuStatus::N, 'N'),
+      (CpuStatus::V, 'V'),
+      (CpuStatus::U, '-'),
+      (CpuStatus::B, 'B'),
+      (CpuStatus::D, 'D'),
+      (CpuStatus::I, 'I'),
+      (CpuStatus::Z, 'Z'),
+      (CpuStatus::C, 'C'),
+    ]
+    .iter()
+    .map(|&(flag, name)| {
+      if cpu.status.is_flag_set(flag) {
+        name
+      } else {
+        '.'
+      }
+    })
+    .collect();
+
+    let rows = [
+      String::from("── registers ──"),
+      format!("PC  ${:04X}", cpu.pc),
+      format!("A   ${:02X}", cpu.reg_a),
+      format!("X   ${:02X}", cpu.reg_x),
+      format!("Y   ${:02X}", cpu.reg_y),
+      format!("SP  ${:02X}", cpu.sp),
+      format!("P   {}", flags),
+    ];
+    for (i, text) in rows.iter().enumerate() {
+      line(stdout, x, y + i as u16, 20, text)?;
+    }
+
+    Ok(())
+  }
+
+  // the top of the stack, the last pushed byte first
+  fn draw_stack(&self, stdout: &mut io::Stdout, x: u16, y: u16) -> io::Result<()> {
+    line(stdout, x, y, 20, "── stack ──")?;
+
+    let sp = self.vm.cpu.sp;
+    for i in 0..STACK_LINES {
+      let offset = sp as usize + 1 + i;
+      let text = if offset <= 0xFF {
+        let addr = 0x0100 | offset as Word;
+        format!("${:04X}  {:02X}", addr, self.vm.cpu.mem.peek(addr))
+      } else {
+        String::new()
+      };
+      line(stdout, x, y + 1 + i as u16, 20, &text)?;
+    }
+
+    Ok(())
+  }
+
+  fn draw_heat(&self, stdout: &mut io::Stdout, x: u16, y: u16) -> io::Result<()> {
+    line(stdout, x, y, 40, "── zero page ──")?;
+    line(stdout, x, y + 1, 40, "    0 1 2 3 4 5 6 7 8 9 A B C D E F")?;
+
+    for row in 0..0x10 {
+      queue!(
+        stdout,
+        cursor::MoveTo(x, y + 2 + row as u16),
+        Print(format!("{:02X}  ", row << 4))
+      )?;
+      for col in 0..0x10 {
+        let (color, cell) = match self.heat[row << 4 | col] {
+          0 => (Color::DarkGrey, "· "),
+          1..=3 => (Color::Blue, "░░"),
+          4..=15 => (Color::Cyan, "▒▒"),
+          16..=63 => (Color::Green, "▓▓"),
+          64..=255 => (Color::Yellow, "██"),
+          _ => (Color::Red, "██"),
+        };
+        queue!(stdout, SetForegroundColor(color), Print(cell))?;
+      }
+      queue!(stdout, ResetColor)?;
+    }
+
+    Ok(())
+  }
+
+  fn draw_memory(&self, stdout: &mut io::Stdout, x: u16, y: u16) -> io::Result<()> {
+    line(stdout, x, y, 80, "── memory ──")?;
+
+    let mem = &self.vm.cpu.mem;
+    for row in 0..MEMORY_LINES {
+      let base = self.memory_top.wrapping_add(row as Word * 0x10);
+      let bytes: Vec<u8> = (0..0x10).map(|i| mem.peek(base.wrapping_add(i))).collect();
+      let hex: Vec<String> = bytes.iter().map(|b| format!("{:02X}", b)).collect();
+      let ascii: String = bytes
+        .iter()
+        .map(|&b| {
+          if (0x20..0x7F).contains(&b) {
+            b as char
+          } else {
+            '.'
+          }
+        })
+        .collect();
+
+      let text = format!("${:04X}  {}  {}", base, hex.join(" "), ascii);
+      line(stdout, x, y + 1 + row as u16, 80, &text)?;
+    }
+
+    Ok(())
+  }
+  /* ------- drawing -------- */
+}
+
+// `text` at x, y padded or cut to `width` so it overwrites the last frame
+fn line(stdout: &mut io::Stdout, x: u16, y: u16, width: usize, text: &str) -> io::Result<()> {
+  let text: String = text
+    .chars()
+    .chain(std::iter::repeat(' '))
+    .take(width)
+    .collect();
+  queue!(stdout, cursor::MoveTo(x, y), Print(text))
+}
@@ -0,0 +1,369 @@
+// the code pyo3 generates for PyResult methods converts PyErr into itself
+#![allow(clippy::useless_conversion)]
+
+use std::{cell::RefCell, rc::Rc};
+
+use pyo3::{
+  exceptions::{PyIndexError, PyRuntimeError, PyTypeError},
+  prelude::*,
+  types::{PyBytes, PySlice},
+};
+
+use crate::vm::{devices::Device, Byte, Error, Vm, Word, MEM_SIZE};
+
+mod test;
+
+// the first exception raised by a callback, handed back to python once the vm stops
+type CallbackError = Rc<RefCell<Option<PyErr>>>;
+
+// `import g6502`, built with maturin and the extension-module feature
+#[pymodule]
+fn g6502(m: &Bound<'_, PyModule>) -> PyResult<()> {
+  m.add_class::<PyVm>()?;
+  m.add_class::<Memory>()?;
+  Ok(())
+}
+
+// the vm for python scripts, 64K of RAM with nothing mapped
+#[pyclass(name = "Vm", module = "g6502", unsendable)]
+pub struct PyVm {
+  vm: Vm,
+  error: CallbackError,
+}
+
+impl Default for PyVm {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+// an illegal opcode or a stack wrap that the vm's policies made an error
+fn vm_error(err: Error) -> PyErr {
+  PyRuntimeError::new_err(err.to_string())
+}
+
+impl PyVm {
+  fn check(&self) -> PyResult<()> {
+    match self.error.borrow_mut().take() {
+      Some(err) => Err(err),
+      None => Ok(()),
+    }
+  }
+
+  // Vm::run stops at once on the breakpoint it stopped at last, so that instruction
+  // runs first and a loop of runs gets somewhere
+  fn run_off_breakpoint(&mut self, max_cycles: u64) -> Result<Option<Word>, Error> {
+    let start = self.vm.cycles();
+    let pc = self.vm.cpu.pc;
+    if self.vm.cpu.cycles == 0 && self.vm.breakpoints().any(|addr| addr == pc) {
+      self.vm.step_instruction()?;
+    }
+    let ran = self.vm.cycles() - start;
+    self.vm.run(max_cycles.saturating_sub(ran))
+  }
+}
+
+#[pymethods]
+impl PyVm {
+  #[new]
+  pub fn new() -> Self {
+    Self {
+      vm: Vm::new(),
+      error: Rc::new(RefCell::new(None)),
+    }
+  }
+
+  pub fn reset(&mut self) -> PyResult<()> {
+    self.vm.reset();
+    self.check()
+  }
+
+  pub fn load(&mut self, data: &[u8], addr: Word) -> PyResult<()> {
+    if addr as usize + data.len() > MEM_SIZE {
+      return Err(PyIndexError::new_err("program doesn't fit in memory"));
+    }
+    self.vm.load(data, addr).map_err(vm_error)
+  }
+
+  // loads `data` at `addr`, points the reset vector at it and resets
+  pub fn load_program(&mut self, data: &[u8], addr: Word) -> PyResult<()> {
+    self.load(data, addr)?;
+    self.load(&[addr as Byte, (addr >> 8) as Byte], 0xFFFC)?;
+    self.reset()
+  }
+
+  /* ------- execution -------- */
+  // a single clock cycle
+  pub fn step(&mut self) -> PyResult<()> {
+    let result = self.vm.step();
+    // a callback's exception comes first, it's what broke the instruction
+    self.check()?;
+    result.map_err(vm_error)
+  }
+
+  pub fn step_instruction(&mut self) -> PyResult<()> {
+    let result = self.vm.step_instruction();
+    self.check()?;
+    result.map_err(vm_error)
+  }
+
+  // the breakpoint it stopped at, or None once `max_cycles` have run. sitting on a
+  // breakpoint, it runs that instruction first instead of stopping right away
+  #[pyo3(signature = (max_cycles = 1_000_000))]
+  pub fn run(&mut self, max_cycles: u64) -> PyResult<Option<Word>> {
+    let stopped = self.run_off_breakpoint(max_cycles);
+    self.check()?;
+    stopped.map_err(vm_error)
+  }
+
+  // runs until the pc gets to `pc` or a breakpoint, returns where it stopped
+  // or None if that took more than `max_cycles`. like run it doesn't stop where it starts
+  #[pyo3(signature = (pc = None, max_cycles = 10_000_000))]
+  pub fn run_until(&mut self, pc: Option<Word>, max_cycles: u64) -> PyResult<Option<Word>> {
+    let temporary = pc.filter(|&pc| !self.vm.breakpoints().any(|addr| addr == pc));
+    if let Some(pc) = temporary {
+      self.vm.add_breakpoint(pc);
+    }
+    let stopped = self.run_off_breakpoint(max_cycles);
+    if let Some(pc) = temporary {
+      self.vm.remove_breakpoint(pc);
+    }
+
+    self.check()?;
+    stopped.map_err(vm_error)
+  }
+
+  pub fn add_breakpoint(&mut self, addr: Word) {
+    self.vm.add_breakpoint(addr);
+  }
+
+  pub fn remove_breakpoint(&mut self, addr: Word) {
+    self.vm.remove_breakpoint(addr);
+  }
+
+  #[getter]
+  pub fn breakpoints(&self) -> Vec<Word> {
+    self.vm.breakpoints().collect()
+  }
+  /* ------- execution -------- */
+
+  /* ------- registers -------- */
+  #[getter]
+  pub fn pc(&self) -> Word {
+    self.vm.cpu.pc
+  }
+
+  #[setter]
+  pub fn set_pc(&mut self, pc: Word) {
+    self.vm.cpu.pc = pc;
+  }
+
+  #[getter]
+  pub fn a(&self) -> Byte {
+    self.vm.cpu.reg_a
+  }
+
+  #[setter]
+  pub fn set_a(&mut self, a: Byte) {
+    self.vm.cpu.reg_a = a;
+  }
+
+  #[getter]
+  pub fn x(&self) -> Byte {
+    self.vm.cpu.reg_x
+  }
+
+  #[setter]
+  pub fn set_x(&mut self, x: Byte) {
+    self.vm.cpu.reg_x = x;
+  }
+
+  #[getter]
+  pub fn y(&self) -> Byte {
+    self.vm.cpu.reg_y
+  }
+
+  #[setter]
+  pub fn set_y(&mut self, y: Byte) {
+    self.vm.cpu.reg_y = y;
+  }
+
+  #[getter]
+  pub fn sp(&self) -> Byte {
+    self.vm.cpu.sp
+  }
+
+  #[setter]
+  pub fn set_sp(&mut self, sp: Byte) {
+    self.vm.cpu.sp = sp;
+  }
+
+  #[getter]
+  pub fn status(&self) -> Byte {
+    self.vm.cpu.status.bits()
+  }
+
+  #[getter]
+  pub fn cycles(&self) -> u64 {
+    self.vm.cycles()
+  }
+
+  pub fn __repr__(&self) -> String {
+    let cpu = &self.vm.cpu;
+    format!(
+      "Vm(pc=${:04X}, a=${:02X}, x=${:02X}, y=${:02X}, sp=${:02X}, status=${:02X})",
+      cpu.pc,
+      cpu.reg_a,
+      cpu.reg_x,
+      cpu.reg_y,
+      cpu.sp,
+      cpu.status.bits()
+    )
+  }
+  /* ------- registers -------- */
+
+  /* ------- memory -------- */
+  // `vm.memory[addr]` and `vm.memory[start:end]` peek, assigning to them writes on the bus
+  #[getter]
+  pub fn memory(slf: &Bound<'_, Self>) -> Memory {
+    Memory {
+      vm: slf.clone().unbind(),
+    }
+  }
+
+  // maps `length` bytes from `start` to python callables, `read(addr)` returns a byte and
+  // `write(addr, data)` takes one, addresses are relative to `start`. `peek(addr)` is for
+  // debugger reads and mustn't have side effects, without it they see `read`
+  #[pyo3(signature = (start, length, read = None, write = None, peek = None))]
+  pub fn map(
+    &mut self,
+    start: Word,
+    length: usize,
+    read: Option<PyObject>,
+    write: Option<PyObject>,
+    peek: Option<PyObject>,
+  ) {
+    let device = CallbackDevice {
+      read,
+      write,
+      peek,
+      error: self.error.clone(),
+    };
+    self
+      .vm
+      .cpu
+      .mem
+      .map_device(start, length, Rc::new(RefCell::new(device)));
+  }
+  /* ------- memory -------- */
+}
+
+#[pyclass(module = "g6502", unsendable)]
+pub struct Memory {
+  vm: Py<PyVm>,
+}
+
+#[pymethods]
+impl Memory {
+  pub fn __len__(&self) -> usize {
+    MEM_SIZE
+  }
+
+  pub fn __getitem__(&self, py: Python<'_>, index: &Bound<'_, PyAny>) -> PyResult<PyObject> {
+    let vm = self.vm.borrow(py);
+    let mem = &vm.vm.cpu.mem;
+
+    let data = if let Ok(slice) = index.downcast::<PySlice>() {
+      let data: Vec<Byte> = addresses(slice)?.map(|addr| mem.peek(addr)).collect();
+      PyBytes::new_bound(py, &data).into_py(py)
+    } else {
+      mem.peek(address(index)?).into_py(py)
+    };
+
+    vm.check()?;
+    Ok(data)
+  }
+
+  pub fn __setitem__(
+    &self,
+    py: Python<'_>,
+    index: &Bound<'_, PyAny>,
+    value: &Bound<'_, PyAny>,
+  ) -> PyResult<()> {
+    let mut vm = self.vm.borrow_mut(py);
+
+    if let Ok(slice) = index.downcast::<PySlice>() {
+      let data: Vec<Byte> = value.extract()?;
+      let addrs: Vec<Word> = addresses(slice)?.collect();
+      if addrs.len() != data.len() {
+        return Err(PyIndexError::new_err("slice and data lengths differ"));
+      }
+      for (addr, data) in addrs.into_iter().zip(data) {
+        vm.vm.cpu.mem.write(addr, data);
+      }
+    } else {
+      vm.vm.cpu.mem.write(address(index)?, value.extract()?);
+    }
+
+    vm.check()
+  }
+}
+
+fn address(index: &Bound<'_, PyAny>) -> PyResult<Word> {
+  let index: isize = index
+    .extract()
+    .map_err(|_| PyTypeError::new_err("memory is indexed by address or slice"))?;
+  Word::try_from(index).map_err(|_| PyIndexError::new_err("address out of range"))
+}
+
+fn addresses(slice: &Bound<'_, PySlice>) -> PyResult<impl Iterator<Item = Word>> {
+  let indices = slice.indices(MEM_SIZE as _)?;
+  Ok((0..indices.slicelength).map(move |i| (indices.start + i as isize * indices.step) as Word))
+}
+
+struct CallbackDevice {
+  read: Option<PyObject>,
+  write: Option<PyObject>,
+  peek: Option<PyObject>,
+  error: CallbackError,
+}
+
+impl CallbackDevice {
+  // keeps the first exception and reads open bus
+  fn call(&self, callback: Option<&PyObject>, addr: Word) -> Byte {
+    let callback = match callback {
+      Some(callback) => callback,
+      None => return 0xFF,
+    };
+
+    Python::with_gil(|py| {
+      callback
+        .call1(py, (addr,))
+        .and_then(|data| data.extract::<Byte>(py))
+        .unwrap_or_else(|err| {
+          self.error.borrow_mut().get_or_insert(err);
+          0xFF
+        })
+    })
+  }
+}
+
+impl Device for CallbackDevice {
+  fn read(&mut self, addr: Word) -> Byte {
+    self.call(self.read.as_ref(), addr)
+  }
+
+  fn write(&mut self, addr: Word, data: Byte) {
+    if let Some(write) = &self.write {
+      Python::with_gil(|py| {
+        if let Err(err) = write.call1(py, (addr, data)) {
+          self.error.borrow_mut().get_or_insert(err);
+        }
+      });
+    }
+  }
+
+  fn peek(&self, addr: Word) -> Byte {
+    self.call(self.peek.as_ref().or(self.read.as_ref()), addr)
+  }
+}
@@ -0,0 +1,85 @@
+#[cfg(test)]
+mod tests {
+  use pyo3::{prelude::*, types::IntoPyDict};
+
+  use crate::python::PyVm;
+
+  // runs `script` with the Vm class in scope
+  fn run_script(script: &str) -> PyResult<()> {
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+      let globals = [("Vm", py.get_type_bound::<PyVm>())].into_py_dict_bound(py);
+      py.run_bound(script, Some(&globals), None)
+    })
+  }
+
+  #[test]
+  fn python_drives_the_vm() {
+    let script = r#"
+vm = Vm()
+# LDA $D011 ; STA $D020 ; STA $10 ; loop: JMP loop
+vm.load_program(bytes([0xAD, 0x11, 0xD0, 0x8D, 0x20, 0xD0, 0x85, 0x10, 0x4C, 0x08, 0x02]), 0x0200)
+writes = []
+vm.map(0xD000, 0x100, read=lambda addr: 0x80 | addr, write=lambda addr, data: writes.append((addr, data)))
+
+assert vm.run_until(pc=0x0208) == 0x0208, vm
+assert vm.breakpoints == [], "run_until left its breakpoint behind"
+assert vm.a == 0x91, vm
+assert writes == [(0x20, 0x91)], writes
+assert vm.memory[0x10] == 0x91
+assert vm.memory[0x0200:0x0203] == bytes([0xAD, 0x11, 0xD0])
+
+vm.memory[0x20:0x22] = [1, 2]
+assert vm.memory[0x20] + vm.memory[0x21] == 3
+"#;
+
+    if let Err(err) = run_script(script) {
+      Python::with_gil(|py| err.print(py));
+      panic!("python script failed");
+    }
+  }
+
+  #[test]
+  fn runs_step_off_breakpoints() {
+    let script = r#"
+vm = Vm()
+# loop: INX ; JMP loop
+vm.load_program(bytes([0xE8, 0x4C, 0x00, 0x02]), 0x0200)
+vm.add_breakpoint(0x0200)
+assert vm.run() == 0x0200, vm
+assert vm.run() == 0x0200, vm
+assert vm.x == 1, "the second run didn't get off the breakpoint"
+
+vm.remove_breakpoint(0x0200)
+for i in range(3):
+    assert vm.run_until(pc=0x0201) == 0x0201, vm
+assert vm.x == 4, "run_until stayed where it started"
+"#;
+
+    if let Err(err) = run_script(script) {
+      Python::with_gil(|py| err.print(py));
+      panic!("python script failed");
+    }
+  }
+
+  #[test]
+  fn callback_exceptions_reach_python() {
+    let script = r#"
+vm = Vm()
+# LDA $D000
+vm.load_program(bytes([0xAD, 0x00, 0xD0]), 0x0200)
+vm.map(0xD000, 0x100, read=lambda addr: 1 // 0)
+try:
+    vm.step_instruction()
+    vm.step_instruction()
+    raise AssertionError("the exception was swallowed")
+except ZeroDivisionError:
+    pass
+"#;
+
+    if let Err(err) = run_script(script) {
+      Python::with_gil(|py| err.print(py));
+      panic!("python script failed");
+    }
+  }
+}
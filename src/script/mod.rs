@@ -0,0 +1,209 @@
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
+
+use rhai::{Dynamic, Engine, EvalAltResult, AST, INT};
+
+use crate::vm::{Byte, CpuStatus, Symbols, Vm, Word};
+
+mod test;
+
+pub type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+// what the scripts see, the vm is moved in for the length of a call
+#[derive(Default)]
+struct State {
+  vm: Vm,
+  resume: bool,
+  output: Vec<String>,
+  symbols: Symbols,
+}
+
+type Shared = Rc<RefCell<State>>;
+
+// rhai scripts attached to breakpoints, they get the registers, the flags and the bus through:
+//   a() x() y() sp() pc() status() cycles() and set_a(v) ... set_pc(v)
+//   flag("C") set_flag("C", on)  peek(addr) poke(addr, v)
+//   sym("name") for a label's address and label(addr) for an address's label, or ""
+//   resume() to carry on instead of stopping, and print() to log
+pub struct Scripts {
+  engine: Engine,
+  state: Shared,
+  // the source is kept to show in the monitor
+  breakpoints: BTreeMap<Word, (String, AST)>,
+}
+
+impl Default for Scripts {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+fn flag(name: &str) -> ScriptResult<CpuStatus> {
+  Ok(match name {
+    "C" => CpuStatus::C,
+    "Z" => CpuStatus::Z,
+    "I" => CpuStatus::I,
+    "D" => CpuStatus::D,
+    "B" => CpuStatus::B,
+    "U" => CpuStatus::U,
+    "V" => CpuStatus::V,
+    "N" => CpuStatus::N,
+    _ => return Err(format!("no flag named {}", name).into()),
+  })
+}
+
+impl Scripts {
+  pub fn new() -> Self {
+    let state = Shared::default();
+    let mut engine = Engine::new();
+
+    macro_rules! register {
+      ($get:literal, $set:literal, $($reg:ident).+, $ty:ty) => {
+        let s = state.clone();
+        engine.register_fn($get, move || s.borrow().vm.$($reg).+ as INT);
+        let s = state.clone();
+        engine.register_fn($set, move |v: INT| s.borrow_mut().vm.$($reg).+ = v as $ty);
+      };
+    }
+    register!("a", "set_a", cpu.reg_a, Byte);
+    register!("x", "set_x", cpu.reg_x, Byte);
+    register!("y", "set_y", cpu.reg_y, Byte);
+    register!("sp", "set_sp", cpu.sp, Byte);
+    register!("pc", "set_pc", cpu.pc, Word);
+
+    let s = state.clone();
+    engine.register_fn("status", move || s.borrow().vm.cpu.status.bits() as INT);
+    let s = state.clone();
+    engine.register_fn("cycles", move || s.borrow().vm.cycles() as INT);
+    let s = state.clone();
+    engine.register_fn("flag", move |name: &str| -> ScriptResult<bool> {
+      Ok(s.borrow().vm.cpu.status.is_flag_set(flag(name)?))
+    });
+    let s = state.clone();
+    engine.register_fn(
+      "set_flag",
+      move |name: &str, on: bool| -> ScriptResult<()> {
+        let status = &mut s.borrow_mut().vm.cpu.status;
+        if on {
+          status.set_flag(flag(name)?);
+        } else {
+          status.clear_flag(flag(name)?);
+        }
+        Ok(())
+      },
+    );
+
+    // peeks so a script can't upset a device by looking at it, pokes go over the bus
+    let s = state.clone();
+    engine.register_fn("peek", move |addr: INT| {
+      s.borrow().vm.cpu.mem.peek(addr as Word) as INT
+    });
+    let s = state.clone();
+    engine.register_fn("poke", move |addr: INT, data: INT| {
+      s.borrow_mut().vm.cpu.mem.write(addr as Word, data as Byte)
+    });
+
+    let s = state.clone();
+    engine.register_fn("sym", move |name: &str| -> ScriptResult<INT> {
+      match s.borrow().symbols.addr(name) {
+        Some(addr) => Ok(addr as INT),
+        None => Err(format!("no symbol named {}", name).into()),
+      }
+    });
+    let s = state.clone();
+    engine.register_fn("label", move |addr: INT| {
+      let state = s.borrow();
+      state.symbols.label(addr as Word).unwrap_or("").to_string()
+    });
+
+    let s = state.clone();
+    engine.register_fn("resume", move || s.borrow_mut().resume = true);
+    let s = state.clone();
+    engine.on_print(move |text| s.borrow_mut().output.push(text.to_string()));
+
+    Self {
+      engine,
+      state,
+      breakpoints: BTreeMap::new(),
+    }
+  }
+
+  pub fn set_symbols(&mut self, symbols: Symbols) {
+    self.state.borrow_mut().symbols = symbols;
+  }
+
+  // runs `source` against the vm, returns what it evaluates to
+  pub fn eval(&mut self, vm: &mut Vm, source: &str) -> ScriptResult<Dynamic> {
+    let ast = self.engine.compile(source)?;
+    self.call(vm, &ast)
+  }
+
+  // sets a breakpoint at `addr` that runs `source` when it's hit
+  pub fn set_breakpoint_script(
+    &mut self,
+    vm: &mut Vm,
+    addr: Word,
+    source: &str,
+  ) -> ScriptResult<()> {
+    let ast = self.engine.compile(source)?;
+    self.breakpoints.insert(addr, (source.to_string(), ast));
+    vm.add_breakpoint(addr);
+    Ok(())
+  }
+
+  pub fn remove_breakpoint_script(&mut self, vm: &mut Vm, addr: Word) {
+    if self.breakpoints.remove(&addr).is_some() {
+      vm.remove_breakpoint(addr);
+    }
+  }
+
+  pub fn breakpoint_script(&self, addr: Word) -> Option<&str> {
+    self
+      .breakpoints
+      .get(&addr)
+      .map(|(source, _)| source.as_str())
+  }
+
+  // runs the script of the breakpoint at `addr`, true when it called resume()
+  pub fn on_break(&mut self, vm: &mut Vm, addr: Word) -> ScriptResult<bool> {
+    let ast = match self.breakpoints.get(&addr) {
+      Some((_, ast)) => ast.clone(),
+      None => return Ok(false),
+    };
+
+    // what the script evaluates to doesn't matter, only whether it resumed
+    self.state.borrow_mut().resume = false;
+    let _ = self.call(vm, &ast)?;
+    Ok(self.state.borrow().resume)
+  }
+
+  // Vm::run that lets breakpoint scripts decide whether to stop, returns the breakpoint
+  // it stopped at or none once `max_cycles` have gone by
+  pub fn run(&mut self, vm: &mut Vm, max_cycles: u64) -> ScriptResult<Option<Word>> {
+    let end = vm.cycles() + max_cycles;
+    while vm.cycles() < end {
+      let addr = match vm.run(end - vm.cycles()).map_err(|err| err.to_string())? {
+        Some(addr) => addr,
+        None => return Ok(None),
+      };
+      if !self.on_break(vm, addr)? {
+        return Ok(Some(addr));
+      }
+      vm.step_instruction().map_err(|err| err.to_string())?;
+    }
+
+    Ok(None)
+  }
+
+  // lines the scripts printed since the last call
+  pub fn take_output(&mut self) -> Vec<String> {
+    std::mem::take(&mut self.state.borrow_mut().output)
+  }
+
+  fn call(&mut self, vm: &mut Vm, ast: &AST) -> ScriptResult<Dynamic> {
+    std::mem::swap(vm, &mut self.state.borrow_mut().vm);
+    let result = self.engine.eval_ast(ast);
+    std::mem::swap(vm, &mut self.state.borrow_mut().vm);
+
+    result
+  }
+}
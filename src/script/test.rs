@@ -0,0 +1,74 @@
+#[cfg(test)]
+mod tests {
+  use crate::{
+    script::Scripts,
+    vm::{Symbols, Vm},
+  };
+
+  fn counter() -> Vm {
+    let mut vm = Vm::new();
+    // LDX #$00 ; loop: INX ; STX $10 ; JMP loop
+    vm.load(&[0xA2, 0x00, 0xE8, 0x86, 0x10, 0x4C, 0x02, 0x02], 0x0200)
+      .unwrap();
+    vm.load(&[0x00, 0x02], 0xFFFC).unwrap();
+    vm.reset();
+    vm
+  }
+
+  #[test]
+  fn breakpoint_scripts_log_and_resume() {
+    let mut vm = counter();
+    let mut scripts = Scripts::new();
+    scripts
+      .set_breakpoint_script(
+        &mut vm,
+        0x0205,
+        r#"print(`X=${x()}`); if x() < 3 { resume() }"#,
+      )
+      .unwrap();
+
+    assert_eq!(
+      scripts.run(&mut vm, 10_000).unwrap(),
+      Some(0x0205),
+      "script didn't stop the vm"
+    );
+    assert_eq!(vm.cpu.reg_x, 3);
+    assert_eq!(scripts.take_output(), ["X=1", "X=2", "X=3"]);
+  }
+
+  #[test]
+  fn scripts_patch_registers_and_memory() {
+    let mut vm = counter();
+    vm.step_instruction().unwrap();
+
+    let mut scripts = Scripts::new();
+    let result = scripts.eval(
+      &mut vm,
+      r#"set_x(0x41); poke(0x10, 0x99); set_flag("C", true)"#,
+    );
+    assert!(result.unwrap().is_unit());
+    assert_eq!(vm.cpu.reg_x, 0x41, "set_x didn't reach the cpu");
+    assert_eq!(vm.cpu.mem.peek(0x10), 0x99, "poke didn't reach memory");
+
+    let carry = scripts
+      .eval(&mut vm, r#"flag("C") && peek(0x10) == 0x99"#)
+      .unwrap();
+    assert!(
+      carry.as_bool().unwrap(),
+      "script didn't see its own changes"
+    );
+    assert!(
+      scripts.eval(&mut vm, r#"flag("Q")"#).is_err(),
+      "unknown flag wasn't an error"
+    );
+
+    let mut symbols = Symbols::new();
+    symbols.insert("count", 0x10);
+    scripts.set_symbols(symbols);
+    let label = scripts
+      .eval(&mut vm, r#"label(sym("count")) + peek(sym("count"))"#)
+      .unwrap();
+    assert_eq!(label.to_string(), "count153", "sym and label disagree");
+    assert!(scripts.eval(&mut vm, r#"sym("nowhere")"#).is_err());
+  }
+}
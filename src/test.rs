@@ -0,0 +1,115 @@
+#[cfg(test)]
+mod tests {
+  use crate::{parse_addr, Monitor};
+  use g6502::vm::{Symbols, Vm};
+
+  fn monitor() -> Monitor {
+    let mut symbols = Symbols::new();
+    symbols.insert("main", 0x0200);
+    symbols.insert("buffer", 0x0300);
+    symbols.insert("add", 0x0210);
+    Monitor::new(Vm::new(), symbols)
+  }
+
+  #[test]
+  fn parses_addresses() {
+    assert_eq!(parse_addr("0200"), Some(0x0200), "bare hex didn't parse");
+    assert_eq!(parse_addr("$C000"), Some(0xC000), "$ prefix didn't parse");
+    assert_eq!(parse_addr("0xfffc"), Some(0xFFFC), "0x prefix didn't parse");
+    assert_eq!(parse_addr("10000"), None, "address past $FFFF parsed");
+    assert_eq!(parse_addr("main"), None, "a word parsed as hex");
+    assert_eq!(parse_addr(""), None, "nothing parsed");
+  }
+
+  #[test]
+  fn breakpoint_command() {
+    let mut monitor = monitor();
+    monitor.command("b 0205");
+    assert_eq!(
+      monitor.vm.breakpoints().collect::<Vec<_>>(),
+      [0x0205],
+      "b didn't set a breakpoint"
+    );
+    assert_eq!(monitor.message, "breakpoint set at $0205", "wrong message");
+
+    monitor.command("  b   $0205 ");
+    assert_eq!(
+      monitor.vm.breakpoints().count(),
+      0,
+      "b on a breakpoint didn't clear it"
+    );
+    assert_eq!(
+      monitor.message, "breakpoint cleared at $0205",
+      "wrong message"
+    );
+
+    monitor.command("b main+5");
+    assert_eq!(
+      monitor.vm.breakpoints().collect::<Vec<_>>(),
+      [0x0205],
+      "b didn't resolve a label sum"
+    );
+  }
+
+  #[test]
+  fn memory_and_pc_commands() {
+    let mut monitor = monitor();
+    monitor.command("m buffer+1A");
+    assert_eq!(monitor.memory_top, 0x0310, "m didn't align to a row");
+
+    monitor.history.push_back(0x0400);
+    monitor.command("pc main");
+    assert_eq!(monitor.vm.cpu.pc, 0x0200, "pc didn't jump to the label");
+    assert!(monitor.history.is_empty(), "pc kept the old history");
+
+    // a label called add isn't $0ADD
+    monitor.command("pc add");
+    assert_eq!(monitor.vm.cpu.pc, 0x0210, "hex won over a label");
+    monitor.command("pc end-10");
+    assert_eq!(monitor.message, "? pc end-10", "unknown label resolved");
+    assert_eq!(monitor.vm.cpu.pc, 0x0210, "bad address moved the pc");
+  }
+
+  #[test]
+  fn bad_commands() {
+    let mut monitor = monitor();
+    monitor.command("");
+    assert_eq!(monitor.message, "", "empty command complained");
+    monitor.command("   ");
+    assert_eq!(monitor.message, "", "blank command complained");
+    monitor.command("x 0200");
+    assert_eq!(monitor.message, "? x 0200", "unknown command accepted");
+    monitor.command("b");
+    assert_eq!(monitor.message, "? b", "b without an address accepted");
+    monitor.command("m zz");
+    assert_eq!(monitor.message, "? m zz", "m with a bad address accepted");
+    assert_eq!(monitor.memory_top, 0x0200, "bad m scrolled memory");
+  }
+
+  #[cfg(feature = "script")]
+  #[test]
+  fn script_commands() {
+    let mut monitor = monitor();
+    monitor.command("e 1 + 2");
+    assert_eq!(monitor.message, "3", "e didn't evaluate");
+
+    monitor.command("bs main print(a())");
+    assert_eq!(
+      monitor.message, "script set at $0200",
+      "bs didn't set a script"
+    );
+    assert_eq!(
+      monitor.vm.breakpoints().collect::<Vec<_>>(),
+      [0x0200],
+      "bs didn't set a breakpoint"
+    );
+    monitor.command("bs main");
+    assert_eq!(
+      monitor.message, "script cleared at $0200",
+      "bs didn't clear"
+    );
+
+    monitor.command("bs nowhere print(1)");
+    assert_eq!(monitor.message, "? bs nowhere print(1)", "bad bs accepted");
+  }
+}
@@ -0,0 +1,160 @@
+use alloc::{vec, vec::Vec};
+
+use super::{
+  defs::{Byte, Word},
+  error::Error,
+};
+
+// a page of RAM or ROM that can be switched into a window
+pub struct Bank {
+  data: Vec<Byte>,
+  writable: bool,
+}
+
+impl Bank {
+  pub fn ram(size: usize) -> Self {
+    Self {
+      data: vec![0; size],
+      writable: true,
+    }
+  }
+
+  pub fn rom(data: &[u8]) -> Self {
+    Self {
+      data: data.to_vec(),
+      writable: false,
+    }
+  }
+
+  pub fn is_rom(&self) -> bool {
+    !self.writable
+  }
+
+  pub fn len(&self) -> usize {
+    self.data.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.data.is_empty()
+  }
+
+  pub fn data(&self) -> &[Byte] {
+    &self.data
+  }
+
+  // bypasses write protection, for loaders and debuggers
+  pub fn data_mut(&mut self) -> &mut [Byte] {
+    &mut self.data
+  }
+
+  fn reset(&mut self) {
+    if self.writable {
+      for byte in &mut self.data {
+        *byte = 0;
+      }
+    }
+  }
+}
+
+// a range of the cpu address space backed by one of several banks,
+// `None` lets the flat memory underneath show through
+pub struct BankWindow {
+  start: Word,
+  len: usize,
+  banks: Vec<Bank>,
+  selected: Option<usize>,
+  initial: Option<usize>,
+}
+
+impl BankWindow {
+  pub fn new(start: Word, len: usize, banks: Vec<Bank>, selected: Option<usize>) -> Self {
+    Self {
+      start,
+      len,
+      banks,
+      selected,
+      initial: selected,
+    }
+  }
+
+  pub fn start(&self) -> Word {
+    self.start
+  }
+
+  pub fn len(&self) -> usize {
+    self.len
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  pub fn contains(&self, addr: Word) -> bool {
+    addr >= self.start && ((addr - self.start) as usize) < self.len
+  }
+
+  pub fn selected(&self) -> Option<usize> {
+    self.selected
+  }
+
+  pub fn select(&mut self, bank: Option<usize>) -> Result<(), Error> {
+    if let Some(idx) = bank {
+      self.bank(idx)?;
+    }
+    self.selected = bank;
+    Ok(())
+  }
+
+  pub fn banks(&self) -> &[Bank] {
+    &self.banks
+  }
+
+  pub fn bank(&self, idx: usize) -> Result<&Bank, Error> {
+    let banks = self.banks.len();
+    self
+      .banks
+      .get(idx)
+      .ok_or(Error::NoBank { bank: idx, banks })
+  }
+
+  pub fn bank_mut(&mut self, idx: usize) -> Result<&mut Bank, Error> {
+    let banks = self.banks.len();
+    self
+      .banks
+      .get_mut(idx)
+      .ok_or(Error::NoBank { bank: idx, banks })
+  }
+
+  pub fn reset(&mut self) {
+    for bank in &mut self.banks {
+      bank.reset();
+    }
+    self.selected = self.initial;
+  }
+
+  // returns `None` when the access should go to the flat memory instead
+  pub fn read(&self, addr: Word) -> Option<Byte> {
+    let bank = self.banks.get(self.selected?)?;
+    bank.data.get((addr - self.start) as usize).copied()
+  }
+
+  // writes to a ROM bank fall through to the RAM underneath, like the c64 does
+  pub fn write(&mut self, addr: Word, data: Byte) -> bool {
+    let offset = (addr - self.start) as usize;
+    match self.selected.and_then(|idx| self.banks.get_mut(idx)) {
+      Some(bank) if bank.writable && offset < bank.data.len() => {
+        bank.data[offset] = data;
+        true
+      }
+      _ => false,
+    }
+  }
+}
+
+// a latch that selects the bank of a window when written to,
+// `data & mask` is the bank index and wraps around the number of banks
+pub struct BankRegister {
+  pub addr: Word,
+  pub window: usize,
+  pub mask: Byte,
+}
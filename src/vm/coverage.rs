@@ -0,0 +1,87 @@
+#[cfg(feature = "alloc")]
+use alloc::{collections::BTreeMap, string::String, vec, vec::Vec};
+#[cfg(feature = "alloc")]
+use core::fmt::Write;
+
+use bitflags::bitflags;
+
+#[cfg(feature = "alloc")]
+use super::{
+  defs::{Word, MEM_SIZE},
+  symbols::Symbols,
+};
+
+bitflags! {
+  #[derive(Default)]
+  pub struct Access: u8 {
+    const OPCODE = 0b0001; // executed as an opcode
+    const OPERAND = 0b0010; // fetched as an instruction's operand
+    const READ = 0b0100; // read as data
+    const WRITTEN = 0b1000;
+    const DUMMY = 0b1_0000; // a bus cycle whose data the cpu doesn't use, observers only
+  }
+}
+
+// how every byte of memory has been used since coverage started
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone)]
+pub struct Coverage {
+  bytes: Vec<Access>,
+}
+
+#[cfg(feature = "alloc")]
+impl Default for Coverage {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+#[cfg(feature = "alloc")]
+impl Coverage {
+  pub fn new() -> Self {
+    Self {
+      bytes: vec![Access::empty(); MEM_SIZE],
+    }
+  }
+
+  pub fn mark(&mut self, addr: Word, access: Access) {
+    self.bytes[addr as usize] |= access;
+  }
+
+  pub fn get(&self, addr: Word) -> Access {
+    self.bytes[addr as usize]
+  }
+
+  // the bytes that were used in any of the `access` ways
+  pub fn count(&self, access: Access) -> usize {
+    self.bytes.iter().filter(|b| b.intersects(access)).count()
+  }
+
+  // an LCOV tracefile of the source lines in `symbols`, a line is hit when any of its
+  // bytes were executed, read or written
+  pub fn lcov(&self, symbols: &Symbols, test_name: &str) -> String {
+    let mut files: BTreeMap<&str, BTreeMap<u32, bool>> = BTreeMap::new();
+    for line in symbols.lines() {
+      let hit = (0..line.len).any(|i| !self.get(line.addr.wrapping_add(i)).is_empty());
+      // a line with more than one span is hit if any of them are
+      *files
+        .entry(&line.file)
+        .or_default()
+        .entry(line.line)
+        .or_default() |= hit;
+    }
+
+    let mut out = String::new();
+    for (file, lines) in files {
+      let _ = writeln!(out, "TN:{}", test_name);
+      let _ = writeln!(out, "SF:{}", file);
+      for (line, &hit) in &lines {
+        let _ = writeln!(out, "DA:{},{}", line, hit as u8);
+      }
+      let _ = writeln!(out, "LF:{}", lines.len());
+      let _ = writeln!(out, "LH:{}", lines.values().filter(|&&hit| hit).count());
+      let _ = writeln!(out, "end_of_record");
+    }
+    out
+  }
+}
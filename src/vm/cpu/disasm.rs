@@ -0,0 +1,103 @@
+use alloc::{
+  format,
+  string::{String, ToString},
+  vec::Vec,
+};
+
+use super::{
+  instructions::{Instruction, ADDR_MODE},
+  CPU,
+};
+use crate::vm::{
+  defs::{Byte, Word},
+  mem::Mem,
+  symbols::Symbols,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Disassembly {
+  pub addr: Word,
+  pub bytes: Vec<Byte>,
+  // mnemonic and operand, the undocumented opcodes have their usual names
+  pub text: String,
+}
+
+impl Disassembly {
+  // address of the next instruction
+  pub fn next(&self) -> Word {
+    self.addr.wrapping_add(self.bytes.len() as Word)
+  }
+}
+
+// operand bytes that follow the opcode
+fn operand_len(mode: &ADDR_MODE) -> Word {
+  match mode {
+    ADDR_MODE::IMPLIED | ADDR_MODE::NONE => 0,
+    ADDR_MODE::ABSOLUTE | ADDR_MODE::ABSOLUTE_X | ADDR_MODE::ABSOLUTE_Y | ADDR_MODE::INDIRECT => 2,
+    _ => 1,
+  }
+}
+
+// decodes the instruction at `addr`, memory is only peeked so devices don't see the reads
+pub fn disassemble(mem: &Mem, addr: Word) -> Disassembly {
+  disassemble_with(mem, addr, None)
+}
+
+// like disassemble, with labels in place of the addresses that have one
+pub fn disassemble_with(mem: &Mem, addr: Word, symbols: Option<&Symbols>) -> Disassembly {
+  let ins = Instruction::from_op_code(mem.peek(addr));
+  let len = operand_len(&ins.addr_mode);
+  let bytes: Vec<Byte> = (0..=len).map(|i| mem.peek(addr.wrapping_add(i))).collect();
+
+  let byte = bytes.get(1).copied().unwrap_or(0);
+  let word = (bytes.get(2).copied().unwrap_or(0) as Word) << 8 | byte as Word;
+  let name = |addr: Word, digits: usize| match symbols.and_then(|s| s.label(addr)) {
+    Some(label) => label.to_string(),
+    None => format!("${:01$X}", addr, digits),
+  };
+  let operand = match ins.addr_mode {
+    ADDR_MODE::IMMEDIATE => format!("#${:02X}", byte),
+    ADDR_MODE::ZERO_PAGE => name(byte as Word, 2),
+    ADDR_MODE::ZERO_PAGE_X => format!("{},X", name(byte as Word, 2)),
+    ADDR_MODE::ZERO_PAGE_Y => format!("{},Y", name(byte as Word, 2)),
+    ADDR_MODE::ABSOLUTE => name(word, 4),
+    ADDR_MODE::ABSOLUTE_X => format!("{},X", name(word, 4)),
+    ADDR_MODE::ABSOLUTE_Y => format!("{},Y", name(word, 4)),
+    ADDR_MODE::INDIRECT => format!("({})", name(word, 4)),
+    ADDR_MODE::INDIRECT_X => format!("({},X)", name(byte as Word, 2)),
+    ADDR_MODE::INDIRECT_Y => format!("({}),Y", name(byte as Word, 2)),
+    ADDR_MODE::RELATIVE => name(addr.wrapping_add(2).wrapping_add(byte as i8 as Word), 4),
+    // the shifts and rotates on the accumulator are implied in the table
+    ADDR_MODE::IMPLIED if matches!(ins.opcode, 0x0A | 0x2A | 0x4A | 0x6A) => String::from("A"),
+    ADDR_MODE::IMPLIED | ADDR_MODE::NONE => String::new(),
+  };
+
+  let text = if operand.is_empty() {
+    ins.name.to_string()
+  } else {
+    format!("{} {}", ins.name, operand)
+  };
+
+  Disassembly { addr, bytes, text }
+}
+
+// one line of an execution trace, the instruction about to run and the registers before it
+pub fn trace_line(cpu: &CPU, cycles: u64, symbols: Option<&Symbols>) -> String {
+  let ins = disassemble_with(&cpu.mem, cpu.pc, symbols);
+  let bytes: Vec<String> = ins.bytes.iter().map(|b| format!("{:02X}", b)).collect();
+  let label = symbols.and_then(|s| s.label(cpu.pc)).unwrap_or("");
+
+  format!(
+    "{:04X} {:<12} {:<9} {:<16} A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X} CYC:{}",
+    cpu.pc,
+    label,
+    bytes.join(" "),
+    ins.text,
+    cpu.reg_a,
+    cpu.reg_x,
+    cpu.reg_y,
+    cpu.status.bits(),
+    cpu.sp,
+    cycles
+  )
+}
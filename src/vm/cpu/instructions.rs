@@ -1,5 +1,6 @@
 use super::{CpuStatus, CPU};
 
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
 #[derive(PartialEq, Eq, Clone)]
 pub enum ADDR_MODE {
   IMMEDIATE,
@@ -19,6 +20,7 @@ pub enum ADDR_MODE {
 }
 
 #[rustfmt::skip]
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Clone)]
 pub enum OPS {
   ADC, AND, ASL, BCC,
@@ -35,13 +37,22 @@ pub enum OPS {
   SEC, SED, SEI, STA,
   STX, STY, TAX, TAY,
   TSX, TXA, TXS, TYA,
-  
-  XXX
+
+  // undocumented, XXX are the NOPs that read their operand
+  SLO, RLA, SRE, RRA,
+  SAX, LAX, DCP, ISC,
+  ANC, ALR, ARR, ANE,
+  LXA, SBX, USBC, LAS,
+  SHA, SHX, SHY, TAS,
+  JAM, XXX
 }
 
+// an interrupt sequence pushes the pc and the status on its third to fifth cycles
+pub const INTERRUPT_WRITES: u8 = 0b0001_1100;
+
 #[derive(Clone)]
 pub struct Instruction {
-  pub name: String,
+  pub name: &'static str,
   pub opcode: u8,
   pub opr: OPS,
   pub addr_mode: ADDR_MODE,
@@ -51,7 +62,7 @@ pub struct Instruction {
 impl Default for Instruction {
   fn default() -> Self {
     Self {
-      name: String::from("NO NAME"),
+      name: "NO NAME",
       opcode: 0x00,
       opr: OPS::XXX,
       addr_mode: ADDR_MODE::NONE,
@@ -61,1096 +72,1898 @@ impl Default for Instruction {
 }
 
 impl Instruction {
+  // one of the undocumented opcodes
+  pub fn is_illegal(&self) -> bool {
+    matches!(
+      self.opr,
+      OPS::SLO
+        | OPS::RLA
+        | OPS::SRE
+        | OPS::RRA
+        | OPS::SAX
+        | OPS::LAX
+        | OPS::DCP
+        | OPS::ISC
+        | OPS::ANC
+        | OPS::ALR
+        | OPS::ARR
+        | OPS::ANE
+        | OPS::LXA
+        | OPS::SBX
+        | OPS::USBC
+        | OPS::LAS
+        | OPS::SHA
+        | OPS::SHX
+        | OPS::SHY
+        | OPS::TAS
+        | OPS::JAM
+        | OPS::XXX
+    )
+  }
+
+  // which of the instruction's `cycles` write to the bus, a bit per cycle from the
+  // opcode fetch on. RDY can't stop the cpu on them
+  pub fn write_cycles(&self, cycles: u8) -> u8 {
+    let last = 1 << (cycles.clamp(1, 8) - 1);
+    match self.opr {
+      OPS::STA
+      | OPS::STX
+      | OPS::STY
+      | OPS::SAX
+      | OPS::SHA
+      | OPS::SHX
+      | OPS::SHY
+      | OPS::TAS
+      | OPS::PHA
+      | OPS::PHP => last,
+      // the unmodified value is written back before the result
+      _ if self.is_read_modify_write() => last | last >> 1,
+      // the return address, then the status for BRK
+      OPS::JSR => 0b0001_1000,
+      OPS::BRK => INTERRUPT_WRITES,
+      _ => 0,
+    }
+  }
+
+  pub fn is_read_modify_write(&self) -> bool {
+    matches!(
+      self.opr,
+      OPS::ASL
+        | OPS::LSR
+        | OPS::ROL
+        | OPS::ROR
+        | OPS::INC
+        | OPS::DEC
+        | OPS::SLO
+        | OPS::RLA
+        | OPS::SRE
+        | OPS::RRA
+        | OPS::DCP
+        | OPS::ISC
+    ) && self.addr_mode != ADDR_MODE::IMPLIED
+  }
+
   // write a function that returns an instruction based on the op code
   pub fn from_op_code(op_code: u8) -> Self {
     match op_code {
       0x69 => Self {
-        name: String::from("ADC"),
+        name: "ADC",
         opcode: 0x69,
         opr: OPS::ADC,
         addr_mode: ADDR_MODE::IMMEDIATE,
         cycles: 2,
       },
       0x65 => Self {
-        name: String::from("ADC"),
+        name: "ADC",
         opcode: 0x65,
         opr: OPS::ADC,
         addr_mode: ADDR_MODE::ZERO_PAGE,
         cycles: 3,
       },
       0x75 => Self {
-        name: String::from("ADC"),
+        name: "ADC",
         opcode: 0x75,
         opr: OPS::ADC,
         addr_mode: ADDR_MODE::ZERO_PAGE_X,
         cycles: 4,
       },
       0x6D => Self {
-        name: String::from("ADC"),
+        name: "ADC",
         opcode: 0x6D,
         opr: OPS::ADC,
         addr_mode: ADDR_MODE::ABSOLUTE,
         cycles: 4,
       },
       0x7D => Self {
-        name: String::from("ADC"),
+        name: "ADC",
         opcode: 0x7D,
         opr: OPS::ADC,
         addr_mode: ADDR_MODE::ABSOLUTE_X,
         cycles: 4,
       },
       0x79 => Self {
-        name: String::from("ADC"),
+        name: "ADC",
         opcode: 0x79,
         opr: OPS::ADC,
         addr_mode: ADDR_MODE::ABSOLUTE_Y,
         cycles: 4,
       },
       0x61 => Self {
-        name: String::from("ADC"),
+        name: "ADC",
         opcode: 0x61,
         opr: OPS::ADC,
         addr_mode: ADDR_MODE::INDIRECT_X,
         cycles: 6,
       },
       0x71 => Self {
-        name: String::from("ADC"),
+        name: "ADC",
         opcode: 0x71,
         opr: OPS::ADC,
         addr_mode: ADDR_MODE::INDIRECT_Y,
         cycles: 5,
       },
       0x29 => Self {
-        name: String::from("AND"),
+        name: "AND",
         opcode: 0x29,
         opr: OPS::AND,
         addr_mode: ADDR_MODE::IMMEDIATE,
         cycles: 2,
       },
       0x25 => Self {
-        name: String::from("AND"),
+        name: "AND",
         opcode: 0x25,
         opr: OPS::AND,
         addr_mode: ADDR_MODE::ZERO_PAGE,
         cycles: 3,
       },
       0x35 => Self {
-        name: String::from("AND"),
+        name: "AND",
         opcode: 0x35,
         opr: OPS::AND,
         addr_mode: ADDR_MODE::ZERO_PAGE_X,
         cycles: 4,
       },
       0x2D => Self {
-        name: String::from("AND"),
+        name: "AND",
         opcode: 0x2D,
         opr: OPS::AND,
         addr_mode: ADDR_MODE::ABSOLUTE,
         cycles: 4,
       },
       0x3D => Self {
-        name: String::from("AND"),
+        name: "AND",
         opcode: 0x3D,
         opr: OPS::AND,
         addr_mode: ADDR_MODE::ABSOLUTE_X,
         cycles: 4,
       },
       0x39 => Self {
-        name: String::from("AND"),
+        name: "AND",
         opcode: 0x39,
         opr: OPS::AND,
         addr_mode: ADDR_MODE::ABSOLUTE_Y,
         cycles: 4,
       },
       0x21 => Self {
-        name: String::from("AND"),
+        name: "AND",
         opcode: 0x21,
         opr: OPS::AND,
         addr_mode: ADDR_MODE::INDIRECT_X,
         cycles: 6,
       },
       0x31 => Self {
-        name: String::from("AND"),
+        name: "AND",
         opcode: 0x31,
         opr: OPS::AND,
         addr_mode: ADDR_MODE::INDIRECT_Y,
         cycles: 5,
       },
       0x0A => Self {
-        name: String::from("ASL"),
+        name: "ASL",
         opcode: 0x0A,
         opr: OPS::ASL,
         addr_mode: ADDR_MODE::IMPLIED,
         cycles: 2,
       },
       0x06 => Self {
-        name: String::from("ASL"),
+        name: "ASL",
         opcode: 0x06,
         opr: OPS::ASL,
         addr_mode: ADDR_MODE::ZERO_PAGE,
         cycles: 5,
       },
       0x16 => Self {
-        name: String::from("ASL"),
+        name: "ASL",
         opcode: 0x16,
         opr: OPS::ASL,
         addr_mode: ADDR_MODE::ZERO_PAGE_X,
         cycles: 6,
       },
       0x0E => Self {
-        name: String::from("ASL"),
+        name: "ASL",
         opcode: 0x0E,
         opr: OPS::ASL,
         addr_mode: ADDR_MODE::ABSOLUTE,
         cycles: 6,
       },
       0x1E => Self {
-        name: String::from("ASL"),
+        name: "ASL",
         opcode: 0x1E,
         opr: OPS::ASL,
         addr_mode: ADDR_MODE::ABSOLUTE_X,
         cycles: 7,
       },
       0x90 => Self {
-        name: String::from("BCC"),
+        name: "BCC",
         opcode: 0x90,
         opr: OPS::BCC,
         addr_mode: ADDR_MODE::RELATIVE,
         cycles: 2,
       },
       0xB0 => Self {
-        name: String::from("BCS"),
+        name: "BCS",
         opcode: 0xB0,
         opr: OPS::BCS,
         addr_mode: ADDR_MODE::RELATIVE,
         cycles: 2,
       },
       0xF0 => Self {
-        name: String::from("BEQ"),
+        name: "BEQ",
         opcode: 0xF0,
         opr: OPS::BEQ,
         addr_mode: ADDR_MODE::RELATIVE,
         cycles: 2,
       },
       0x24 => Self {
-        name: String::from("BIT"),
+        name: "BIT",
         opcode: 0x24,
         opr: OPS::BIT,
         addr_mode: ADDR_MODE::ZERO_PAGE,
         cycles: 3,
       },
       0x2C => Self {
-        name: String::from("BIT"),
+        name: "BIT",
         opcode: 0x2C,
         opr: OPS::BIT,
         addr_mode: ADDR_MODE::ABSOLUTE,
         cycles: 4,
       },
       0x30 => Self {
-        name: String::from("BMI"),
+        name: "BMI",
         opcode: 0x30,
         opr: OPS::BMI,
         addr_mode: ADDR_MODE::RELATIVE,
         cycles: 2,
       },
       0xD0 => Self {
-        name: String::from("BNE"),
+        name: "BNE",
         opcode: 0xD0,
         opr: OPS::BNE,
         addr_mode: ADDR_MODE::RELATIVE,
         cycles: 2,
       },
       0x10 => Self {
-        name: String::from("BPL"),
+        name: "BPL",
         opcode: 0x10,
         opr: OPS::BPL,
         addr_mode: ADDR_MODE::RELATIVE,
         cycles: 2,
       },
       0x00 => Self {
-        name: String::from("BRK"),
+        name: "BRK",
         opcode: 0x00,
         opr: OPS::BRK,
         addr_mode: ADDR_MODE::IMPLIED,
         cycles: 7,
       },
       0x50 => Self {
-        name: String::from("BVC"),
+        name: "BVC",
         opcode: 0x50,
         opr: OPS::BVC,
         addr_mode: ADDR_MODE::RELATIVE,
         cycles: 2,
       },
       0x70 => Self {
-        name: String::from("BVS"),
+        name: "BVS",
         opcode: 0x70,
         opr: OPS::BVS,
         addr_mode: ADDR_MODE::RELATIVE,
         cycles: 2,
       },
       0x18 => Self {
-        name: String::from("CLC"),
+        name: "CLC",
         opcode: 0x18,
         opr: OPS::CLC,
         addr_mode: ADDR_MODE::IMPLIED,
         cycles: 2,
       },
       0xD8 => Self {
-        name: String::from("CLD"),
+        name: "CLD",
         opcode: 0xD8,
         opr: OPS::CLD,
         addr_mode: ADDR_MODE::IMPLIED,
         cycles: 2,
       },
       0x58 => Self {
-        name: String::from("CLI"),
+        name: "CLI",
         opcode: 0x58,
         opr: OPS::CLI,
         addr_mode: ADDR_MODE::IMPLIED,
         cycles: 2,
       },
       0xB8 => Self {
-        name: String::from("CLV"),
+        name: "CLV",
         opcode: 0xB8,
         opr: OPS::CLV,
         addr_mode: ADDR_MODE::IMPLIED,
         cycles: 2,
       },
       0xC9 => Self {
-        name: String::from("CMP"),
+        name: "CMP",
         opcode: 0xC9,
         opr: OPS::CMP,
         addr_mode: ADDR_MODE::IMMEDIATE,
         cycles: 2,
       },
       0xC5 => Self {
-        name: String::from("CMP"),
+        name: "CMP",
         opcode: 0xC5,
         opr: OPS::CMP,
         addr_mode: ADDR_MODE::ZERO_PAGE,
         cycles: 3,
       },
       0xD5 => Self {
-        name: String::from("CMP"),
+        name: "CMP",
         opcode: 0xD5,
         opr: OPS::CMP,
         addr_mode: ADDR_MODE::ZERO_PAGE_X,
         cycles: 4,
       },
       0xCD => Self {
-        name: String::from("CMP"),
+        name: "CMP",
         opcode: 0xCD,
         opr: OPS::CMP,
         addr_mode: ADDR_MODE::ABSOLUTE,
         cycles: 4,
       },
       0xDD => Self {
-        name: String::from("CMP"),
+        name: "CMP",
         opcode: 0xDD,
         opr: OPS::CMP,
         addr_mode: ADDR_MODE::ABSOLUTE_X,
         cycles: 4,
       },
       0xD9 => Self {
-        name: String::from("CMP"),
+        name: "CMP",
         opcode: 0xD9,
         opr: OPS::CMP,
         addr_mode: ADDR_MODE::ABSOLUTE_Y,
         cycles: 4,
       },
       0xC1 => Self {
-        name: String::from("CMP"),
+        name: "CMP",
         opcode: 0xC1,
         opr: OPS::CMP,
         addr_mode: ADDR_MODE::INDIRECT_X,
         cycles: 6,
       },
       0xD1 => Self {
-        name: String::from("CMP"),
+        name: "CMP",
         opcode: 0xD1,
         opr: OPS::CMP,
         addr_mode: ADDR_MODE::INDIRECT_Y,
         cycles: 5,
       },
       0xE0 => Self {
-        name: String::from("CPX"),
+        name: "CPX",
         opcode: 0xE0,
         opr: OPS::CPX,
         addr_mode: ADDR_MODE::IMMEDIATE,
         cycles: 2,
       },
       0xE4 => Self {
-        name: String::from("CPX"),
+        name: "CPX",
         opcode: 0xE4,
         opr: OPS::CPX,
         addr_mode: ADDR_MODE::ZERO_PAGE,
         cycles: 3,
       },
       0xEC => Self {
-        name: String::from("CPX"),
+        name: "CPX",
         opcode: 0xEC,
         opr: OPS::CPX,
         addr_mode: ADDR_MODE::ABSOLUTE,
         cycles: 4,
       },
       0xC0 => Self {
-        name: String::from("CPY"),
+        name: "CPY",
         opcode: 0xC0,
         opr: OPS::CPY,
         addr_mode: ADDR_MODE::IMMEDIATE,
         cycles: 2,
       },
       0xC4 => Self {
-        name: String::from("CPY"),
+        name: "CPY",
         opcode: 0xC4,
         opr: OPS::CPY,
         addr_mode: ADDR_MODE::ZERO_PAGE,
         cycles: 3,
       },
       0xCC => Self {
-        name: String::from("CPY"),
+        name: "CPY",
         opcode: 0xCC,
         opr: OPS::CPY,
         addr_mode: ADDR_MODE::ABSOLUTE,
         cycles: 4,
       },
       0xC6 => Self {
-        name: String::from("DEC"),
+        name: "DEC",
         opcode: 0xC6,
         opr: OPS::DEC,
         addr_mode: ADDR_MODE::ZERO_PAGE,
         cycles: 5,
       },
       0xD6 => Self {
-        name: String::from("DEC"),
+        name: "DEC",
         opcode: 0xD6,
         opr: OPS::DEC,
         addr_mode: ADDR_MODE::ZERO_PAGE_X,
         cycles: 6,
       },
       0xCE => Self {
-        name: String::from("DEC"),
+        name: "DEC",
         opcode: 0xCE,
         opr: OPS::DEC,
         addr_mode: ADDR_MODE::ABSOLUTE,
         cycles: 6,
       },
       0xDE => Self {
-        name: String::from("DEC"),
+        name: "DEC",
         opcode: 0xDE,
         opr: OPS::DEC,
         addr_mode: ADDR_MODE::ABSOLUTE_X,
         cycles: 7,
       },
       0xCA => Self {
-        name: String::from("DEX"),
+        name: "DEX",
         opcode: 0xCA,
         opr: OPS::DEX,
         addr_mode: ADDR_MODE::IMPLIED,
         cycles: 2,
       },
       0x88 => Self {
-        name: String::from("DEY"),
+        name: "DEY",
         opcode: 0x88,
         opr: OPS::DEY,
         addr_mode: ADDR_MODE::IMPLIED,
         cycles: 2,
       },
       0x49 => Self {
-        name: String::from("EOR"),
+        name: "EOR",
         opcode: 0x49,
         opr: OPS::EOR,
         addr_mode: ADDR_MODE::IMMEDIATE,
         cycles: 2,
       },
       0x45 => Self {
-        name: String::from("EOR"),
+        name: "EOR",
         opcode: 0x45,
         opr: OPS::EOR,
         addr_mode: ADDR_MODE::ZERO_PAGE,
         cycles: 3,
       },
       0x55 => Self {
-        name: String::from("EOR"),
+        name: "EOR",
         opcode: 0x55,
         opr: OPS::EOR,
         addr_mode: ADDR_MODE::ZERO_PAGE_X,
         cycles: 4,
       },
       0x4D => Self {
-        name: String::from("EOR"),
+        name: "EOR",
         opcode: 0x4D,
         opr: OPS::EOR,
         addr_mode: ADDR_MODE::ABSOLUTE,
         cycles: 4,
       },
       0x5D => Self {
-        name: String::from("EOR"),
+        name: "EOR",
         opcode: 0x5D,
         opr: OPS::EOR,
         addr_mode: ADDR_MODE::ABSOLUTE_X,
         cycles: 4,
       },
       0x59 => Self {
-        name: String::from("EOR"),
+        name: "EOR",
         opcode: 0x59,
         opr: OPS::EOR,
         addr_mode: ADDR_MODE::ABSOLUTE_Y,
         cycles: 4,
       },
       0x41 => Self {
-        name: String::from("EOR"),
+        name: "EOR",
         opcode: 0x41,
         opr: OPS::EOR,
         addr_mode: ADDR_MODE::INDIRECT_X,
         cycles: 6,
       },
       0x51 => Self {
-        name: String::from("EOR"),
+        name: "EOR",
         opcode: 0x51,
         opr: OPS::EOR,
         addr_mode: ADDR_MODE::INDIRECT_Y,
         cycles: 5,
       },
       0xE6 => Self {
-        name: String::from("INC"),
+        name: "INC",
         opcode: 0xE6,
         opr: OPS::INC,
         addr_mode: ADDR_MODE::ZERO_PAGE,
         cycles: 5,
       },
       0xF6 => Self {
-        name: String::from("INC"),
+        name: "INC",
         opcode: 0xF6,
         opr: OPS::INC,
         addr_mode: ADDR_MODE::ZERO_PAGE_X,
         cycles: 6,
       },
       0xEE => Self {
-        name: String::from("INC"),
+        name: "INC",
         opcode: 0xEE,
         opr: OPS::INC,
         addr_mode: ADDR_MODE::ABSOLUTE,
         cycles: 6,
       },
       0xFE => Self {
-        name: String::from("INC"),
+        name: "INC",
         opcode: 0xFE,
         opr: OPS::INC,
         addr_mode: ADDR_MODE::ABSOLUTE_X,
         cycles: 7,
       },
       0xE8 => Self {
-        name: String::from("INX"),
+        name: "INX",
         opcode: 0xE8,
         opr: OPS::INX,
         addr_mode: ADDR_MODE::IMPLIED,
         cycles: 2,
       },
       0xC8 => Self {
-        name: String::from("INY"),
+        name: "INY",
         opcode: 0xC8,
         opr: OPS::INY,
         addr_mode: ADDR_MODE::IMPLIED,
         cycles: 2,
       },
       0x4C => Self {
-        name: String::from("JMP"),
+        name: "JMP",
         opcode: 0x4C,
         opr: OPS::JMP,
         addr_mode: ADDR_MODE::ABSOLUTE,
         cycles: 3,
       },
       0x6C => Self {
-        name: String::from("JMP"),
+        name: "JMP",
         opcode: 0x6C,
         opr: OPS::JMP,
         addr_mode: ADDR_MODE::INDIRECT,
         cycles: 5,
       },
       0x20 => Self {
-        name: String::from("JSR"),
+        name: "JSR",
         opcode: 0x20,
         opr: OPS::JSR,
         addr_mode: ADDR_MODE::ABSOLUTE,
         cycles: 6,
       },
       0xA9 => Self {
-        name: String::from("LDA"),
+        name: "LDA",
         opcode: 0xA9,
         opr: OPS::LDA,
         addr_mode: ADDR_MODE::IMMEDIATE,
         cycles: 2,
       },
       0xA5 => Self {
-        name: String::from("LDA"),
+        name: "LDA",
         opcode: 0xA5,
         opr: OPS::LDA,
         addr_mode: ADDR_MODE::ZERO_PAGE,
         cycles: 3,
       },
       0xB5 => Self {
-        name: String::from("LDA"),
+        name: "LDA",
         opcode: 0xB5,
         opr: OPS::LDA,
         addr_mode: ADDR_MODE::ZERO_PAGE_X,
         cycles: 4,
       },
       0xAD => Self {
-        name: String::from("LDA"),
+        name: "LDA",
         opcode: 0xAD,
         opr: OPS::LDA,
         addr_mode: ADDR_MODE::ABSOLUTE,
         cycles: 4,
       },
       0xBD => Self {
-        name: String::from("LDA"),
+        name: "LDA",
         opcode: 0xBD,
         opr: OPS::LDA,
         addr_mode: ADDR_MODE::ABSOLUTE_X,
         cycles: 4,
       },
       0xB9 => Self {
-        name: String::from("LDA"),
+        name: "LDA",
         opcode: 0xB9,
         opr: OPS::LDA,
         addr_mode: ADDR_MODE::ABSOLUTE_Y,
         cycles: 4,
       },
       0xA1 => Self {
-        name: String::from("LDA"),
+        name: "LDA",
         opcode: 0xA1,
         opr: OPS::LDA,
         addr_mode: ADDR_MODE::INDIRECT_X,
         cycles: 6,
       },
       0xB1 => Self {
-        name: String::from("LDA"),
+        name: "LDA",
         opcode: 0xB1,
         opr: OPS::LDA,
         addr_mode: ADDR_MODE::INDIRECT_Y,
         cycles: 5,
       },
       0xA2 => Self {
-        name: String::from("LDX"),
+        name: "LDX",
         opcode: 0xA2,
         opr: OPS::LDX,
         addr_mode: ADDR_MODE::IMMEDIATE,
         cycles: 2,
       },
       0xA6 => Self {
-        name: String::from("LDX"),
+        name: "LDX",
         opcode: 0xA6,
         opr: OPS::LDX,
         addr_mode: ADDR_MODE::ZERO_PAGE,
         cycles: 3,
       },
       0xB6 => Self {
-        name: String::from("LDX"),
+        name: "LDX",
         opcode: 0xB6,
         opr: OPS::LDX,
         addr_mode: ADDR_MODE::ZERO_PAGE_Y,
         cycles: 4,
       },
       0xAE => Self {
-        name: String::from("LDX"),
+        name: "LDX",
         opcode: 0xAE,
         opr: OPS::LDX,
         addr_mode: ADDR_MODE::ABSOLUTE,
         cycles: 4,
       },
       0xBE => Self {
-        name: String::from("LDX"),
+        name: "LDX",
         opcode: 0xBE,
         opr: OPS::LDX,
         addr_mode: ADDR_MODE::ABSOLUTE_Y,
         cycles: 4,
       },
       0xA0 => Self {
-        name: String::from("LDY"),
+        name: "LDY",
         opcode: 0xA0,
         opr: OPS::LDY,
         addr_mode: ADDR_MODE::IMMEDIATE,
         cycles: 2,
       },
       0xA4 => Self {
-        name: String::from("LDY"),
+        name: "LDY",
         opcode: 0xA4,
         opr: OPS::LDY,
         addr_mode: ADDR_MODE::ZERO_PAGE,
         cycles: 3,
       },
       0xB4 => Self {
-        name: String::from("LDY"),
+        name: "LDY",
         opcode: 0xB4,
         opr: OPS::LDY,
         addr_mode: ADDR_MODE::ZERO_PAGE_X,
         cycles: 4,
       },
       0xAC => Self {
-        name: String::from("LDY"),
+        name: "LDY",
         opcode: 0xAC,
         opr: OPS::LDY,
         addr_mode: ADDR_MODE::ABSOLUTE,
         cycles: 4,
       },
       0xBC => Self {
-        name: String::from("LDY"),
+        name: "LDY",
         opcode: 0xBC,
         opr: OPS::LDY,
         addr_mode: ADDR_MODE::ABSOLUTE_X,
         cycles: 4,
       },
       0x4A => Self {
-        name: String::from("LSR"),
+        name: "LSR",
         opcode: 0x4A,
         opr: OPS::LSR,
         addr_mode: ADDR_MODE::IMPLIED,
         cycles: 2,
       },
       0x46 => Self {
-        name: String::from("LSR"),
+        name: "LSR",
         opcode: 0x46,
         opr: OPS::LSR,
         addr_mode: ADDR_MODE::ZERO_PAGE,
         cycles: 5,
       },
       0x56 => Self {
-        name: String::from("LSR"),
+        name: "LSR",
         opcode: 0x56,
         opr: OPS::LSR,
         addr_mode: ADDR_MODE::ZERO_PAGE_X,
         cycles: 6,
       },
       0x4E => Self {
-        name: String::from("LSR"),
+        name: "LSR",
         opcode: 0x4E,
         opr: OPS::LSR,
         addr_mode: ADDR_MODE::ABSOLUTE,
         cycles: 6,
       },
       0x5E => Self {
-        name: String::from("LSR"),
+        name: "LSR",
         opcode: 0x5E,
         opr: OPS::LSR,
         addr_mode: ADDR_MODE::ABSOLUTE_X,
         cycles: 7,
       },
       0xEA => Self {
-        name: String::from("NOP"),
+        name: "NOP",
         opcode: 0xEA,
         opr: OPS::NOP,
         addr_mode: ADDR_MODE::IMPLIED,
         cycles: 2,
       },
       0x09 => Self {
-        name: String::from("ORA"),
+        name: "ORA",
         opcode: 0x09,
         opr: OPS::ORA,
         addr_mode: ADDR_MODE::IMMEDIATE,
         cycles: 2,
       },
       0x05 => Self {
-        name: String::from("ORA"),
+        name: "ORA",
         opcode: 0x05,
         opr: OPS::ORA,
         addr_mode: ADDR_MODE::ZERO_PAGE,
         cycles: 3,
       },
       0x15 => Self {
-        name: String::from("ORA"),
+        name: "ORA",
         opcode: 0x15,
         opr: OPS::ORA,
         addr_mode: ADDR_MODE::ZERO_PAGE_X,
         cycles: 4,
       },
       0x0D => Self {
-        name: String::from("ORA"),
+        name: "ORA",
         opcode: 0x0D,
         opr: OPS::ORA,
         addr_mode: ADDR_MODE::ABSOLUTE,
         cycles: 4,
       },
       0x1D => Self {
-        name: String::from("ORA"),
+        name: "ORA",
         opcode: 0x1D,
         opr: OPS::ORA,
         addr_mode: ADDR_MODE::ABSOLUTE_X,
         cycles: 4,
       },
       0x19 => Self {
-        name: String::from("ORA"),
+        name: "ORA",
         opcode: 0x19,
         opr: OPS::ORA,
         addr_mode: ADDR_MODE::ABSOLUTE_Y,
         cycles: 4,
       },
       0x01 => Self {
-        name: String::from("ORA"),
+        name: "ORA",
         opcode: 0x01,
         opr: OPS::ORA,
         addr_mode: ADDR_MODE::INDIRECT_X,
         cycles: 6,
       },
       0x11 => Self {
-        name: String::from("ORA"),
+        name: "ORA",
         opcode: 0x11,
         opr: OPS::ORA,
         addr_mode: ADDR_MODE::INDIRECT_Y,
         cycles: 5,
       },
       0x48 => Self {
-        name: String::from("PHA"),
+        name: "PHA",
         opcode: 0x48,
         opr: OPS::PHA,
         addr_mode: ADDR_MODE::IMPLIED,
         cycles: 3,
       },
       0x08 => Self {
-        name: String::from("PHP"),
+        name: "PHP",
         opcode: 0x08,
         opr: OPS::PHP,
         addr_mode: ADDR_MODE::IMPLIED,
         cycles: 3,
       },
       0x68 => Self {
-        name: String::from("PLA"),
+        name: "PLA",
         opcode: 0x68,
         opr: OPS::PLA,
         addr_mode: ADDR_MODE::IMPLIED,
         cycles: 4,
       },
       0x28 => Self {
-        name: String::from("PLP"),
+        name: "PLP",
         opcode: 0x28,
         opr: OPS::PLP,
         addr_mode: ADDR_MODE::IMPLIED,
         cycles: 4,
       },
       0x2A => Self {
-        name: String::from("ROL"),
+        name: "ROL",
         opcode: 0x2A,
         opr: OPS::ROL,
         addr_mode: ADDR_MODE::IMPLIED,
         cycles: 2,
       },
       0x26 => Self {
-        name: String::from("ROL"),
+        name: "ROL",
         opcode: 0x26,
         opr: OPS::ROL,
         addr_mode: ADDR_MODE::ZERO_PAGE,
         cycles: 5,
       },
       0x36 => Self {
-        name: String::from("ROL"),
+        name: "ROL",
         opcode: 0x36,
         opr: OPS::ROL,
         addr_mode: ADDR_MODE::ZERO_PAGE_X,
         cycles: 6,
       },
       0x2E => Self {
-        name: String::from("ROL"),
+        name: "ROL",
         opcode: 0x2E,
         opr: OPS::ROL,
         addr_mode: ADDR_MODE::ABSOLUTE,
         cycles: 6,
       },
       0x3E => Self {
-        name: String::from("ROL"),
+        name: "ROL",
         opcode: 0x3E,
         opr: OPS::ROL,
         addr_mode: ADDR_MODE::ABSOLUTE_X,
         cycles: 7,
       },
       0x6A => Self {
-        name: String::from("ROR"),
+        name: "ROR",
         opcode: 0x6A,
         opr: OPS::ROR,
         addr_mode: ADDR_MODE::IMPLIED,
         cycles: 2,
       },
       0x66 => Self {
-        name: String::from("ROR"),
+        name: "ROR",
         opcode: 0x66,
         opr: OPS::ROR,
         addr_mode: ADDR_MODE::ZERO_PAGE,
         cycles: 5,
       },
       0x76 => Self {
-        name: String::from("ROR"),
+        name: "ROR",
         opcode: 0x76,
         opr: OPS::ROR,
         addr_mode: ADDR_MODE::ZERO_PAGE_X,
         cycles: 6,
       },
       0x6E => Self {
-        name: String::from("ROR"),
+        name: "ROR",
         opcode: 0x6E,
         opr: OPS::ROR,
         addr_mode: ADDR_MODE::ABSOLUTE,
         cycles: 6,
       },
       0x7E => Self {
-        name: String::from("ROR"),
+        name: "ROR",
         opcode: 0x7E,
         opr: OPS::ROR,
         addr_mode: ADDR_MODE::ABSOLUTE_X,
         cycles: 7,
       },
       0x40 => Self {
-        name: String::from("RTI"),
+        name: "RTI",
         opcode: 0x40,
         opr: OPS::RTI,
         addr_mode: ADDR_MODE::IMPLIED,
         cycles: 6,
       },
       0x60 => Self {
-        name: String::from("RTS"),
+        name: "RTS",
         opcode: 0x60,
         opr: OPS::RTS,
         addr_mode: ADDR_MODE::IMPLIED,
         cycles: 6,
       },
       0xE9 => Self {
-        name: String::from("SBC"),
+        name: "SBC",
         opcode: 0xE9,
         opr: OPS::SBC,
         addr_mode: ADDR_MODE::IMMEDIATE,
         cycles: 2,
       },
       0xE5 => Self {
-        name: String::from("SBC"),
+        name: "SBC",
         opcode: 0xE5,
         opr: OPS::SBC,
         addr_mode: ADDR_MODE::ZERO_PAGE,
         cycles: 3,
       },
       0xF5 => Self {
-        name: String::from("SBC"),
+        name: "SBC",
         opcode: 0xF5,
         opr: OPS::SBC,
         addr_mode: ADDR_MODE::ZERO_PAGE_X,
         cycles: 4,
       },
       0xED => Self {
-        name: String::from("SBC"),
+        name: "SBC",
         opcode: 0xED,
         opr: OPS::SBC,
         addr_mode: ADDR_MODE::ABSOLUTE,
         cycles: 4,
       },
       0xFD => Self {
-        name: String::from("SBC"),
+        name: "SBC",
         opcode: 0xFD,
         opr: OPS::SBC,
         addr_mode: ADDR_MODE::ABSOLUTE_X,
         cycles: 4,
       },
       0xF9 => Self {
-        name: String::from("SBC"),
+        name: "SBC",
         opcode: 0xF9,
         opr: OPS::SBC,
         addr_mode: ADDR_MODE::ABSOLUTE_Y,
         cycles: 4,
       },
       0xE1 => Self {
-        name: String::from("SBC"),
+        name: "SBC",
         opcode: 0xE1,
         opr: OPS::SBC,
         addr_mode: ADDR_MODE::INDIRECT_X,
         cycles: 6,
       },
       0xF1 => Self {
-        name: String::from("SBC"),
+        name: "SBC",
         opcode: 0xF1,
         opr: OPS::SBC,
         addr_mode: ADDR_MODE::INDIRECT_Y,
         cycles: 5,
       },
       0x38 => Self {
-        name: String::from("SEC"),
+        name: "SEC",
         opcode: 0x38,
         opr: OPS::SEC,
         addr_mode: ADDR_MODE::IMPLIED,
         cycles: 2,
       },
       0xF8 => Self {
-        name: String::from("SED"),
+        name: "SED",
         opcode: 0xF8,
         opr: OPS::SED,
         addr_mode: ADDR_MODE::IMPLIED,
         cycles: 2,
       },
       0x78 => Self {
-        name: String::from("SEI"),
+        name: "SEI",
         opcode: 0x78,
         opr: OPS::SEI,
         addr_mode: ADDR_MODE::IMPLIED,
         cycles: 2,
       },
       0x85 => Self {
-        name: String::from("STA"),
+        name: "STA",
         opcode: 0x85,
         opr: OPS::STA,
         addr_mode: ADDR_MODE::ZERO_PAGE,
         cycles: 3,
       },
       0x95 => Self {
-        name: String::from("STA"),
+        name: "STA",
         opcode: 0x95,
         opr: OPS::STA,
         addr_mode: ADDR_MODE::ZERO_PAGE_X,
         cycles: 4,
       },
       0x8D => Self {
-        name: String::from("STA"),
+        name: "STA",
         opcode: 0x8D,
         opr: OPS::STA,
         addr_mode: ADDR_MODE::ABSOLUTE,
         cycles: 4,
       },
       0x9D => Self {
-        name: String::from("STA"),
+        name: "STA",
         opcode: 0x9D,
         opr: OPS::STA,
         addr_mode: ADDR_MODE::ABSOLUTE_X,
         cycles: 5,
       },
       0x99 => Self {
-        name: String::from("STA"),
+        name: "STA",
         opcode: 0x99,
         opr: OPS::STA,
         addr_mode: ADDR_MODE::ABSOLUTE_Y,
         cycles: 5,
       },
       0x81 => Self {
-        name: String::from("STA"),
+        name: "STA",
         opcode: 0x81,
         opr: OPS::STA,
         addr_mode: ADDR_MODE::INDIRECT_X,
         cycles: 6,
       },
       0x91 => Self {
-        name: String::from("STA"),
+        name: "STA",
         opcode: 0x91,
         opr: OPS::STA,
         addr_mode: ADDR_MODE::INDIRECT_Y,
         cycles: 6,
       },
       0x86 => Self {
-        name: String::from("STX"),
+        name: "STX",
         opcode: 0x86,
         opr: OPS::STX,
         addr_mode: ADDR_MODE::ZERO_PAGE,
         cycles: 3,
       },
       0x96 => Self {
-        name: String::from("STX"),
+        name: "STX",
         opcode: 0x96,
         opr: OPS::STX,
         addr_mode: ADDR_MODE::ZERO_PAGE_Y,
         cycles: 4,
       },
       0x8E => Self {
-        name: String::from("STX"),
+        name: "STX",
         opcode: 0x8E,
         opr: OPS::STX,
         addr_mode: ADDR_MODE::ABSOLUTE,
         cycles: 4,
       },
       0x84 => Self {
-        name: String::from("STY"),
+        name: "STY",
         opcode: 0x84,
         opr: OPS::STY,
         addr_mode: ADDR_MODE::ZERO_PAGE,
         cycles: 3,
       },
       0x94 => Self {
-        name: String::from("STY"),
+        name: "STY",
         opcode: 0x94,
         opr: OPS::STY,
         addr_mode: ADDR_MODE::ZERO_PAGE_X,
         cycles: 4,
       },
       0x8C => Self {
-        name: String::from("STY"),
+        name: "STY",
         opcode: 0x8C,
         opr: OPS::STY,
         addr_mode: ADDR_MODE::ABSOLUTE,
         cycles: 4,
       },
       0xAA => Self {
-        name: String::from("TAX"),
+        name: "TAX",
         opcode: 0xAA,
         opr: OPS::TAX,
         addr_mode: ADDR_MODE::IMPLIED,
         cycles: 2,
       },
-      0xA8 => Self {
-        name: String::from("TAY"),
-        opcode: 0xA8,
-        opr: OPS::TAY,
+      0xA8 => Self {
+        name: "TAY",
+        opcode: 0xA8,
+        opr: OPS::TAY,
+        addr_mode: ADDR_MODE::IMPLIED,
+        cycles: 2,
+      },
+      0xBA => Self {
+        name: "TSX",
+        opcode: 0xBA,
+        opr: OPS::TSX,
+        addr_mode: ADDR_MODE::IMPLIED,
+        cycles: 2,
+      },
+      0x8A => Self {
+        name: "TXA",
+        opcode: 0x8A,
+        opr: OPS::TXA,
+        addr_mode: ADDR_MODE::IMPLIED,
+        cycles: 2,
+      },
+      0x9A => Self {
+        name: "TXS",
+        opcode: 0x9A,
+        opr: OPS::TXS,
+        addr_mode: ADDR_MODE::IMPLIED,
+        cycles: 2,
+      },
+      0x98 => Self {
+        name: "TYA",
+        opcode: 0x98,
+        opr: OPS::TYA,
+        addr_mode: ADDR_MODE::IMPLIED,
+        cycles: 2,
+      },
+      // the undocumented NMOS opcodes, what they run as depends on the cpu's policy
+      0x07 => Self {
+        name: "SLO",
+        opcode: 0x07,
+        opr: OPS::SLO,
+        addr_mode: ADDR_MODE::ZERO_PAGE,
+        cycles: 5,
+      },
+      0x17 => Self {
+        name: "SLO",
+        opcode: 0x17,
+        opr: OPS::SLO,
+        addr_mode: ADDR_MODE::ZERO_PAGE_X,
+        cycles: 6,
+      },
+      0x0F => Self {
+        name: "SLO",
+        opcode: 0x0F,
+        opr: OPS::SLO,
+        addr_mode: ADDR_MODE::ABSOLUTE,
+        cycles: 6,
+      },
+      0x1F => Self {
+        name: "SLO",
+        opcode: 0x1F,
+        opr: OPS::SLO,
+        addr_mode: ADDR_MODE::ABSOLUTE_X,
+        cycles: 7,
+      },
+      0x1B => Self {
+        name: "SLO",
+        opcode: 0x1B,
+        opr: OPS::SLO,
+        addr_mode: ADDR_MODE::ABSOLUTE_Y,
+        cycles: 7,
+      },
+      0x03 => Self {
+        name: "SLO",
+        opcode: 0x03,
+        opr: OPS::SLO,
+        addr_mode: ADDR_MODE::INDIRECT_X,
+        cycles: 8,
+      },
+      0x13 => Self {
+        name: "SLO",
+        opcode: 0x13,
+        opr: OPS::SLO,
+        addr_mode: ADDR_MODE::INDIRECT_Y,
+        cycles: 8,
+      },
+      0x27 => Self {
+        name: "RLA",
+        opcode: 0x27,
+        opr: OPS::RLA,
+        addr_mode: ADDR_MODE::ZERO_PAGE,
+        cycles: 5,
+      },
+      0x37 => Self {
+        name: "RLA",
+        opcode: 0x37,
+        opr: OPS::RLA,
+        addr_mode: ADDR_MODE::ZERO_PAGE_X,
+        cycles: 6,
+      },
+      0x2F => Self {
+        name: "RLA",
+        opcode: 0x2F,
+        opr: OPS::RLA,
+        addr_mode: ADDR_MODE::ABSOLUTE,
+        cycles: 6,
+      },
+      0x3F => Self {
+        name: "RLA",
+        opcode: 0x3F,
+        opr: OPS::RLA,
+        addr_mode: ADDR_MODE::ABSOLUTE_X,
+        cycles: 7,
+      },
+      0x3B => Self {
+        name: "RLA",
+        opcode: 0x3B,
+        opr: OPS::RLA,
+        addr_mode: ADDR_MODE::ABSOLUTE_Y,
+        cycles: 7,
+      },
+      0x23 => Self {
+        name: "RLA",
+        opcode: 0x23,
+        opr: OPS::RLA,
+        addr_mode: ADDR_MODE::INDIRECT_X,
+        cycles: 8,
+      },
+      0x33 => Self {
+        name: "RLA",
+        opcode: 0x33,
+        opr: OPS::RLA,
+        addr_mode: ADDR_MODE::INDIRECT_Y,
+        cycles: 8,
+      },
+      0x47 => Self {
+        name: "SRE",
+        opcode: 0x47,
+        opr: OPS::SRE,
+        addr_mode: ADDR_MODE::ZERO_PAGE,
+        cycles: 5,
+      },
+      0x57 => Self {
+        name: "SRE",
+        opcode: 0x57,
+        opr: OPS::SRE,
+        addr_mode: ADDR_MODE::ZERO_PAGE_X,
+        cycles: 6,
+      },
+      0x4F => Self {
+        name: "SRE",
+        opcode: 0x4F,
+        opr: OPS::SRE,
+        addr_mode: ADDR_MODE::ABSOLUTE,
+        cycles: 6,
+      },
+      0x5F => Self {
+        name: "SRE",
+        opcode: 0x5F,
+        opr: OPS::SRE,
+        addr_mode: ADDR_MODE::ABSOLUTE_X,
+        cycles: 7,
+      },
+      0x5B => Self {
+        name: "SRE",
+        opcode: 0x5B,
+        opr: OPS::SRE,
+        addr_mode: ADDR_MODE::ABSOLUTE_Y,
+        cycles: 7,
+      },
+      0x43 => Self {
+        name: "SRE",
+        opcode: 0x43,
+        opr: OPS::SRE,
+        addr_mode: ADDR_MODE::INDIRECT_X,
+        cycles: 8,
+      },
+      0x53 => Self {
+        name: "SRE",
+        opcode: 0x53,
+        opr: OPS::SRE,
+        addr_mode: ADDR_MODE::INDIRECT_Y,
+        cycles: 8,
+      },
+      0x67 => Self {
+        name: "RRA",
+        opcode: 0x67,
+        opr: OPS::RRA,
+        addr_mode: ADDR_MODE::ZERO_PAGE,
+        cycles: 5,
+      },
+      0x77 => Self {
+        name: "RRA",
+        opcode: 0x77,
+        opr: OPS::RRA,
+        addr_mode: ADDR_MODE::ZERO_PAGE_X,
+        cycles: 6,
+      },
+      0x6F => Self {
+        name: "RRA",
+        opcode: 0x6F,
+        opr: OPS::RRA,
+        addr_mode: ADDR_MODE::ABSOLUTE,
+        cycles: 6,
+      },
+      0x7F => Self {
+        name: "RRA",
+        opcode: 0x7F,
+        opr: OPS::RRA,
+        addr_mode: ADDR_MODE::ABSOLUTE_X,
+        cycles: 7,
+      },
+      0x7B => Self {
+        name: "RRA",
+        opcode: 0x7B,
+        opr: OPS::RRA,
+        addr_mode: ADDR_MODE::ABSOLUTE_Y,
+        cycles: 7,
+      },
+      0x63 => Self {
+        name: "RRA",
+        opcode: 0x63,
+        opr: OPS::RRA,
+        addr_mode: ADDR_MODE::INDIRECT_X,
+        cycles: 8,
+      },
+      0x73 => Self {
+        name: "RRA",
+        opcode: 0x73,
+        opr: OPS::RRA,
+        addr_mode: ADDR_MODE::INDIRECT_Y,
+        cycles: 8,
+      },
+      0x87 => Self {
+        name: "SAX",
+        opcode: 0x87,
+        opr: OPS::SAX,
+        addr_mode: ADDR_MODE::ZERO_PAGE,
+        cycles: 3,
+      },
+      0x97 => Self {
+        name: "SAX",
+        opcode: 0x97,
+        opr: OPS::SAX,
+        addr_mode: ADDR_MODE::ZERO_PAGE_Y,
+        cycles: 4,
+      },
+      0x8F => Self {
+        name: "SAX",
+        opcode: 0x8F,
+        opr: OPS::SAX,
+        addr_mode: ADDR_MODE::ABSOLUTE,
+        cycles: 4,
+      },
+      0x83 => Self {
+        name: "SAX",
+        opcode: 0x83,
+        opr: OPS::SAX,
+        addr_mode: ADDR_MODE::INDIRECT_X,
+        cycles: 6,
+      },
+      0xA7 => Self {
+        name: "LAX",
+        opcode: 0xA7,
+        opr: OPS::LAX,
+        addr_mode: ADDR_MODE::ZERO_PAGE,
+        cycles: 3,
+      },
+      0xB7 => Self {
+        name: "LAX",
+        opcode: 0xB7,
+        opr: OPS::LAX,
+        addr_mode: ADDR_MODE::ZERO_PAGE_Y,
+        cycles: 4,
+      },
+      0xAF => Self {
+        name: "LAX",
+        opcode: 0xAF,
+        opr: OPS::LAX,
+        addr_mode: ADDR_MODE::ABSOLUTE,
+        cycles: 4,
+      },
+      0xBF => Self {
+        name: "LAX",
+        opcode: 0xBF,
+        opr: OPS::LAX,
+        addr_mode: ADDR_MODE::ABSOLUTE_Y,
+        cycles: 4,
+      },
+      0xA3 => Self {
+        name: "LAX",
+        opcode: 0xA3,
+        opr: OPS::LAX,
+        addr_mode: ADDR_MODE::INDIRECT_X,
+        cycles: 6,
+      },
+      0xB3 => Self {
+        name: "LAX",
+        opcode: 0xB3,
+        opr: OPS::LAX,
+        addr_mode: ADDR_MODE::INDIRECT_Y,
+        cycles: 5,
+      },
+      0xC7 => Self {
+        name: "DCP",
+        opcode: 0xC7,
+        opr: OPS::DCP,
+        addr_mode: ADDR_MODE::ZERO_PAGE,
+        cycles: 5,
+      },
+      0xD7 => Self {
+        name: "DCP",
+        opcode: 0xD7,
+        opr: OPS::DCP,
+        addr_mode: ADDR_MODE::ZERO_PAGE_X,
+        cycles: 6,
+      },
+      0xCF => Self {
+        name: "DCP",
+        opcode: 0xCF,
+        opr: OPS::DCP,
+        addr_mode: ADDR_MODE::ABSOLUTE,
+        cycles: 6,
+      },
+      0xDF => Self {
+        name: "DCP",
+        opcode: 0xDF,
+        opr: OPS::DCP,
+        addr_mode: ADDR_MODE::ABSOLUTE_X,
+        cycles: 7,
+      },
+      0xDB => Self {
+        name: "DCP",
+        opcode: 0xDB,
+        opr: OPS::DCP,
+        addr_mode: ADDR_MODE::ABSOLUTE_Y,
+        cycles: 7,
+      },
+      0xC3 => Self {
+        name: "DCP",
+        opcode: 0xC3,
+        opr: OPS::DCP,
+        addr_mode: ADDR_MODE::INDIRECT_X,
+        cycles: 8,
+      },
+      0xD3 => Self {
+        name: "DCP",
+        opcode: 0xD3,
+        opr: OPS::DCP,
+        addr_mode: ADDR_MODE::INDIRECT_Y,
+        cycles: 8,
+      },
+      0xE7 => Self {
+        name: "ISC",
+        opcode: 0xE7,
+        opr: OPS::ISC,
+        addr_mode: ADDR_MODE::ZERO_PAGE,
+        cycles: 5,
+      },
+      0xF7 => Self {
+        name: "ISC",
+        opcode: 0xF7,
+        opr: OPS::ISC,
+        addr_mode: ADDR_MODE::ZERO_PAGE_X,
+        cycles: 6,
+      },
+      0xEF => Self {
+        name: "ISC",
+        opcode: 0xEF,
+        opr: OPS::ISC,
+        addr_mode: ADDR_MODE::ABSOLUTE,
+        cycles: 6,
+      },
+      0xFF => Self {
+        name: "ISC",
+        opcode: 0xFF,
+        opr: OPS::ISC,
+        addr_mode: ADDR_MODE::ABSOLUTE_X,
+        cycles: 7,
+      },
+      0xFB => Self {
+        name: "ISC",
+        opcode: 0xFB,
+        opr: OPS::ISC,
+        addr_mode: ADDR_MODE::ABSOLUTE_Y,
+        cycles: 7,
+      },
+      0xE3 => Self {
+        name: "ISC",
+        opcode: 0xE3,
+        opr: OPS::ISC,
+        addr_mode: ADDR_MODE::INDIRECT_X,
+        cycles: 8,
+      },
+      0xF3 => Self {
+        name: "ISC",
+        opcode: 0xF3,
+        opr: OPS::ISC,
+        addr_mode: ADDR_MODE::INDIRECT_Y,
+        cycles: 8,
+      },
+      0x0B => Self {
+        name: "ANC",
+        opcode: 0x0B,
+        opr: OPS::ANC,
+        addr_mode: ADDR_MODE::IMMEDIATE,
+        cycles: 2,
+      },
+      0x2B => Self {
+        name: "ANC",
+        opcode: 0x2B,
+        opr: OPS::ANC,
+        addr_mode: ADDR_MODE::IMMEDIATE,
+        cycles: 2,
+      },
+      0x4B => Self {
+        name: "ALR",
+        opcode: 0x4B,
+        opr: OPS::ALR,
+        addr_mode: ADDR_MODE::IMMEDIATE,
+        cycles: 2,
+      },
+      0x6B => Self {
+        name: "ARR",
+        opcode: 0x6B,
+        opr: OPS::ARR,
+        addr_mode: ADDR_MODE::IMMEDIATE,
+        cycles: 2,
+      },
+      0x8B => Self {
+        name: "ANE",
+        opcode: 0x8B,
+        opr: OPS::ANE,
+        addr_mode: ADDR_MODE::IMMEDIATE,
+        cycles: 2,
+      },
+      0xAB => Self {
+        name: "LXA",
+        opcode: 0xAB,
+        opr: OPS::LXA,
+        addr_mode: ADDR_MODE::IMMEDIATE,
+        cycles: 2,
+      },
+      0xCB => Self {
+        name: "SBX",
+        opcode: 0xCB,
+        opr: OPS::SBX,
+        addr_mode: ADDR_MODE::IMMEDIATE,
+        cycles: 2,
+      },
+      0xEB => Self {
+        name: "SBC",
+        opcode: 0xEB,
+        opr: OPS::USBC,
+        addr_mode: ADDR_MODE::IMMEDIATE,
+        cycles: 2,
+      },
+      0xBB => Self {
+        name: "LAS",
+        opcode: 0xBB,
+        opr: OPS::LAS,
+        addr_mode: ADDR_MODE::ABSOLUTE_Y,
+        cycles: 4,
+      },
+      0x93 => Self {
+        name: "SHA",
+        opcode: 0x93,
+        opr: OPS::SHA,
+        addr_mode: ADDR_MODE::INDIRECT_Y,
+        cycles: 6,
+      },
+      0x9F => Self {
+        name: "SHA",
+        opcode: 0x9F,
+        opr: OPS::SHA,
+        addr_mode: ADDR_MODE::ABSOLUTE_Y,
+        cycles: 5,
+      },
+      0x9E => Self {
+        name: "SHX",
+        opcode: 0x9E,
+        opr: OPS::SHX,
+        addr_mode: ADDR_MODE::ABSOLUTE_Y,
+        cycles: 5,
+      },
+      0x9C => Self {
+        name: "SHY",
+        opcode: 0x9C,
+        opr: OPS::SHY,
+        addr_mode: ADDR_MODE::ABSOLUTE_X,
+        cycles: 5,
+      },
+      0x9B => Self {
+        name: "TAS",
+        opcode: 0x9B,
+        opr: OPS::TAS,
+        addr_mode: ADDR_MODE::ABSOLUTE_Y,
+        cycles: 5,
+      },
+      0x1A => Self {
+        name: "NOP",
+        opcode: 0x1A,
+        opr: OPS::XXX,
+        addr_mode: ADDR_MODE::IMPLIED,
+        cycles: 2,
+      },
+      0x3A => Self {
+        name: "NOP",
+        opcode: 0x3A,
+        opr: OPS::XXX,
+        addr_mode: ADDR_MODE::IMPLIED,
+        cycles: 2,
+      },
+      0x5A => Self {
+        name: "NOP",
+        opcode: 0x5A,
+        opr: OPS::XXX,
+        addr_mode: ADDR_MODE::IMPLIED,
+        cycles: 2,
+      },
+      0x7A => Self {
+        name: "NOP",
+        opcode: 0x7A,
+        opr: OPS::XXX,
+        addr_mode: ADDR_MODE::IMPLIED,
+        cycles: 2,
+      },
+      0xDA => Self {
+        name: "NOP",
+        opcode: 0xDA,
+        opr: OPS::XXX,
+        addr_mode: ADDR_MODE::IMPLIED,
+        cycles: 2,
+      },
+      0xFA => Self {
+        name: "NOP",
+        opcode: 0xFA,
+        opr: OPS::XXX,
+        addr_mode: ADDR_MODE::IMPLIED,
+        cycles: 2,
+      },
+      0x80 => Self {
+        name: "NOP",
+        opcode: 0x80,
+        opr: OPS::XXX,
+        addr_mode: ADDR_MODE::IMMEDIATE,
+        cycles: 2,
+      },
+      0x82 => Self {
+        name: "NOP",
+        opcode: 0x82,
+        opr: OPS::XXX,
+        addr_mode: ADDR_MODE::IMMEDIATE,
+        cycles: 2,
+      },
+      0x89 => Self {
+        name: "NOP",
+        opcode: 0x89,
+        opr: OPS::XXX,
+        addr_mode: ADDR_MODE::IMMEDIATE,
+        cycles: 2,
+      },
+      0xC2 => Self {
+        name: "NOP",
+        opcode: 0xC2,
+        opr: OPS::XXX,
+        addr_mode: ADDR_MODE::IMMEDIATE,
+        cycles: 2,
+      },
+      0xE2 => Self {
+        name: "NOP",
+        opcode: 0xE2,
+        opr: OPS::XXX,
+        addr_mode: ADDR_MODE::IMMEDIATE,
+        cycles: 2,
+      },
+      0x04 => Self {
+        name: "NOP",
+        opcode: 0x04,
+        opr: OPS::XXX,
+        addr_mode: ADDR_MODE::ZERO_PAGE,
+        cycles: 3,
+      },
+      0x44 => Self {
+        name: "NOP",
+        opcode: 0x44,
+        opr: OPS::XXX,
+        addr_mode: ADDR_MODE::ZERO_PAGE,
+        cycles: 3,
+      },
+      0x64 => Self {
+        name: "NOP",
+        opcode: 0x64,
+        opr: OPS::XXX,
+        addr_mode: ADDR_MODE::ZERO_PAGE,
+        cycles: 3,
+      },
+      0x14 => Self {
+        name: "NOP",
+        opcode: 0x14,
+        opr: OPS::XXX,
+        addr_mode: ADDR_MODE::ZERO_PAGE_X,
+        cycles: 4,
+      },
+      0x34 => Self {
+        name: "NOP",
+        opcode: 0x34,
+        opr: OPS::XXX,
+        addr_mode: ADDR_MODE::ZERO_PAGE_X,
+        cycles: 4,
+      },
+      0x54 => Self {
+        name: "NOP",
+        opcode: 0x54,
+        opr: OPS::XXX,
+        addr_mode: ADDR_MODE::ZERO_PAGE_X,
+        cycles: 4,
+      },
+      0x74 => Self {
+        name: "NOP",
+        opcode: 0x74,
+        opr: OPS::XXX,
+        addr_mode: ADDR_MODE::ZERO_PAGE_X,
+        cycles: 4,
+      },
+      0xD4 => Self {
+        name: "NOP",
+        opcode: 0xD4,
+        opr: OPS::XXX,
+        addr_mode: ADDR_MODE::ZERO_PAGE_X,
+        cycles: 4,
+      },
+      0xF4 => Self {
+        name: "NOP",
+        opcode: 0xF4,
+        opr: OPS::XXX,
+        addr_mode: ADDR_MODE::ZERO_PAGE_X,
+        cycles: 4,
+      },
+      0x0C => Self {
+        name: "NOP",
+        opcode: 0x0C,
+        opr: OPS::XXX,
+        addr_mode: ADDR_MODE::ABSOLUTE,
+        cycles: 4,
+      },
+      0x1C => Self {
+        name: "NOP",
+        opcode: 0x1C,
+        opr: OPS::XXX,
+        addr_mode: ADDR_MODE::ABSOLUTE_X,
+        cycles: 4,
+      },
+      0x3C => Self {
+        name: "NOP",
+        opcode: 0x3C,
+        opr: OPS::XXX,
+        addr_mode: ADDR_MODE::ABSOLUTE_X,
+        cycles: 4,
+      },
+      0x5C => Self {
+        name: "NOP",
+        opcode: 0x5C,
+        opr: OPS::XXX,
+        addr_mode: ADDR_MODE::ABSOLUTE_X,
+        cycles: 4,
+      },
+      0x7C => Self {
+        name: "NOP",
+        opcode: 0x7C,
+        opr: OPS::XXX,
+        addr_mode: ADDR_MODE::ABSOLUTE_X,
+        cycles: 4,
+      },
+      0xDC => Self {
+        name: "NOP",
+        opcode: 0xDC,
+        opr: OPS::XXX,
+        addr_mode: ADDR_MODE::ABSOLUTE_X,
+        cycles: 4,
+      },
+      0xFC => Self {
+        name: "NOP",
+        opcode: 0xFC,
+        opr: OPS::XXX,
+        addr_mode: ADDR_MODE::ABSOLUTE_X,
+        cycles: 4,
+      },
+      0x02 => Self {
+        name: "JAM",
+        opcode: 0x02,
+        opr: OPS::JAM,
+        addr_mode: ADDR_MODE::IMPLIED,
+        cycles: 2,
+      },
+      0x12 => Self {
+        name: "JAM",
+        opcode: 0x12,
+        opr: OPS::JAM,
+        addr_mode: ADDR_MODE::IMPLIED,
+        cycles: 2,
+      },
+      0x22 => Self {
+        name: "JAM",
+        opcode: 0x22,
+        opr: OPS::JAM,
+        addr_mode: ADDR_MODE::IMPLIED,
+        cycles: 2,
+      },
+      0x32 => Self {
+        name: "JAM",
+        opcode: 0x32,
+        opr: OPS::JAM,
+        addr_mode: ADDR_MODE::IMPLIED,
+        cycles: 2,
+      },
+      0x42 => Self {
+        name: "JAM",
+        opcode: 0x42,
+        opr: OPS::JAM,
+        addr_mode: ADDR_MODE::IMPLIED,
+        cycles: 2,
+      },
+      0x52 => Self {
+        name: "JAM",
+        opcode: 0x52,
+        opr: OPS::JAM,
+        addr_mode: ADDR_MODE::IMPLIED,
+        cycles: 2,
+      },
+      0x62 => Self {
+        name: "JAM",
+        opcode: 0x62,
+        opr: OPS::JAM,
         addr_mode: ADDR_MODE::IMPLIED,
         cycles: 2,
       },
-      0xBA => Self {
-        name: String::from("TSX"),
-        opcode: 0xBA,
-        opr: OPS::TSX,
+      0x72 => Self {
+        name: "JAM",
+        opcode: 0x72,
+        opr: OPS::JAM,
         addr_mode: ADDR_MODE::IMPLIED,
         cycles: 2,
       },
-      0x8A => Self {
-        name: String::from("TXA"),
-        opcode: 0x8A,
-        opr: OPS::TXA,
+      0x92 => Self {
+        name: "JAM",
+        opcode: 0x92,
+        opr: OPS::JAM,
         addr_mode: ADDR_MODE::IMPLIED,
         cycles: 2,
       },
-      0x9A => Self {
-        name: String::from("TXS"),
-        opcode: 0x9A,
-        opr: OPS::TXS,
+      0xB2 => Self {
+        name: "JAM",
+        opcode: 0xB2,
+        opr: OPS::JAM,
         addr_mode: ADDR_MODE::IMPLIED,
         cycles: 2,
       },
-      0x98 => Self {
-        name: String::from("TYA"),
-        opcode: 0x98,
-        opr: OPS::TYA,
+      0xD2 => Self {
+        name: "JAM",
+        opcode: 0xD2,
+        opr: OPS::JAM,
         addr_mode: ADDR_MODE::IMPLIED,
         cycles: 2,
       },
-      _ => Self {
-        name: String::from("???"),
-        opcode: 0x00,
-        opr: OPS::NOP,
+      0xF2 => Self {
+        name: "JAM",
+        opcode: 0xF2,
+        opr: OPS::JAM,
         addr_mode: ADDR_MODE::IMPLIED,
-        cycles: 0,
+        cycles: 2,
       },
     }
   }
 }
 
 pub fn execute(cpu: &mut CPU, instruction: Instruction) -> u8 {
-  let mut addi_cycles: u8 = 0;
-  addi_cycles += match instruction.addr_mode {
+  let cycles = instruction.cycles;
+  let relative = instruction.addr_mode == ADDR_MODE::RELATIVE;
+  let addr_cycles = match instruction.addr_mode {
     ADDR_MODE::ABSOLUTE => abs(cpu),
     ADDR_MODE::ABSOLUTE_X => abx(cpu),
     ADDR_MODE::ABSOLUTE_Y => aby(cpu),
     ADDR_MODE::IMMEDIATE => imm(cpu),
     ADDR_MODE::IMPLIED => imp(cpu),
     ADDR_MODE::INDIRECT => ind(cpu),
-    ADDR_MODE::INDIRECT_X => inx(cpu),
-    ADDR_MODE::INDIRECT_Y => iny(cpu),
+    ADDR_MODE::INDIRECT_X => izx(cpu),
+    ADDR_MODE::INDIRECT_Y => izy(cpu),
     ADDR_MODE::RELATIVE => rel(cpu),
     ADDR_MODE::ZERO_PAGE => zp0(cpu),
     ADDR_MODE::ZERO_PAGE_X => zpx(cpu),
     ADDR_MODE::ZERO_PAGE_Y => zpy(cpu),
     _ => 0x00,
   };
+  let next = cpu.pc;
 
-  addi_cycles += match instruction.opr {
+  let op_cycles = match instruction.opr {
     OPS::ADC => adc(cpu),
     OPS::AND => and(cpu),
     OPS::ASL => asl(cpu),
@@ -1207,10 +2020,45 @@ pub fn execute(cpu: &mut CPU, instruction: Instruction) -> u8 {
     OPS::TXA => txa(cpu),
     OPS::TXS => txs(cpu),
     OPS::TYA => tya(cpu),
-    _ => 0x00,
+    OPS::SLO => slo(cpu),
+    OPS::RLA => rla(cpu),
+    OPS::SRE => sre(cpu),
+    OPS::RRA => rra(cpu),
+    OPS::SAX => sax(cpu),
+    OPS::LAX => lax(cpu),
+    OPS::DCP => dcp(cpu),
+    OPS::ISC => isc(cpu),
+    OPS::ANC => anc(cpu),
+    OPS::ALR => alr(cpu),
+    OPS::ARR => arr(cpu),
+    OPS::ANE => ane(cpu),
+    OPS::LXA => lxa(cpu),
+    OPS::SBX => sbx(cpu),
+    OPS::USBC => sbc(cpu),
+    OPS::LAS => las(cpu),
+    OPS::SHA => sha(cpu),
+    OPS::SHX => shx(cpu),
+    OPS::SHY => shy(cpu),
+    OPS::TAS => tas(cpu),
+    OPS::JAM => jam(cpu),
+    OPS::XXX => xxx(cpu),
   };
 
-  addi_cycles
+  // crossing a page only costs a cycle for ops that can take it,
+  // branches count their own extra cycles
+  if relative {
+    // a taken branch reads the next opcode, then the wrong page before pc's high byte
+    // is fixed
+    if op_cycles > 0 {
+      cpu.mem.dummy_read(next);
+    }
+    if op_cycles > 1 {
+      cpu.mem.dummy_read((next & 0xFF00) | (cpu.pc & 0x00FF));
+    }
+    cycles + op_cycles
+  } else {
+    cycles + (addr_cycles & op_cycles)
+  }
 }
 
 /* ------- addressing modes -------- */
@@ -1218,12 +2066,14 @@ pub fn execute(cpu: &mut CPU, instruction: Instruction) -> u8 {
 fn imm(cpu: &mut CPU) -> u8 {
   cpu.working_addr = cpu.pc;
 
-  cpu.pc += 1;
+  cpu.pc = cpu.pc.wrapping_add(1);
   0x00
 }
 
 // implied mode
 fn imp(cpu: &mut CPU) -> u8 {
+  // the byte after the opcode is read and thrown away
+  cpu.mem.dummy_read(cpu.pc);
   cpu.working_data = cpu.reg_a;
   0x00
 }
@@ -1237,14 +2087,19 @@ fn zp0(cpu: &mut CPU) -> u8 {
 
 // zero page x mode
 fn zpx(cpu: &mut CPU) -> u8 {
-  cpu.working_addr = (cpu.fetch() as u16 + cpu.reg_x as u16) & 0x00FF;
+  let addr = cpu.fetch() as u16;
+  // the unindexed address is read while x is added
+  cpu.mem.dummy_read(addr);
+  cpu.working_addr = (addr + cpu.reg_x as u16) & 0x00FF;
 
   0x00
 }
 
 // zero page y mode
 fn zpy(cpu: &mut CPU) -> u8 {
-  cpu.working_addr = (cpu.fetch() as u16 + cpu.reg_y as u16) & 0x00FF;
+  let addr = cpu.fetch() as u16;
+  cpu.mem.dummy_read(addr);
+  cpu.working_addr = (addr + cpu.reg_y as u16) & 0x00FF;
 
   0x00
 }
@@ -1259,27 +2114,17 @@ fn abs(cpu: &mut CPU) -> u8 {
 // absolute x mode
 fn abx(cpu: &mut CPU) -> u8 {
   let addr = cpu.fetch_word();
-  cpu.working_addr = addr + cpu.reg_x as u16;
+  cpu.working_addr = addr.wrapping_add(cpu.reg_x as u16);
 
-  // page boundary crossing
-  if (addr & 0xFF00) != (0xFF00 & cpu.working_addr) {
-    0x01
-  } else {
-    0x00
-  }
+  index_page(cpu, addr)
 }
 
 // absolute y mode
 fn aby(cpu: &mut CPU) -> u8 {
   let addr = cpu.fetch_word();
-  cpu.working_addr = addr + cpu.reg_y as u16;
+  cpu.working_addr = addr.wrapping_add(cpu.reg_y as u16);
 
-  // page boundary crossing
-  if (addr & 0xFF00) != (0xFF00 & cpu.working_addr) {
-    0x01
-  } else {
-    0x00
-  }
+  index_page(cpu, addr)
 }
 
 // indirect mode
@@ -1298,6 +2143,7 @@ fn ind(cpu: &mut CPU) -> u8 {
 // indirect zero page x mode
 fn izx(cpu: &mut CPU) -> u8 {
   let addr = cpu.fetch() as u16;
+  cpu.mem.dummy_read(addr);
   let lo = cpu.mem.read((addr + cpu.reg_x as u16) & 0x00FF) as u16;
   let hi = cpu.mem.read((addr + cpu.reg_x as u16 + 1) & 0x00FF) as u16;
   cpu.working_addr = lo | (hi << 8);
@@ -1310,10 +2156,24 @@ fn izy(cpu: &mut CPU) -> u8 {
   let addr = cpu.fetch() as u16;
   let lo = cpu.mem.read(addr & 0x00FF) as u16;
   let hi = cpu.mem.read((addr + 1) & 0x00FF) as u16;
-  cpu.working_addr = (lo | (hi << 8)) + cpu.reg_y as u16;
+  let base = lo | (hi << 8);
+  cpu.working_addr = base.wrapping_add(cpu.reg_y as u16);
+
+  index_page(cpu, base)
+}
+
+// 0x01 when indexing `base` crossed a page. the cpu reads the indexed address on
+// `base`'s page first, loads skip the fixed one when it's the same
+fn index_page(cpu: &mut CPU, base: u16) -> u8 {
+  let crossed = (base & 0xFF00) != (0xFF00 & cpu.working_addr);
+  let ins = &cpu.curr_instruction;
+  if crossed || ins.write_cycles(ins.cycles) != 0 {
+    let addr = (base & 0xFF00) | (cpu.working_addr & 0x00FF);
+    cpu.mem.dummy_read(addr);
+  }
 
   // page boundary crossing
-  if (addr & 0xFF00) != (0xFF00 & cpu.working_addr) {
+  if crossed {
     0x01
   } else {
     0x00
@@ -1322,10 +2182,10 @@ fn izy(cpu: &mut CPU) -> u8 {
 
 // relative mode
 fn rel(cpu: &mut CPU) -> u8 {
-  cpu.working_addr = cpu.fetch() as u16;
-  // page boundary crossing
-  if (cpu.working_addr & 0x80) == 0x80 {
-    cpu.working_addr |= 0xFF00;
+  cpu.rel_working_addr = cpu.fetch() as u16;
+  // sign extend the offset
+  if (cpu.rel_working_addr & 0x80) == 0x80 {
+    cpu.rel_working_addr |= 0xFF00;
   }
 
   0x00
@@ -1379,6 +2239,7 @@ fn asl(cpu: &mut CPU) -> u8 {
     cpu.status.clear_flag(CpuStatus::N);
   }
 
+  cpu.working_data = data;
   if cpu.curr_instruction.addr_mode == ADDR_MODE::IMPLIED {
     cpu.reg_a = data;
   } else {
@@ -1392,7 +2253,7 @@ fn asl(cpu: &mut CPU) -> u8 {
 fn bcs(cpu: &mut CPU) -> u8 {
   if cpu.status.is_flag_set(CpuStatus::C) {
     let mut cycles: u8 = 1;
-    let addr = cpu.pc + cpu.rel_working_addr;
+    let addr = cpu.pc.wrapping_add(cpu.rel_working_addr);
 
     // page boundary crossing
     if (addr & 0xFF00) != (cpu.pc & 0xFF00) {
@@ -1410,7 +2271,7 @@ fn bcs(cpu: &mut CPU) -> u8 {
 fn bcc(cpu: &mut CPU) -> u8 {
   if !cpu.status.is_flag_set(CpuStatus::C) {
     let mut cycles: u8 = 1;
-    let addr = cpu.pc + cpu.rel_working_addr;
+    let addr = cpu.pc.wrapping_add(cpu.rel_working_addr);
 
     // page boundary crossing
     if (addr & 0xFF00) != (cpu.pc & 0xFF00) {
@@ -1428,7 +2289,7 @@ fn bcc(cpu: &mut CPU) -> u8 {
 fn beq(cpu: &mut CPU) -> u8 {
   if cpu.status.is_flag_set(CpuStatus::Z) {
     let mut cycles: u8 = 1;
-    let addr = cpu.pc + cpu.rel_working_addr;
+    let addr = cpu.pc.wrapping_add(cpu.rel_working_addr);
 
     // page boundary crossing
     if (addr & 0xFF00) != (cpu.pc & 0xFF00) {
@@ -1446,7 +2307,7 @@ fn beq(cpu: &mut CPU) -> u8 {
 fn bmi(cpu: &mut CPU) -> u8 {
   if cpu.status.is_flag_set(CpuStatus::N) {
     let mut cycles: u8 = 1;
-    let addr = cpu.pc + cpu.rel_working_addr;
+    let addr = cpu.pc.wrapping_add(cpu.rel_working_addr);
 
     // page boundary crossing
     if (addr & 0xFF00) != (cpu.pc & 0xFF00) {
@@ -1464,7 +2325,7 @@ fn bmi(cpu: &mut CPU) -> u8 {
 fn bne(cpu: &mut CPU) -> u8 {
   if !cpu.status.is_flag_set(CpuStatus::Z) {
     let mut cycles: u8 = 1;
-    let addr = cpu.pc + cpu.rel_working_addr;
+    let addr = cpu.pc.wrapping_add(cpu.rel_working_addr);
 
     // page boundary crossing
     if (addr & 0xFF00) != (cpu.pc & 0xFF00) {
@@ -1482,7 +2343,7 @@ fn bne(cpu: &mut CPU) -> u8 {
 fn bpl(cpu: &mut CPU) -> u8 {
   if !cpu.status.is_flag_set(CpuStatus::N) {
     let mut cycles: u8 = 1;
-    let addr = cpu.pc + cpu.rel_working_addr;
+    let addr = cpu.pc.wrapping_add(cpu.rel_working_addr);
 
     // page boundary crossing
     if (addr & 0xFF00) != (cpu.pc & 0xFF00) {
@@ -1500,7 +2361,7 @@ fn bpl(cpu: &mut CPU) -> u8 {
 fn bvc(cpu: &mut CPU) -> u8 {
   if !cpu.status.is_flag_set(CpuStatus::V) {
     let mut cycles: u8 = 1;
-    let addr = cpu.pc + cpu.rel_working_addr;
+    let addr = cpu.pc.wrapping_add(cpu.rel_working_addr);
 
     // page boundary crossing
     if (addr & 0xFF00) != (cpu.pc & 0xFF00) {
@@ -1518,7 +2379,7 @@ fn bvc(cpu: &mut CPU) -> u8 {
 fn bvs(cpu: &mut CPU) -> u8 {
   if cpu.status.is_flag_set(CpuStatus::V) {
     let mut cycles: u8 = 1;
-    let addr = cpu.pc + cpu.rel_working_addr;
+    let addr = cpu.pc.wrapping_add(cpu.rel_working_addr);
 
     // page boundary crossing
     if (addr & 0xFF00) != (cpu.pc & 0xFF00) {
@@ -1560,19 +2421,15 @@ fn bit(cpu: &mut CPU) -> u8 {
 
 // BRK
 fn brk(cpu: &mut CPU) -> u8 {
-  cpu.pc += 1;
-
-  cpu.status.set_flag(CpuStatus::B);
-  cpu.status.set_flag(CpuStatus::I);
-
-  cpu.mem.write(cpu.sp as u16 + 0x0100, (cpu.pc >> 8) as u8);
-  cpu.sp -= 1;
+  // skip the padding byte
+  cpu.pc = cpu.pc.wrapping_add(1);
 
-  cpu.mem.write(cpu.sp as u16 + 0x0100, cpu.pc as u8);
-  cpu.sp -= 1;
+  cpu.push((cpu.pc >> 8) as u8);
+  cpu.push(cpu.pc as u8);
 
-  cpu.mem.write(cpu.sp as u16 + 0x0100, cpu.status.bits());
-  cpu.sp -= 1;
+  // B only exists on the stack
+  cpu.push((cpu.status | CpuStatus::B | CpuStatus::U).bits());
+  cpu.status.set_flag(CpuStatus::I);
 
   let lo = cpu.mem.read(0xFFFE) as u16;
   let hi = cpu.mem.read(0xFFFF) as u16;
@@ -1709,6 +2566,7 @@ fn dec(cpu: &mut CPU) -> u8 {
     cpu.status.clear_flag(CpuStatus::Z);
   }
 
+  cpu.working_data = temp;
   cpu.mem.write(cpu.working_addr, temp);
 
   0x00
@@ -1793,6 +2651,7 @@ fn inc(cpu: &mut CPU) -> u8 {
     cpu.status.clear_flag(CpuStatus::Z);
   }
 
+  cpu.working_data = temp;
   cpu.mem.write(cpu.working_addr, temp);
 
   0x00
@@ -1845,13 +2704,12 @@ fn jmp(cpu: &mut CPU) -> u8 {
 
 // JSR
 fn jsr(cpu: &mut CPU) -> u8 {
-  let temp = cpu.pc - 1;
+  // the stack is read while the target's low byte is held
+  cpu.mem.dummy_read(0x0100 | cpu.sp as u16);
+  let temp = cpu.pc.wrapping_sub(1);
 
-  cpu.mem.write(cpu.sp as u16 + 0x0100, (temp >> 8) as u8);
-  cpu.sp = cpu.sp.wrapping_sub(1);
-
-  cpu.mem.write(cpu.sp as u16 + 0x0100, temp as u8);
-  cpu.sp = cpu.sp.wrapping_sub(1);
+  cpu.push((temp >> 8) as u8);
+  cpu.push(temp as u8);
 
   cpu.pc = cpu.working_addr;
 
@@ -1949,6 +2807,7 @@ fn lsr(cpu: &mut CPU) -> u8 {
     cpu.status.clear_flag(CpuStatus::Z);
   }
 
+  cpu.working_data = temp;
   if cpu.curr_instruction.addr_mode == ADDR_MODE::IMPLIED {
     cpu.reg_a = temp;
   } else {
@@ -1968,7 +2827,7 @@ fn ora(cpu: &mut CPU) -> u8 {
   cpu.fill_working_data();
   let data = cpu.working_data;
 
-  cpu.reg_a = cpu.reg_a | data;
+  cpu.reg_a |= data;
 
   if cpu.reg_a & 0x80 == 0x80 {
     cpu.status.set_flag(CpuStatus::N);
@@ -1987,24 +2846,22 @@ fn ora(cpu: &mut CPU) -> u8 {
 
 // PHA
 fn pha(cpu: &mut CPU) -> u8 {
-  cpu.mem.write(cpu.sp as u16 + 0x0100, cpu.reg_a);
-  cpu.sp = cpu.sp.wrapping_sub(1);
+  cpu.push(cpu.reg_a);
 
   0x00
 }
 
 // PHP
 fn php(cpu: &mut CPU) -> u8 {
-  cpu.mem.write(cpu.sp as u16 + 0x0100, cpu.status.bits());
-  cpu.sp = cpu.sp.wrapping_sub(1);
+  cpu.push((cpu.status | CpuStatus::B | CpuStatus::U).bits());
 
   0x00
 }
 
 // PLA
 fn pla(cpu: &mut CPU) -> u8 {
-  cpu.sp = cpu.sp.wrapping_add(1);
-  cpu.reg_a = cpu.mem.read(cpu.sp as u16 + 0x0100);
+  cpu.pull_dummy();
+  cpu.reg_a = cpu.pull();
 
   if cpu.reg_a & 0x80 == 0x80 {
     cpu.status.set_flag(CpuStatus::N);
@@ -2023,8 +2880,10 @@ fn pla(cpu: &mut CPU) -> u8 {
 
 // PLP
 fn plp(cpu: &mut CPU) -> u8 {
-  cpu.sp = cpu.sp.wrapping_add(1);
-  cpu.status = CpuStatus::from_bits_truncate(cpu.mem.read(cpu.sp as u16 + 0x0100));
+  cpu.pull_dummy();
+  cpu.status = CpuStatus::from_bits_truncate(cpu.pull());
+  cpu.status.clear_flag(CpuStatus::B);
+  cpu.status.set_flag(CpuStatus::U);
 
   0x00
 }
@@ -2058,6 +2917,7 @@ fn rol(cpu: &mut CPU) -> u8 {
     cpu.status.clear_flag(CpuStatus::C);
   }
 
+  cpu.working_data = temp as u8;
   if cpu.curr_instruction.addr_mode == ADDR_MODE::IMPLIED {
     cpu.reg_a = temp as u8;
   } else {
@@ -2071,34 +2931,35 @@ fn rol(cpu: &mut CPU) -> u8 {
 fn ror(cpu: &mut CPU) -> u8 {
   cpu.fill_working_data();
   let data = cpu.working_data;
-  let mut temp = (data as u16) << 1;
+  let mut temp = data >> 1;
 
   if cpu.status.is_flag_set(CpuStatus::C) {
-    temp |= 0x0001;
+    temp |= 0x80;
   }
 
-  if temp & 0x0080 == 0x0080 {
+  if temp & 0x80 == 0x80 {
     cpu.status.set_flag(CpuStatus::N);
   } else {
     cpu.status.clear_flag(CpuStatus::N);
   }
 
-  if (temp as u8) == 0x00 {
+  if temp == 0x00 {
     cpu.status.set_flag(CpuStatus::Z);
   } else {
     cpu.status.clear_flag(CpuStatus::Z);
   }
 
-  if (temp & 0xFF00) > 0 {
+  if data & 0x01 == 0x01 {
     cpu.status.set_flag(CpuStatus::C);
   } else {
     cpu.status.clear_flag(CpuStatus::C);
   }
 
+  cpu.working_data = temp;
   if cpu.curr_instruction.addr_mode == ADDR_MODE::IMPLIED {
-    cpu.reg_a = temp as u8;
+    cpu.reg_a = temp;
   } else {
-    cpu.mem.write(cpu.working_addr, temp as u8);
+    cpu.mem.write(cpu.working_addr, temp);
   }
 
   0x00
@@ -2106,25 +2967,24 @@ fn ror(cpu: &mut CPU) -> u8 {
 
 // RTI
 fn rti(cpu: &mut CPU) -> u8 {
-  cpu.sp = cpu.sp.wrapping_add(1);
-  cpu.status = CpuStatus::from_bits_truncate(cpu.mem.read(cpu.sp as u16 + 0x0100));
-
-  cpu.sp = cpu.sp.wrapping_add(1);
-  cpu.pc = cpu.mem.read(cpu.sp as u16 + 0x0100) as u16;
+  cpu.pull_dummy();
+  cpu.status = CpuStatus::from_bits_truncate(cpu.pull());
+  cpu.status.clear_flag(CpuStatus::B);
+  cpu.status.set_flag(CpuStatus::U);
 
-  cpu.sp = cpu.sp.wrapping_add(1);
-  cpu.pc |= (cpu.mem.read(cpu.sp as u16 + 0x0100) as u16) << 8;
+  cpu.pc = cpu.pull() as u16;
+  cpu.pc |= (cpu.pull() as u16) << 8;
 
   0x00
 }
 
 // RTS
 fn rts(cpu: &mut CPU) -> u8 {
-  cpu.sp = cpu.sp.wrapping_add(1);
-  cpu.pc = cpu.mem.read(cpu.sp as u16 + 0x0100) as u16;
-
-  cpu.sp = cpu.sp.wrapping_add(1);
-  cpu.pc |= (cpu.mem.read(cpu.sp as u16 + 0x0100) as u16) << 8;
+  cpu.pull_dummy();
+  cpu.pc = cpu.pull() as u16;
+  cpu.pc |= (cpu.pull() as u16) << 8;
+  // the return address is read before it's stepped past
+  cpu.mem.dummy_read(cpu.pc);
 
   cpu.pc = cpu.pc.wrapping_add(1);
 
@@ -2213,7 +3073,7 @@ fn tay(cpu: &mut CPU) -> u8 {
 
 // TSX
 fn tsx(cpu: &mut CPU) -> u8 {
-  cpu.reg_x = cpu.sp as u8;
+  cpu.reg_x = cpu.sp;
 
   if cpu.reg_x & 0x80 == 0x80 {
     cpu.status.set_flag(CpuStatus::N);
@@ -2251,7 +3111,7 @@ fn txa(cpu: &mut CPU) -> u8 {
 
 // TXS
 fn txs(cpu: &mut CPU) -> u8 {
-  cpu.sp = cpu.reg_x as u16;
+  cpu.sp = cpu.reg_x;
 
   0x00
 }
@@ -2275,15 +3135,44 @@ fn tya(cpu: &mut CPU) -> u8 {
   0x00
 }
 
-// XXX
-fn xxx(cpu: &mut CPU) -> u8 {
-  panic!("Invalid opcode: {:02X}", cpu.curr_instruction.opcode);
-}
-
 // ADC
 fn adc(cpu: &mut CPU) -> u8 {
-  let data = cpu.working_data;
+  cpu.fill_working_data();
+  add(cpu, cpu.working_data);
+
+  0x01
+}
+
+// SBC
+fn sbc(cpu: &mut CPU) -> u8 {
+  cpu.fill_working_data();
+  subtract(cpu, cpu.working_data);
+
+  0x01
+}
+
+// A + data + C, binary or BCD
+fn add(cpu: &mut CPU, data: u8) {
+  if cpu.status.is_flag_set(CpuStatus::D) {
+    add_decimal(cpu, data);
+  } else {
+    add_with_carry(cpu, data);
+  }
+}
+
+// A - data - !C, binary or BCD
+fn subtract(cpu: &mut CPU, data: u8) {
+  let (reg_a, carry) = (cpu.reg_a, cpu.status.is_flag_set(CpuStatus::C));
+  // subtracting is adding the one's complement
+  add_with_carry(cpu, data ^ 0xFF);
+
+  // in decimal mode the NMOS 6502 keeps the binary flags and only fixes up A
+  if cpu.status.is_flag_set(CpuStatus::D) {
+    cpu.reg_a = subtract_decimal(reg_a, data, carry);
+  }
+}
 
+fn add_with_carry(cpu: &mut CPU, data: u8) {
   let mut temp = cpu.reg_a as u16 + data as u16;
 
   if cpu.status.is_flag_set(CpuStatus::C) {
@@ -2314,55 +3203,333 @@ fn adc(cpu: &mut CPU) -> u8 {
     cpu.status.clear_flag(CpuStatus::C);
   }
 
-  if cpu.curr_instruction.addr_mode == ADDR_MODE::IMPLIED {
-    cpu.reg_a = temp as u8;
+  cpu.reg_a = temp as u8;
+}
+
+// BCD add, Z comes from the binary sum and N/V from the sum before the high digit is adjusted
+fn add_decimal(cpu: &mut CPU, data: u8) {
+  let (reg_a, data) = (cpu.reg_a as i16, data as i16);
+  let carry = cpu.status.is_flag_set(CpuStatus::C) as i16;
+
+  let mut low = (reg_a & 0x0F) + (data & 0x0F) + carry;
+  if low >= 0x0A {
+    low = ((low + 0x06) & 0x0F) + 0x10;
   }
+  let mut temp = (reg_a & 0xF0) + (data & 0xF0) + low;
 
-  0x01
+  if (reg_a + data + carry) & 0xFF == 0x00 {
+    cpu.status.set_flag(CpuStatus::Z);
+  } else {
+    cpu.status.clear_flag(CpuStatus::Z);
+  }
+
+  if temp & 0x80 == 0x80 {
+    cpu.status.set_flag(CpuStatus::N);
+  } else {
+    cpu.status.clear_flag(CpuStatus::N);
+  }
+
+  if (!(reg_a ^ data) & (reg_a ^ temp) & 0x0080) == 0x0080 {
+    cpu.status.set_flag(CpuStatus::V);
+  } else {
+    cpu.status.clear_flag(CpuStatus::V);
+  }
+
+  if temp >= 0xA0 {
+    temp += 0x60;
+  }
+
+  if temp > 0xFF {
+    cpu.status.set_flag(CpuStatus::C);
+  } else {
+    cpu.status.clear_flag(CpuStatus::C);
+  }
+
+  cpu.reg_a = temp as u8;
 }
 
-// SBC
-fn sbc(cpu: &mut CPU) -> u8 {
-  let data = cpu.working_data;
+// BCD subtract, only the result, the flags are the binary ones
+fn subtract_decimal(reg_a: u8, data: u8, carry: bool) -> u8 {
+  let (reg_a, data) = (reg_a as i16, data as i16);
 
-  let mut addi = cpu.reg_a as u16 + data as u16;
-  if cpu.status.is_flag_set(CpuStatus::C) {
-    addi += 1;
+  let mut low = (reg_a & 0x0F) - (data & 0x0F) + carry as i16 - 1;
+  if low < 0 {
+    low = ((low - 0x06) & 0x0F) - 0x10;
   }
+  let mut temp = (reg_a & 0xF0) - (data & 0xF0) + low;
+  if temp < 0 {
+    temp -= 0x60;
+  }
+
+  temp as u8
+}
 
-  let mut temp = data as u16 ^ 0x00FF;
+/* ------- undocumented OPs -------- */
+// what the NMOS 6502 does with the opcodes it doesn't document. the read-modify-write
+// ones reuse the documented OPs, which leave what they wrote in working_data
 
-  if !cpu.status.is_flag_set(CpuStatus::C) {
-    temp -= 1;
+// the unstable ANE and LXA OR A with this before ANDing, it varies between chips
+const MAGIC: u8 = 0xEE;
+
+fn set_zn(cpu: &mut CPU, data: u8) {
+  if data == 0x00 {
+    cpu.status.set_flag(CpuStatus::Z);
+  } else {
+    cpu.status.clear_flag(CpuStatus::Z);
   }
 
-  if temp & 0x0080 == 0x0080 {
+  if data & 0x80 == 0x80 {
     cpu.status.set_flag(CpuStatus::N);
   } else {
     cpu.status.clear_flag(CpuStatus::N);
   }
+}
+
+// the SHA, SHX, SHY and TAS stores, `data` ANDed with the high byte of the address
+// before `index` was added plus one. crossing a page puts that in the high byte too
+fn store_high(cpu: &mut CPU, data: u8, index: u8) {
+  let base = cpu.working_addr.wrapping_sub(index as u16);
+  let data = data & ((base >> 8) as u8).wrapping_add(1);
+  if (base & 0xFF00) != (cpu.working_addr & 0xFF00) {
+    cpu.working_addr = (cpu.working_addr & 0x00FF) | (data as u16) << 8;
+  }
 
-  if temp & 0x00FF == 0x0000 {
-    cpu.status.set_flag(CpuStatus::Z);
+  cpu.mem.write(cpu.working_addr, data);
+}
+
+// SLO, ASL then ORA
+fn slo(cpu: &mut CPU) -> u8 {
+  asl(cpu);
+  cpu.reg_a |= cpu.working_data;
+  set_zn(cpu, cpu.reg_a);
+
+  0x00
+}
+
+// RLA, ROL then AND
+fn rla(cpu: &mut CPU) -> u8 {
+  rol(cpu);
+  cpu.reg_a &= cpu.working_data;
+  set_zn(cpu, cpu.reg_a);
+
+  0x00
+}
+
+// SRE, LSR then EOR
+fn sre(cpu: &mut CPU) -> u8 {
+  lsr(cpu);
+  cpu.reg_a ^= cpu.working_data;
+  set_zn(cpu, cpu.reg_a);
+
+  0x00
+}
+
+// RRA, ROR then ADC
+fn rra(cpu: &mut CPU) -> u8 {
+  ror(cpu);
+  add(cpu, cpu.working_data);
+
+  0x00
+}
+
+// SAX
+fn sax(cpu: &mut CPU) -> u8 {
+  cpu.mem.write(cpu.working_addr, cpu.reg_a & cpu.reg_x);
+
+  0x00
+}
+
+// LAX, LDA and LDX at once
+fn lax(cpu: &mut CPU) -> u8 {
+  cpu.fill_working_data();
+  cpu.reg_a = cpu.working_data;
+  cpu.reg_x = cpu.working_data;
+  set_zn(cpu, cpu.reg_a);
+
+  0x01
+}
+
+// DCP, DEC then CMP
+fn dcp(cpu: &mut CPU) -> u8 {
+  dec(cpu);
+  let data = cpu.working_data;
+
+  if cpu.reg_a >= data {
+    cpu.status.set_flag(CpuStatus::C);
   } else {
-    cpu.status.clear_flag(CpuStatus::Z);
+    cpu.status.clear_flag(CpuStatus::C);
   }
+  set_zn(cpu, cpu.reg_a.wrapping_sub(data));
 
-  if (addi ^ cpu.reg_a as u16) & (addi ^ temp) & 0x0080 == 0x0080 {
-    cpu.status.set_flag(CpuStatus::V);
+  0x00
+}
+
+// ISC, INC then SBC
+fn isc(cpu: &mut CPU) -> u8 {
+  inc(cpu);
+  subtract(cpu, cpu.working_data);
+
+  0x00
+}
+
+// ANC, AND with N copied to C
+fn anc(cpu: &mut CPU) -> u8 {
+  and(cpu);
+  if cpu.status.is_flag_set(CpuStatus::N) {
+    cpu.status.set_flag(CpuStatus::C);
   } else {
-    cpu.status.clear_flag(CpuStatus::V);
+    cpu.status.clear_flag(CpuStatus::C);
   }
 
-  if temp & 0xFF00 != 0x0000 {
+  0x00
+}
+
+// ALR, AND then LSR A
+fn alr(cpu: &mut CPU) -> u8 {
+  cpu.fill_working_data();
+  let data = cpu.reg_a & cpu.working_data;
+
+  if data & 0x01 == 0x01 {
     cpu.status.set_flag(CpuStatus::C);
   } else {
     cpu.status.clear_flag(CpuStatus::C);
   }
+  cpu.reg_a = data >> 1;
+  set_zn(cpu, cpu.reg_a);
 
-  if cpu.curr_instruction.addr_mode == ADDR_MODE::IMPLIED {
-    cpu.reg_a = temp as u8;
+  0x00
+}
+
+// ARR, AND then ROR A with C and V from bits 6 and 5, decimal mode fixes up
+// each digit like ADC would
+fn arr(cpu: &mut CPU) -> u8 {
+  cpu.fill_working_data();
+  let data = cpu.reg_a & cpu.working_data;
+  let carry = cpu.status.is_flag_set(CpuStatus::C);
+  cpu.reg_a = (data >> 1) | (carry as u8) << 7;
+  set_zn(cpu, cpu.reg_a);
+
+  if cpu.status.is_flag_set(CpuStatus::D) {
+    if (data ^ cpu.reg_a) & 0x40 == 0x40 {
+      cpu.status.set_flag(CpuStatus::V);
+    } else {
+      cpu.status.clear_flag(CpuStatus::V);
+    }
+
+    if (data & 0x0F) + (data & 0x01) > 0x05 {
+      cpu.reg_a = (cpu.reg_a & 0xF0) | (cpu.reg_a.wrapping_add(0x06) & 0x0F);
+    }
+    if (data as u16 & 0xF0) + (data as u16 & 0x10) > 0x50 {
+      cpu.status.set_flag(CpuStatus::C);
+      cpu.reg_a = cpu.reg_a.wrapping_add(0x60);
+    } else {
+      cpu.status.clear_flag(CpuStatus::C);
+    }
+  } else {
+    if cpu.reg_a & 0x40 == 0x40 {
+      cpu.status.set_flag(CpuStatus::C);
+    } else {
+      cpu.status.clear_flag(CpuStatus::C);
+    }
+
+    if ((cpu.reg_a >> 6) ^ (cpu.reg_a >> 5)) & 0x01 == 0x01 {
+      cpu.status.set_flag(CpuStatus::V);
+    } else {
+      cpu.status.clear_flag(CpuStatus::V);
+    }
+  }
+
+  0x00
+}
+
+// ANE
+fn ane(cpu: &mut CPU) -> u8 {
+  cpu.fill_working_data();
+  cpu.reg_a = (cpu.reg_a | MAGIC) & cpu.reg_x & cpu.working_data;
+  set_zn(cpu, cpu.reg_a);
+
+  0x00
+}
+
+// LXA
+fn lxa(cpu: &mut CPU) -> u8 {
+  cpu.fill_working_data();
+  cpu.reg_a = (cpu.reg_a | MAGIC) & cpu.working_data;
+  cpu.reg_x = cpu.reg_a;
+  set_zn(cpu, cpu.reg_a);
+
+  0x00
+}
+
+// SBX, X = (A & X) - data with the flags of a CMP
+fn sbx(cpu: &mut CPU) -> u8 {
+  cpu.fill_working_data();
+  let data = cpu.working_data;
+  let temp = cpu.reg_a & cpu.reg_x;
+
+  if temp >= data {
+    cpu.status.set_flag(CpuStatus::C);
+  } else {
+    cpu.status.clear_flag(CpuStatus::C);
   }
+  cpu.reg_x = temp.wrapping_sub(data);
+  set_zn(cpu, cpu.reg_x);
+
+  0x00
+}
+
+// LAS
+fn las(cpu: &mut CPU) -> u8 {
+  cpu.fill_working_data();
+  let data = cpu.working_data & cpu.sp;
+  cpu.reg_a = data;
+  cpu.reg_x = data;
+  cpu.sp = data;
+  set_zn(cpu, data);
+
+  0x01
+}
+
+// SHA
+fn sha(cpu: &mut CPU) -> u8 {
+  store_high(cpu, cpu.reg_a & cpu.reg_x, cpu.reg_y);
+
+  0x00
+}
+
+// SHX
+fn shx(cpu: &mut CPU) -> u8 {
+  store_high(cpu, cpu.reg_x, cpu.reg_y);
+
+  0x00
+}
+
+// SHY
+fn shy(cpu: &mut CPU) -> u8 {
+  store_high(cpu, cpu.reg_y, cpu.reg_x);
+
+  0x00
+}
+
+// TAS, SP = A & X then SHA
+fn tas(cpu: &mut CPU) -> u8 {
+  cpu.sp = cpu.reg_a & cpu.reg_x;
+  store_high(cpu, cpu.sp, cpu.reg_y);
+
+  0x00
+}
+
+// JAM, locks the cpu up until a reset
+fn jam(cpu: &mut CPU) -> u8 {
+  cpu.halt();
+
+  0x00
+}
+
+// XXX, the undocumented NOPs still read their operand
+fn xxx(cpu: &mut CPU) -> u8 {
+  cpu.fill_working_data();
 
   0x01
 }
+/* ------- undocumented OPs -------- */
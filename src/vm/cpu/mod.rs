@@ -1,14 +1,29 @@
-use self::instructions::{Instruction, ADDR_MODE};
+use self::instructions::{Instruction, ADDR_MODE, INTERRUPT_WRITES, OPS};
 
+#[cfg(feature = "alloc")]
+use super::profiler::Profiler;
 use super::{
+  coverage::Access,
   defs::{Byte, Word},
+  error::Error,
   mem::Mem,
 };
+#[cfg(feature = "alloc")]
+use alloc::{boxed::Box, format, string::String};
 use bitflags::bitflags;
+#[cfg(feature = "alloc")]
+use core::str::FromStr;
 
+#[cfg(feature = "alloc")]
+mod disasm;
 mod instructions;
+mod test;
+
+#[cfg(feature = "alloc")]
+pub use disasm::{disassemble, disassemble_with, trace_line, Disassembly};
 
 bitflags! {
+  #[derive(Default)]
   pub struct CpuStatus: u8 {
     const C = 0b00000001; // Carry Flag
     const Z = 0b00000010; // Zero Flag
@@ -21,15 +36,9 @@ bitflags! {
   }
 }
 
-impl Default for CpuStatus {
-  fn default() -> Self {
-    Self { bits: 0 }
-  }
-}
-
 impl CpuStatus {
   pub fn reset(&mut self) {
-    self.bits = 0xFF | CpuStatus::U.bits;
+    self.bits = CpuStatus::U.bits | CpuStatus::I.bits;
   }
 
   pub fn set_flag(&mut self, flag: CpuStatus) {
@@ -45,9 +54,50 @@ impl CpuStatus {
   }
 }
 
+// what the cpu does with an opcode that isn't documented
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IllegalOpcodePolicy {
+  // runs it like an NMOS 6502, the JAMs halt
+  Undocumented,
+  // skips it as a NOP that takes the undocumented instruction's bytes and cycles
+  #[default]
+  Nop,
+  // halts the cpu on the opcode until it's reset
+  Halt,
+  // clock returns Error::IllegalOpcode and leaves the pc on the opcode
+  Error,
+}
+
+#[cfg(feature = "alloc")]
+impl FromStr for IllegalOpcodePolicy {
+  type Err = String;
+
+  // the lowercase names, for command lines
+  fn from_str(name: &str) -> Result<Self, Self::Err> {
+    match name {
+      "undocumented" => Ok(Self::Undocumented),
+      "nop" => Ok(Self::Nop),
+      "halt" => Ok(Self::Halt),
+      "error" => Ok(Self::Error),
+      _ => Err(format!("unknown illegal opcode policy {}", name)),
+    }
+  }
+}
+
+// what the cpu does when a push or pull takes the stack pointer past the end of page one
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StackPolicy {
+  // wraps around like the hardware does
+  #[default]
+  Wrap,
+  // wraps, then clock returns Error::StackOverflow or StackUnderflow once the
+  // instruction is done
+  Error,
+}
+
 pub struct CPU {
   pub pc: Word,
-  pub sp: Word,
+  pub sp: Byte,
 
   pub reg_a: Byte,
   pub reg_x: Byte,
@@ -62,6 +112,25 @@ pub struct CPU {
   pub rel_working_addr: Word,
   pub working_data: Byte,
   pub curr_instruction: Instruction,
+
+  pub on_illegal_opcode: IllegalOpcodePolicy,
+  pub on_stack_wrap: StackPolicy,
+  // where the current instruction started, for errors
+  instruction_pc: Word,
+  error: Option<Error>,
+  halted: bool,
+
+  // the RDY and SO inputs as the host drives them, devices pull them too
+  rdy: bool,
+  so: bool,
+  so_level: bool,
+  sync: bool,
+  // cycles of the current instruction or interrupt, and which of them write
+  sequence_cycles: u8,
+  write_cycles: u8,
+
+  #[cfg(feature = "alloc")]
+  profiler: Option<Box<Profiler>>,
 }
 
 impl CPU {
@@ -82,6 +151,22 @@ impl CPU {
       working_addr: 0x0000,
       working_data: 0x00,
       curr_instruction: Instruction::default(),
+
+      on_illegal_opcode: IllegalOpcodePolicy::default(),
+      on_stack_wrap: StackPolicy::default(),
+      instruction_pc: 0,
+      error: None,
+      halted: false,
+
+      rdy: true,
+      so: false,
+      so_level: false,
+      sync: false,
+      sequence_cycles: 0,
+      write_cycles: 0,
+
+      #[cfg(feature = "alloc")]
+      profiler: None,
     };
     new.reset();
 
@@ -94,99 +179,276 @@ impl CPU {
     let hi = self.mem.read(self.working_addr + 1) as Word;
     self.pc = (hi << 8) | lo;
 
-    self.sp = 0x0100;
+    self.sp = 0xFD;
     self.status.reset();
+    self.halted = false;
 
     self.cycles = 8;
+    self.sequence_cycles = 8;
+    self.write_cycles = 0;
+  }
+
+  // stopped by a JAM or the Halt policy, only a reset gets it going again
+  pub fn halted(&self) -> bool {
+    self.halted
+  }
+
+  // halts on the current instruction's opcode, the bus keeps running
+  fn halt(&mut self) {
+    self.pc = self.instruction_pc;
+    self.halted = true;
+  }
+
+  /* ------- pins -------- */
+  // RDY low stops the cpu on its next read cycle, writes still go ahead
+  pub fn set_rdy(&mut self, level: bool) {
+    self.rdy = level;
+  }
+
+  // whether RDY is high from the host and every device and no DMA has the bus
+  pub fn ready(&self) -> bool {
+    self.rdy && self.mem.rdy() && !self.mem.dma_active()
+  }
+
+  // asserting SO sets V, it's sampled every cycle and only the edge counts
+  pub fn set_so(&mut self, active: bool) {
+    self.so = active;
+  }
+
+  // SYNC, high on the cycles that fetch an opcode
+  pub fn sync(&self) -> bool {
+    self.sync
   }
 
-  pub fn clock(&mut self) {
+  // whether the cycle about to run writes to the bus
+  fn write_cycle(&self) -> bool {
+    let cycle = self.sequence_cycles.saturating_sub(self.cycles);
+    self.cycles > 0 && self.write_cycles.checked_shr(cycle as u32).unwrap_or(0) & 1 != 0
+  }
+  /* ------- pins -------- */
+
+  // runs a cycle, the whole of an instruction happens on its first one.
+  // errors leave the cpu where it stopped, clocking it again carries on
+  pub fn clock(&mut self) -> Result<(), Error> {
+    let so = self.so || self.mem.so();
+    if so && !self.so_level {
+      self.status.set_flag(CpuStatus::V);
+    }
+    self.so_level = so;
+    self.sync = false;
+
+    // DMA takes the bus as soon as the cpu isn't writing, the cycle is stolen
+    if self.mem.dma_active() && !self.write_cycle() {
+      self.mem.dma_cycle();
+      self.mem.tick();
+      return Ok(());
+    }
+
+    // a stopped cpu leaves the bus to the devices
+    if self.halted || (!self.ready() && !self.write_cycle()) {
+      self.mem.tick();
+      return Ok(());
+    }
+
     if self.cycles == 0 {
-      let op_code = self.fetch();
-      let ins = Instruction::from_op_code(op_code);
-      self.curr_instruction = ins.clone();
-      self.cycles = instructions::execute(self, ins);
+      if self.mem.irq() && !self.status.is_flag_set(CpuStatus::I) {
+        self.irq();
+      } else {
+        let (pc, sp) = (self.pc, self.sp);
+        // a rejected opcode never makes it onto the bus, so a retry doesn't fetch it twice
+        if self.on_illegal_opcode == IllegalOpcodePolicy::Error {
+          let op_code = self.mem.peek(pc);
+          if Instruction::from_op_code(op_code).is_illegal() {
+            return Err(Error::IllegalOpcode {
+              pc,
+              opcode: op_code,
+            });
+          }
+        }
+        self.sync = true;
+        self.mem.sync(pc);
+        let op_code = self.mem.fetch(pc, Access::OPCODE);
+        let mut ins = Instruction::from_op_code(op_code);
+        if ins.is_illegal() {
+          match self.on_illegal_opcode {
+            // Error turned the opcode down before the fetch
+            IllegalOpcodePolicy::Undocumented | IllegalOpcodePolicy::Error => {}
+            // the undocumented NOPs already are
+            IllegalOpcodePolicy::Nop if matches!(ins.opr, OPS::XXX) => {}
+            IllegalOpcodePolicy::Nop => ins.opr = OPS::NOP,
+            IllegalOpcodePolicy::Halt => {
+              ins.opr = OPS::JAM;
+              ins.addr_mode = ADDR_MODE::IMPLIED;
+            }
+          }
+        }
+
+        self.instruction_pc = pc;
+        self.pc = self.pc.wrapping_add(1);
+        self.curr_instruction = ins.clone();
+        self.cycles = instructions::execute(self, ins);
+        // halting takes this cycle and no more
+        if self.halted {
+          self.cycles = 1;
+        }
+        self.sequence_cycles = self.cycles;
+        self.write_cycles = self.curr_instruction.write_cycles(self.cycles);
+
+        self.profile_instruction(pc, op_code, sp);
+      }
     }
 
     self.cycles -= 1;
+    self.mem.tick();
+
+    match self.error.take() {
+      Some(err) => Err(err),
+      None => Ok(()),
+    }
   }
 
   pub fn irq(&mut self) {
-    if self.status.is_flag_set(CpuStatus::I) {
+    if self.halted || self.status.is_flag_set(CpuStatus::I) {
       return;
     }
 
-    self.mem.write(self.sp, (self.pc >> 8) as Byte);
-    self.sp -= 1;
-    self.mem.write(self.sp, (self.pc & 0x00FF) as Byte);
-    self.sp -= 1;
-
-    self.status.set_flag(CpuStatus::B);
-    self.status.set_flag(CpuStatus::U);
-    self.status.set_flag(CpuStatus::I);
-    self.mem.write(self.sp, self.status.bits);
-    self.sp -= 1;
+    self.interrupt(0xFFFE);
+    self.cycles = 7;
+    self.sequence_cycles = 7;
+    self.write_cycles = INTERRUPT_WRITES;
+    self.profile_interrupt();
+  }
 
-    self.working_addr = 0xFFFE;
-    let lo = self.mem.read(self.working_addr) as Word;
-    let hi = self.mem.read(self.working_addr + 1) as Word;
-    self.pc = (hi << 8) | lo;
+  pub fn nmi(&mut self) {
+    if self.halted {
+      return;
+    }
 
+    self.interrupt(0xFFFA);
     self.cycles = 7;
+    self.sequence_cycles = 7;
+    self.write_cycles = INTERRUPT_WRITES;
+    self.profile_interrupt();
   }
 
-  pub fn nmi(&mut self) {
-    self.mem.write(self.sp, (self.pc >> 8) as Byte);
-    self.sp -= 1;
-    self.mem.write(self.sp, (self.pc & 0x00FF) as Byte);
-    self.sp -= 1;
+  // `pc` and `sp` from before the instruction
+  #[cfg_attr(not(feature = "alloc"), allow(unused_variables))]
+  fn profile_instruction(&mut self, pc: Word, op_code: Byte, sp: Byte) {
+    #[cfg(feature = "alloc")]
+    if let Some(profiler) = &mut self.profiler {
+      profiler.instruction(pc, op_code, self.cycles, sp, self.pc, self.sp);
+    }
+  }
 
-    self.status.set_flag(CpuStatus::B);
-    self.status.set_flag(CpuStatus::U);
+  fn profile_interrupt(&mut self) {
+    #[cfg(feature = "alloc")]
+    if let Some(profiler) = &mut self.profiler {
+      // the pc and status were pushed
+      profiler.interrupt(self.pc, self.cycles, self.sp.wrapping_add(3));
+    }
+  }
+
+  fn interrupt(&mut self, vector: Word) {
+    // the next opcode is read twice and dropped
+    self.mem.dummy_read(self.pc);
+    self.mem.dummy_read(self.pc);
+    self.push((self.pc >> 8) as Byte);
+    self.push((self.pc & 0x00FF) as Byte);
+
+    // B is only set on the stack by BRK/PHP
+    let mut status = self.status;
+    status.clear_flag(CpuStatus::B);
+    status.set_flag(CpuStatus::U);
+    self.push(status.bits);
     self.status.set_flag(CpuStatus::I);
-    self.mem.write(self.sp, self.status.bits);
-    self.sp -= 1;
 
-    self.working_addr = 0xFFFA;
+    self.working_addr = vector;
     let lo = self.mem.read(self.working_addr) as Word;
     let hi = self.mem.read(self.working_addr + 1) as Word;
     self.pc = (hi << 8) | lo;
+  }
 
-    self.cycles = 8;
+  /* ------- profiling -------- */
+  // starts counting executions and cycles per pc and following calls, a new
+  // profile each time
+  #[cfg(feature = "alloc")]
+  pub fn start_profiling(&mut self) {
+    self.profiler = Some(Box::new(Profiler::new()));
+  }
+
+  #[cfg(feature = "alloc")]
+  pub fn profiler(&self) -> Option<&Profiler> {
+    self.profiler.as_deref()
+  }
+
+  #[cfg(feature = "alloc")]
+  pub fn stop_profiling(&mut self) -> Option<Profiler> {
+    self.profiler.take().map(|profiler| *profiler)
   }
+  /* ------- profiling -------- */
 
   pub fn rti(&mut self) {
     // return from interrupt
-    self.sp += 1;
-    self.status.bits = self.mem.read(self.sp);
+    self.status.bits = self.pull();
     self.status.clear_flag(CpuStatus::B);
-    self.status.clear_flag(CpuStatus::U);
+    self.status.set_flag(CpuStatus::U);
 
-    self.sp += 1;
-    let lo = self.mem.read(self.sp) as Word;
-    self.sp += 1;
-    let hi = self.mem.read(self.sp) as Word;
+    let lo = self.pull() as Word;
+    let hi = self.pull() as Word;
     self.pc = (hi << 8) | lo;
 
     self.cycles = 6;
   }
 
+  pub fn push(&mut self, data: Byte) {
+    if self.sp == 0x00 && self.on_stack_wrap == StackPolicy::Error {
+      let pc = self.instruction_pc;
+      self.error.get_or_insert(Error::StackOverflow { pc });
+    }
+    self.mem.write(0x0100 | self.sp as Word, data);
+    self.sp = self.sp.wrapping_sub(1);
+  }
+
+  pub fn pull(&mut self) -> Byte {
+    if self.sp == 0xFF && self.on_stack_wrap == StackPolicy::Error {
+      let pc = self.instruction_pc;
+      self.error.get_or_insert(Error::StackUnderflow { pc });
+    }
+    self.sp = self.sp.wrapping_add(1);
+    self.mem.read(0x0100 | self.sp as Word)
+  }
+
+  // pulls spend a cycle reading the stack before sp is stepped
+  pub fn pull_dummy(&mut self) {
+    self.mem.dummy_read(0x0100 | self.sp as Word);
+  }
+
   pub fn fill_working_data(&mut self) {
-    if !(self.curr_instruction.addr_mode == ADDR_MODE::IMPLIED) {
-      self.working_data = self.mem.read(self.working_addr);
+    match self.curr_instruction.addr_mode {
+      ADDR_MODE::IMPLIED => {}
+      // the operand is the data
+      ADDR_MODE::IMMEDIATE => {
+        self.working_data = self.mem.fetch(self.working_addr, Access::OPERAND)
+      }
+      _ => {
+        self.working_data = self.mem.read(self.working_addr);
+        if self.curr_instruction.is_read_modify_write() {
+          self.mem.dummy_write(self.working_addr, self.working_data);
+        }
+      }
     }
   }
 
+  // an operand byte of the instruction
   pub fn fetch(&mut self) -> Byte {
-    let data = self.mem.read(self.pc);
-    self.pc += 1;
+    let data = self.mem.fetch(self.pc, Access::OPERAND);
+    self.pc = self.pc.wrapping_add(1);
     data
   }
 
   pub fn fetch_word(&mut self) -> Word {
     let lo = self.fetch() as Word;
     let hi = self.fetch() as Word;
-    self.pc += 2;
 
     (hi << 8) | lo
   }
@@ -1,13 +1,11 @@
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
-  use crate::vm::Mem;
-
-  use super::CPU;
+  use crate::vm::{disassemble, CpuStatus, Dma, Error, IllegalOpcodePolicy, Mem, StackPolicy, CPU};
 
   #[test]
   fn init_cpu() {
     let mut mem = Mem::new();
-    mem.load(&[0x01, 0x00], 0xFFFC);
+    mem.load(&[0x01, 0x00], 0xFFFC).unwrap();
     let cpu = CPU::new(mem);
     assert_eq!(
       cpu.pc, 0x0001,
@@ -20,26 +18,316 @@ mod tests {
   fn load_accumulator() {
     let mut mem = Mem::new();
     // starting address
-    mem.load(&[0x00, 0x00], 0xFFFC);
+    mem.load(&[0x00, 0x00], 0xFFFC).unwrap();
 
     // program
-    mem.load(&[0xA9], 0);
-    mem.load(&[0x45], 1);
+    mem.load(&[0xA9], 0).unwrap();
+    mem.load(&[0x45], 1).unwrap();
 
     let mut cpu = CPU::new(mem);
 
     // for testing purposes
     cpu.cycles = 0;
-    cpu.clock();
+    cpu.clock().unwrap();
 
     assert_eq!(
       cpu.working_addr, 0x0001,
       "CPU didn't load working address with 0x0001, it has {} instead",
       cpu.working_addr
     );
-    assert_eq!( cpu.reg_a, 0x45,
+    assert_eq!(
+      cpu.reg_a, 0x45,
       "CPU didn't load accumulator with 0x45, it has {} instead",
       cpu.reg_a
     );
   }
+
+  // loads a program at 0x0200 and runs `count` whole instructions
+  fn run(program: &[u8], count: usize) -> CPU {
+    run_with(program, count, IllegalOpcodePolicy::default())
+  }
+
+  fn run_with(program: &[u8], count: usize, illegal: IllegalOpcodePolicy) -> CPU {
+    let mut mem = Mem::new();
+    mem.load(&[0x00, 0x02], 0xFFFC).unwrap();
+    mem.load(program, 0x0200).unwrap();
+
+    let mut cpu = CPU::new(mem);
+    cpu.on_illegal_opcode = illegal;
+    cpu.cycles = 0;
+    for _ in 0..count {
+      cpu.clock().unwrap();
+      while cpu.cycles > 0 {
+        cpu.clock().unwrap();
+      }
+    }
+
+    cpu
+  }
+
+  #[test]
+  fn jsr_rts() {
+    // JSR $0206 ; LDX #$01 ; BRK ; LDA #$42 ; RTS
+    let cpu = run(&[0x20, 0x06, 0x02, 0xA2, 0x01, 0x00, 0xA9, 0x42, 0x60], 4);
+    assert_eq!(
+      cpu.reg_a, 0x42,
+      "subroutine didn't run, A is {:#04x}",
+      cpu.reg_a
+    );
+    assert_eq!(cpu.reg_x, 0x01, "RTS didn't return after the JSR");
+    assert_eq!(cpu.sp, 0xFD, "stack isn't balanced, SP is {:#04x}", cpu.sp);
+  }
+
+  #[test]
+  fn add_and_subtract_flags() {
+    // LDA #$7F ; CLC ; ADC #$01
+    let cpu = run(&[0xA9, 0x7F, 0x18, 0x69, 0x01], 3);
+    assert_eq!(cpu.reg_a, 0x80);
+    assert!(
+      cpu.status.is_flag_set(CpuStatus::V),
+      "signed overflow not set"
+    );
+    assert!(
+      !cpu.status.is_flag_set(CpuStatus::C),
+      "carry set without unsigned overflow"
+    );
+
+    // LDA #$00 ; SEC ; SBC #$01
+    let cpu = run(&[0xA9, 0x00, 0x38, 0xE9, 0x01], 3);
+    assert_eq!(cpu.reg_a, 0xFF);
+    assert!(
+      !cpu.status.is_flag_set(CpuStatus::C),
+      "borrow didn't clear carry"
+    );
+    assert!(cpu.status.is_flag_set(CpuStatus::N));
+  }
+
+  #[test]
+  fn branch_backwards() {
+    // LDX #$03 ; DEX ; BNE -3
+    let cpu = run(&[0xA2, 0x03, 0xCA, 0xD0, 0xFD], 7);
+    assert_eq!(
+      cpu.reg_x, 0x00,
+      "loop didn't run to the end, X is {}",
+      cpu.reg_x
+    );
+    assert_eq!(cpu.pc, 0x0205, "branch fell through to {:#06x}", cpu.pc);
+  }
+
+  #[test]
+  fn decimal_mode() {
+    // SED ; CLC ; LDA #$19 ; ADC #$28
+    let cpu = run(&[0xF8, 0x18, 0xA9, 0x19, 0x69, 0x28], 4);
+    assert_eq!(cpu.reg_a, 0x47, "BCD add gave {:#04x}", cpu.reg_a);
+
+    // SED ; CLC ; LDA #$99 ; ADC #$01
+    let cpu = run(&[0xF8, 0x18, 0xA9, 0x99, 0x69, 0x01], 4);
+    assert_eq!(cpu.reg_a, 0x00, "BCD add gave {:#04x}", cpu.reg_a);
+    assert!(cpu.status.is_flag_set(CpuStatus::C), "BCD add didn't carry");
+
+    // SED ; SEC ; LDA #$50 ; SBC #$01
+    let cpu = run(&[0xF8, 0x38, 0xA9, 0x50, 0xE9, 0x01], 4);
+    assert_eq!(cpu.reg_a, 0x49, "BCD subtract gave {:#04x}", cpu.reg_a);
+    assert!(
+      cpu.status.is_flag_set(CpuStatus::C),
+      "BCD subtract borrowed"
+    );
+  }
+
+  #[test]
+  fn disassembly() {
+    let mut mem = Mem::new();
+    // LDA #$42 ; STA ($10),Y ; JMP ($FFFC) ; ASL A ; BNE -2 ; JAM
+    let program = [
+      0xA9, 0x42, 0x91, 0x10, 0x6C, 0xFC, 0xFF, 0x0A, 0xD0, 0xFE, 0x02,
+    ];
+    mem.load(&program, 0x0200).unwrap();
+
+    let mut addr = 0x0200;
+    let mut lines = Vec::new();
+    for _ in 0..6 {
+      let ins = disassemble(&mem, addr);
+      lines.push(ins.text.clone());
+      addr = ins.next();
+    }
+
+    assert_eq!(
+      lines,
+      ["LDA #$42", "STA ($10),Y", "JMP ($FFFC)", "ASL A", "BNE $0208", "JAM"],
+      "disassembly doesn't match the program"
+    );
+    assert_eq!(addr, 0x020B, "instruction lengths are off");
+  }
+
+  #[test]
+  fn illegal_opcode_policies() {
+    // LDA #$01 ; illegal ; LDX #$02
+    let program = [0xA9, 0x01, 0x02, 0xA2, 0x02];
+    let cpu = run(&program, 3);
+    assert_eq!(
+      cpu.reg_x, 0x02,
+      "the illegal opcode wasn't skipped as a NOP"
+    );
+
+    let mut cpu = run(&program, 1);
+    cpu.on_illegal_opcode = IllegalOpcodePolicy::Error;
+    assert_eq!(
+      cpu.clock(),
+      Err(Error::IllegalOpcode {
+        pc: 0x0202,
+        opcode: 0x02
+      }),
+      "the illegal opcode didn't stop the cpu"
+    );
+    assert_eq!(cpu.pc, 0x0202, "the pc moved past the illegal opcode");
+  }
+
+  #[test]
+  fn stack_policy_reports_wraps() {
+    // PHA ; PLA ; PLA
+    let mut cpu = run(&[0x48, 0x68, 0x68], 0);
+    cpu.on_stack_wrap = StackPolicy::Error;
+    cpu.sp = 0x00;
+    assert_eq!(
+      cpu.clock(),
+      Err(Error::StackOverflow { pc: 0x0200 }),
+      "pushing at $0100 didn't overflow"
+    );
+    assert_eq!(cpu.sp, 0xFF, "the stack pointer didn't wrap");
+
+    while cpu.cycles > 0 {
+      cpu.clock().unwrap();
+    }
+    assert_eq!(
+      cpu.clock(),
+      Err(Error::StackUnderflow { pc: 0x0201 }),
+      "pulling at $01FF didn't underflow"
+    );
+  }
+
+  #[test]
+  fn undocumented_opcodes() {
+    // LDA #$81 ; LDX #$F0 ; SAX $10 ; LAX $10 ; SLO $10 ; DCP $10 ; SBX #$7F
+    let program = [
+      0xA9, 0x81, 0xA2, 0xF0, 0x87, 0x10, 0xA7, 0x10, 0x07, 0x10, 0xC7, 0x10, 0xCB, 0x7F,
+    ];
+    let cpu = run_with(&program, 7, IllegalOpcodePolicy::Undocumented);
+    assert_eq!(cpu.mem.peek(0x10), 0xFF, "SAX, SLO or DCP went wrong");
+    assert_eq!(cpu.reg_a, 0x80, "LAX or SLO left A at {:#04x}", cpu.reg_a);
+    assert_eq!(cpu.reg_x, 0x01, "SBX left X at {:#04x}", cpu.reg_x);
+    assert!(cpu.status.is_flag_set(CpuStatus::C), "SBX didn't set C");
+
+    // the same bytes as NOPs
+    let cpu = run(&program, 7);
+    assert_eq!(cpu.pc, 0x020E, "a NOP took the wrong number of bytes");
+    assert_eq!(cpu.mem.peek(0x10), 0x00, "a NOP wrote to memory");
+    assert_eq!((cpu.reg_a, cpu.reg_x), (0x81, 0xF0), "a NOP changed A or X");
+
+    // SLO $0300 still takes its six cycles
+    let mut cpu = run(&[0x0F, 0x00, 0x03], 0);
+    cpu.clock().unwrap();
+    assert_eq!(cpu.cycles, 5, "the NOP didn't take SLO's cycles");
+  }
+
+  #[test]
+  fn jam_halts_until_reset() {
+    // LDA #$01 ; JAM ; LDX #$02
+    let mut cpu = run_with(
+      &[0xA9, 0x01, 0x02, 0xA2, 0x02],
+      3,
+      IllegalOpcodePolicy::Undocumented,
+    );
+    assert!(cpu.halted(), "JAM didn't halt the cpu");
+    assert_eq!(cpu.pc, 0x0202, "the pc moved off the JAM");
+    cpu.nmi();
+    cpu.clock().unwrap();
+    assert_eq!(cpu.reg_x, 0x00, "the halted cpu ran on");
+
+    cpu.reset();
+    assert!(!cpu.halted(), "reset didn't clear the halt");
+
+    // SLO $10 under the Halt policy
+    let cpu = run_with(&[0x07, 0x10], 1, IllegalOpcodePolicy::Halt);
+    assert!(cpu.halted(), "the Halt policy didn't halt");
+    assert_eq!(cpu.pc, 0x0200);
+  }
+
+  #[test]
+  fn rdy_stops_reads_but_not_writes() {
+    // STA $0300 ; LDX #$01
+    let mut cpu = run_with(&[0x8D, 0x00, 0x03, 0xA2, 0x01], 0, IllegalOpcodePolicy::Nop);
+    cpu.reg_a = 0x42;
+    cpu.set_rdy(false);
+    for _ in 0..10 {
+      cpu.clock().unwrap();
+    }
+    assert_eq!(cpu.pc, 0x0200, "the opcode fetch ran with RDY low");
+
+    cpu.set_rdy(true);
+    cpu.clock().unwrap();
+    cpu.set_rdy(false);
+    cpu.clock().unwrap();
+    assert_eq!(cpu.cycles, 3, "a read cycle ran with RDY low");
+
+    // the last cycle is the store's write
+    cpu.set_rdy(true);
+    cpu.clock().unwrap();
+    cpu.clock().unwrap();
+    cpu.set_rdy(false);
+    cpu.clock().unwrap();
+    assert_eq!(cpu.cycles, 0, "RDY stopped a write cycle");
+    cpu.clock().unwrap();
+    assert_eq!(cpu.reg_x, 0x00, "LDX ran with RDY low");
+  }
+
+  #[test]
+  fn so_and_sync_pins() {
+    // NOP ; CLV ; NOP
+    let mut cpu = run_with(&[0xEA, 0xB8, 0xEA], 0, IllegalOpcodePolicy::Nop);
+    cpu.set_so(true);
+    cpu.clock().unwrap();
+    assert!(cpu.sync(), "SYNC is low on an opcode fetch");
+    assert!(cpu.status.is_flag_set(CpuStatus::V), "SO didn't set V");
+    cpu.clock().unwrap();
+    assert!(!cpu.sync(), "SYNC is high after the opcode fetch");
+
+    // CLV, SO is still asserted but only its edge counts
+    cpu.clock().unwrap();
+    cpu.clock().unwrap();
+    assert!(
+      !cpu.status.is_flag_set(CpuStatus::V),
+      "a held SO set V again"
+    );
+    cpu.set_so(false);
+    cpu.clock().unwrap();
+    cpu.set_so(true);
+    cpu.clock().unwrap();
+    assert!(
+      cpu.status.is_flag_set(CpuStatus::V),
+      "a new SO edge didn't set V"
+    );
+  }
+
+  #[test]
+  fn dma_waits_for_write_cycles() {
+    // INC $10, its last two cycles write
+    let mut cpu = run_with(&[0xE6, 0x10], 0, IllegalOpcodePolicy::Nop);
+    for _ in 0..3 {
+      cpu.clock().unwrap();
+    }
+    cpu.mem.start_dma(Dma::hold(2));
+    cpu.clock().unwrap();
+    cpu.clock().unwrap();
+    assert_eq!(cpu.cycles, 0, "DMA took a write cycle");
+    assert_eq!(cpu.mem.dma_cycles(), 0);
+
+    cpu.clock().unwrap();
+    cpu.clock().unwrap();
+    assert_eq!(
+      cpu.mem.dma_cycles(),
+      2,
+      "DMA didn't get the bus after the writes"
+    );
+    assert_eq!(cpu.pc, 0x0202, "the cpu ran during DMA");
+  }
 }
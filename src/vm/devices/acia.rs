@@ -0,0 +1,226 @@
+use alloc::{boxed::Box, collections::VecDeque, vec::Vec};
+
+use super::{serial::SerialHost, Device};
+use crate::vm::defs::{Byte, Word};
+
+// register select
+const DATA: Word = 0x0;
+const STATUS: Word = 0x1;
+const COMMAND: Word = 0x2;
+// 0x3 is the control register
+
+// status bits
+const ST_OVERRUN: Byte = 0x04;
+const ST_RDRF: Byte = 0x08;
+const ST_TDRE: Byte = 0x10;
+const ST_IRQ: Byte = 0x80;
+
+// command bits
+const CMD_DTR: Byte = 0x01;
+const CMD_RX_IRQ_OFF: Byte = 0x02;
+const CMD_TX_MASK: Byte = 0x0C;
+const CMD_TX_IRQ: Byte = 0x04;
+const CMD_ECHO: Byte = 0x10;
+
+// MOS 6551 asynchronous communication interface adapter
+pub struct Acia {
+  host: Option<Box<dyn SerialHost>>,
+  // bytes waiting on the wire, fed by the host or `receive`
+  rx_queue: VecDeque<Byte>,
+  // transmitted bytes when there is no host attached
+  tx_buffer: Vec<Byte>,
+
+  rx_data: Byte,
+  tx_data: Byte,
+  status: Byte,
+  command: Byte,
+  control: Byte,
+
+  // cycles it takes to move one character, 0 is instant
+  char_cycles: u32,
+  // the host holds bytes back while RDRF is set, like RTS/CTS would
+  flow_control: bool,
+  tx_timer: u32,
+  rx_timer: u32,
+}
+
+impl Default for Acia {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Acia {
+  pub fn new() -> Self {
+    Self {
+      host: None,
+      rx_queue: VecDeque::new(),
+      tx_buffer: Vec::new(),
+
+      rx_data: 0,
+      tx_data: 0,
+      status: ST_TDRE,
+      command: 0,
+      control: 0,
+
+      char_cycles: 0,
+      flow_control: true,
+      tx_timer: 0,
+      rx_timer: 0,
+    }
+  }
+
+  pub fn with_host(host: Box<dyn SerialHost>) -> Self {
+    let mut new = Self::new();
+    new.host = Some(host);
+
+    new
+  }
+
+  // hardware reset, the programmed reset through the status register keeps the control register
+  pub fn reset(&mut self) {
+    self.status = ST_TDRE;
+    self.command = 0;
+    self.control = 0;
+    self.tx_timer = 0;
+    self.rx_timer = 0;
+  }
+
+  pub fn set_char_cycles(&mut self, cycles: u32) {
+    self.char_cycles = cycles;
+  }
+
+  // on by default. without it a byte that arrives before the last one was read is
+  // dropped and sets the overrun bit, like a line with no handshake
+  pub fn set_flow_control(&mut self, on: bool) {
+    self.flow_control = on;
+  }
+
+  /* ------- host side -------- */
+  pub fn receive(&mut self, data: Byte) {
+    self.rx_queue.push_back(data);
+  }
+
+  pub fn take_output(&mut self) -> Vec<Byte> {
+    core::mem::take(&mut self.tx_buffer)
+  }
+  /* ------- host side -------- */
+
+  fn transmit(&mut self, data: Byte) {
+    match &mut self.host {
+      Some(host) => host.send(data),
+      None => self.tx_buffer.push(data),
+    }
+  }
+
+  fn update_irq(&mut self) {
+    let rx_irq = self.status & ST_RDRF != 0 && self.command & (CMD_DTR | CMD_RX_IRQ_OFF) == CMD_DTR;
+    let tx_irq = self.status & ST_TDRE != 0 && self.command & CMD_TX_MASK == CMD_TX_IRQ;
+    if rx_irq || tx_irq {
+      self.status |= ST_IRQ;
+    }
+  }
+
+  fn poll_rx(&mut self) {
+    // the receiver is off until DTR is asserted
+    if self.command & CMD_DTR == 0 {
+      return;
+    }
+
+    if self.rx_queue.is_empty() {
+      if let Some(data) = self.host.as_mut().and_then(|h| h.recv()) {
+        self.rx_queue.push_back(data);
+      }
+    }
+
+    // the host can't see our handshake lines, so with flow control bytes are held
+    // back instead of overrunning
+    if self.status & ST_RDRF != 0 {
+      if !self.flow_control && self.rx_queue.pop_front().is_some() {
+        self.status |= ST_OVERRUN;
+      }
+      return;
+    }
+
+    if let Some(data) = self.rx_queue.pop_front() {
+      self.rx_data = data;
+      self.status |= ST_RDRF;
+      if self.command & CMD_ECHO != 0 {
+        self.transmit(data);
+      }
+      self.update_irq();
+    }
+  }
+}
+
+impl Device for Acia {
+  fn read(&mut self, addr: Word) -> Byte {
+    match addr & 0x03 {
+      DATA => {
+        self.status &= !(ST_RDRF | ST_OVERRUN | 0x03);
+        self.rx_data
+      }
+      STATUS => {
+        let status = self.status;
+        self.status &= !ST_IRQ;
+        status
+      }
+      reg => self.peek(reg),
+    }
+  }
+
+  fn write(&mut self, addr: Word, data: Byte) {
+    match addr & 0x03 {
+      DATA => {
+        self.tx_data = data;
+        if self.char_cycles == 0 {
+          self.transmit(data);
+          self.update_irq();
+        } else {
+          self.status &= !ST_TDRE;
+          self.tx_timer = self.char_cycles;
+        }
+      }
+      STATUS => {
+        self.command &= 0xE0;
+        self.status &= !ST_OVERRUN;
+      }
+      COMMAND => {
+        self.command = data;
+        self.update_irq();
+      }
+      _ => self.control = data,
+    }
+  }
+
+  fn peek(&self, addr: Word) -> Byte {
+    match addr & 0x03 {
+      DATA => self.rx_data,
+      STATUS => self.status,
+      COMMAND => self.command,
+      _ => self.control,
+    }
+  }
+
+  fn tick(&mut self) {
+    if self.tx_timer > 0 {
+      self.tx_timer -= 1;
+      if self.tx_timer == 0 {
+        self.transmit(self.tx_data);
+        self.status |= ST_TDRE;
+        self.update_irq();
+      }
+    }
+
+    if self.rx_timer > 0 {
+      self.rx_timer -= 1;
+    } else {
+      self.rx_timer = self.char_cycles;
+      self.poll_rx();
+    }
+  }
+
+  fn irq(&self) -> bool {
+    self.status & ST_IRQ != 0
+  }
+}
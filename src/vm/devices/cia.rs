@@ -0,0 +1,500 @@
+use super::Device;
+use crate::vm::defs::{Byte, Word};
+
+// register select
+const PRA: Word = 0x0;
+const PRB: Word = 0x1;
+const DDRA: Word = 0x2;
+const DDRB: Word = 0x3;
+const TA_LO: Word = 0x4;
+const TA_HI: Word = 0x5;
+const TB_LO: Word = 0x6;
+const TB_HI: Word = 0x7;
+const TOD_10THS: Word = 0x8;
+const TOD_SEC: Word = 0x9;
+const TOD_MIN: Word = 0xA;
+const TOD_HR: Word = 0xB;
+const SDR: Word = 0xC;
+const ICR: Word = 0xD;
+const CRA: Word = 0xE;
+
+// interrupt flags
+const IF_TA: Byte = 0x01;
+const IF_TB: Byte = 0x02;
+const IF_ALARM: Byte = 0x04;
+const IF_SP: Byte = 0x08;
+const IF_FLAG: Byte = 0x10;
+
+// control register bits
+const CR_START: Byte = 0x01;
+const CR_PBON: Byte = 0x02;
+const CR_TOGGLE: Byte = 0x04;
+const CR_ONESHOT: Byte = 0x08;
+const CR_LOAD: Byte = 0x10;
+const CRA_CNT: Byte = 0x20;
+const CRA_SPOUT: Byte = 0x40;
+const CRA_50HZ: Byte = 0x80;
+const CRB_ALARM: Byte = 0x80;
+
+struct Timer {
+  counter: Word,
+  latch: Word,
+  control: Byte,
+  // PB6/PB7 output level
+  out: bool,
+}
+
+impl Timer {
+  fn new() -> Self {
+    Self {
+      counter: 0xFFFF,
+      latch: 0xFFFF,
+      control: 0,
+      out: false,
+    }
+  }
+
+  fn write_control(&mut self, data: Byte) {
+    if data & CR_LOAD != 0 {
+      self.counter = self.latch;
+    }
+    if data & CR_START != 0 && self.control & CR_START == 0 {
+      self.out = true;
+    }
+    // LOAD is a strobe and never reads back
+    self.control = data & !CR_LOAD;
+  }
+
+  fn write_hi(&mut self, data: Byte) {
+    self.latch = (self.latch & 0x00FF) | (data as Word) << 8;
+    if self.control & CR_START == 0 {
+      self.counter = self.latch;
+    }
+  }
+
+  // counts one pulse, returns true on underflow
+  fn count(&mut self) -> bool {
+    if self.control & CR_START == 0 {
+      return false;
+    }
+    // pulse mode only holds the output high for one count
+    if self.control & CR_TOGGLE == 0 {
+      self.out = false;
+    }
+    if self.counter > 0 {
+      self.counter -= 1;
+      return false;
+    }
+
+    self.counter = self.latch;
+    if self.control & CR_ONESHOT != 0 {
+      self.control &= !CR_START;
+    }
+    self.out = if self.control & CR_TOGGLE != 0 {
+      !self.out
+    } else {
+      true
+    };
+
+    true
+  }
+}
+
+// time of day clock, all fields are BCD
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct Tod {
+  tenths: Byte,
+  sec: Byte,
+  min: Byte,
+  hr: Byte,
+}
+
+impl Tod {
+  fn bcd_inc(value: Byte) -> Byte {
+    if value & 0x0F == 0x09 {
+      (value & 0xF0) + 0x10
+    } else {
+      value + 1
+    }
+  }
+
+  fn advance(&mut self) {
+    self.tenths = (self.tenths + 1) % 10;
+    if self.tenths != 0 {
+      return;
+    }
+    self.sec = Self::bcd_inc(self.sec);
+    if self.sec != 0x60 {
+      return;
+    }
+    self.sec = 0;
+    self.min = Self::bcd_inc(self.min);
+    if self.min != 0x60 {
+      return;
+    }
+    self.min = 0;
+
+    // 12 hour clock with bit 7 as PM, 11 -> 12 flips AM/PM
+    let pm = self.hr & 0x80;
+    let hr = self.hr & 0x1F;
+    self.hr = match hr {
+      0x11 => 0x12 | (pm ^ 0x80),
+      0x12 => 0x01 | pm,
+      _ => Self::bcd_inc(hr) | pm,
+    };
+  }
+}
+
+// MOS 6526 complex interface adapter
+pub struct Cia {
+  pra: Byte,
+  prb: Byte,
+  ddra: Byte,
+  ddrb: Byte,
+  pins_a: Byte,
+  pins_b: Byte,
+
+  ta: Timer,
+  tb: Timer,
+
+  tod: Tod,
+  alarm: Tod,
+  tod_latch: Option<Tod>,
+  tod_stopped: bool,
+  tod_pulses: u8,
+  // cycles between TOD pin pulses, 0 when the host drives it
+  tod_cycles: u32,
+  tod_timer: u32,
+
+  sdr: Byte,
+  sr: Byte,
+  sr_bits: u8,
+  sr_pending: bool,
+  sp: bool,
+
+  icr: Byte,
+  icr_mask: Byte,
+
+  cnt: bool,
+  flag: bool,
+}
+
+impl Default for Cia {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Cia {
+  pub fn new() -> Self {
+    let tod = Tod {
+      tenths: 0,
+      sec: 0,
+      min: 0,
+      hr: 0x01,
+    };
+
+    Self {
+      pra: 0,
+      prb: 0,
+      ddra: 0,
+      ddrb: 0,
+      pins_a: 0xFF,
+      pins_b: 0xFF,
+
+      ta: Timer::new(),
+      tb: Timer::new(),
+
+      tod,
+      alarm: tod,
+      tod_latch: None,
+      tod_stopped: false,
+      tod_pulses: 0,
+      tod_cycles: 0,
+      tod_timer: 0,
+
+      sdr: 0,
+      sr: 0,
+      sr_bits: 0,
+      sr_pending: false,
+      sp: true,
+
+      icr: 0,
+      icr_mask: 0,
+
+      cnt: true,
+      flag: true,
+    }
+  }
+
+  pub fn reset(&mut self) {
+    let tod_cycles = self.tod_cycles;
+    *self = Self::new();
+    self.tod_cycles = tod_cycles;
+  }
+
+  // generates the TOD pin from the cpu clock, e.g. 985248 / 50 on a PAL c64
+  pub fn set_tod_cycles(&mut self, cycles: u32) {
+    self.tod_cycles = cycles;
+  }
+
+  /* ------- pins -------- */
+  pub fn port_a(&self) -> Byte {
+    (self.pra & self.ddra) | (self.pins_a & !self.ddra)
+  }
+
+  pub fn port_b(&self) -> Byte {
+    let mut data = (self.prb & self.ddrb) | (self.pins_b & !self.ddrb);
+    if self.ta.control & CR_PBON != 0 {
+      data = (data & !0x40) | if self.ta.out { 0x40 } else { 0x00 };
+    }
+    if self.tb.control & CR_PBON != 0 {
+      data = (data & !0x80) | if self.tb.out { 0x80 } else { 0x00 };
+    }
+    data
+  }
+
+  pub fn set_port_a(&mut self, data: Byte) {
+    self.pins_a = data;
+  }
+
+  pub fn set_port_b(&mut self, data: Byte) {
+    self.pins_b = data;
+  }
+
+  // a falling edge on FLAG sets its interrupt flag
+  pub fn set_flag(&mut self, level: bool) {
+    if self.flag && !level {
+      self.icr |= IF_FLAG;
+    }
+    self.flag = level;
+  }
+
+  pub fn set_cnt(&mut self, level: bool) {
+    let rising = !self.cnt && level;
+    self.cnt = level;
+    if !rising {
+      return;
+    }
+
+    if self.ta.control & CRA_CNT != 0 && self.ta.count() {
+      self.timer_a_underflow();
+    }
+    if self.tb.control & 0x60 == 0x20 && self.tb.count() {
+      self.icr |= IF_TB;
+    }
+
+    // serial input shifts in on rising CNT edges
+    if self.ta.control & CRA_SPOUT == 0 {
+      self.sr = (self.sr << 1) | self.sp as Byte;
+      self.sr_bits += 1;
+      if self.sr_bits == 8 {
+        self.sr_bits = 0;
+        self.sdr = self.sr;
+        self.icr |= IF_SP;
+      }
+    }
+  }
+
+  pub fn set_sp(&mut self, level: bool) {
+    self.sp = level;
+  }
+
+  pub fn sp(&self) -> bool {
+    self.sp
+  }
+
+  pub fn cnt(&self) -> bool {
+    self.cnt
+  }
+
+  // one pulse on the TOD pin, 50 or 60 a second
+  pub fn tod_pulse(&mut self) {
+    if self.tod_stopped {
+      return;
+    }
+    self.tod_pulses += 1;
+    let divider = if self.ta.control & CRA_50HZ != 0 {
+      5
+    } else {
+      6
+    };
+    if self.tod_pulses < divider {
+      return;
+    }
+    self.tod_pulses = 0;
+
+    self.tod.advance();
+    if self.tod == self.alarm {
+      self.icr |= IF_ALARM;
+    }
+  }
+  /* ------- pins -------- */
+
+  fn timer_a_underflow(&mut self) {
+    self.icr |= IF_TA;
+
+    // timer B can count timer A underflows, optionally gated by CNT
+    let count_b = match self.tb.control & 0x60 {
+      0x40 => true,
+      0x60 => self.cnt,
+      _ => false,
+    };
+    if count_b && self.tb.count() {
+      self.icr |= IF_TB;
+    }
+
+    // serial output clocks a bit every second underflow
+    if self.ta.control & CRA_SPOUT != 0 && (self.sr_bits > 0 || self.sr_pending) {
+      if self.sr_bits == 0 {
+        self.sr = self.sdr;
+        self.sr_bits = 16;
+        self.sr_pending = false;
+      }
+      self.cnt = !self.cnt;
+      if !self.cnt {
+        self.sp = self.sr & 0x80 != 0;
+        self.sr <<= 1;
+      }
+      self.sr_bits -= 1;
+      if self.sr_bits == 0 {
+        self.cnt = true;
+        self.icr |= IF_SP;
+      }
+    }
+  }
+
+  fn tod_read(&self) -> Tod {
+    self.tod_latch.unwrap_or(self.tod)
+  }
+
+  fn tod_write(&mut self, addr: Word, data: Byte) {
+    let target = if self.tb.control & CRB_ALARM != 0 {
+      &mut self.alarm
+    } else {
+      &mut self.tod
+    };
+    match addr {
+      TOD_10THS => target.tenths = data & 0x0F,
+      TOD_SEC => target.sec = data & 0x7F,
+      TOD_MIN => target.min = data & 0x7F,
+      _ => target.hr = data & 0x9F,
+    }
+
+    // writing the hours stops the clock until the tenths are written
+    if self.tb.control & CRB_ALARM == 0 {
+      match addr {
+        TOD_HR => self.tod_stopped = true,
+        TOD_10THS => self.tod_stopped = false,
+        _ => {}
+      }
+    }
+  }
+}
+
+impl Device for Cia {
+  fn read(&mut self, addr: Word) -> Byte {
+    match addr & 0x0F {
+      // reading the hours latches the clock until the tenths are read
+      TOD_HR => {
+        let tod = self.tod_read();
+        self.tod_latch = Some(tod);
+        tod.hr
+      }
+      TOD_10THS => {
+        let tenths = self.tod_read().tenths;
+        self.tod_latch = None;
+        tenths
+      }
+      ICR => {
+        let data = self.peek(ICR);
+        self.icr = 0;
+        data
+      }
+      reg => self.peek(reg),
+    }
+  }
+
+  fn write(&mut self, addr: Word, data: Byte) {
+    match addr & 0x0F {
+      PRA => self.pra = data,
+      PRB => self.prb = data,
+      DDRA => self.ddra = data,
+      DDRB => self.ddrb = data,
+      TA_LO => self.ta.latch = (self.ta.latch & 0xFF00) | data as Word,
+      TA_HI => self.ta.write_hi(data),
+      TB_LO => self.tb.latch = (self.tb.latch & 0xFF00) | data as Word,
+      TB_HI => self.tb.write_hi(data),
+      reg @ TOD_10THS..=TOD_HR => self.tod_write(reg, data),
+      SDR => {
+        self.sdr = data;
+        if self.ta.control & CRA_SPOUT != 0 {
+          self.sr_pending = true;
+        }
+      }
+      ICR => {
+        if data & 0x80 != 0 {
+          self.icr_mask |= data & 0x1F;
+        } else {
+          self.icr_mask &= !data;
+        }
+      }
+      CRA => {
+        if (data ^ self.ta.control) & CRA_SPOUT != 0 {
+          self.sr_bits = 0;
+          self.sr_pending = false;
+        }
+        self.ta.write_control(data);
+      }
+      _ => self.tb.write_control(data),
+    }
+  }
+
+  fn peek(&self, addr: Word) -> Byte {
+    match addr & 0x0F {
+      PRA => self.port_a(),
+      PRB => self.port_b(),
+      DDRA => self.ddra,
+      DDRB => self.ddrb,
+      TA_LO => self.ta.counter as Byte,
+      TA_HI => (self.ta.counter >> 8) as Byte,
+      TB_LO => self.tb.counter as Byte,
+      TB_HI => (self.tb.counter >> 8) as Byte,
+      TOD_10THS => self.tod_read().tenths,
+      TOD_SEC => self.tod_read().sec,
+      TOD_MIN => self.tod_read().min,
+      TOD_HR => self.tod_read().hr,
+      SDR => self.sdr,
+      ICR => {
+        if self.icr & self.icr_mask != 0 {
+          self.icr | 0x80
+        } else {
+          self.icr
+        }
+      }
+      CRA => self.ta.control,
+      _ => self.tb.control,
+    }
+  }
+
+  fn tick(&mut self) {
+    if self.ta.control & CRA_CNT == 0 && self.ta.count() {
+      self.timer_a_underflow();
+    }
+    if self.tb.control & 0x60 == 0 && self.tb.count() {
+      self.icr |= IF_TB;
+    }
+
+    if self.tod_cycles > 0 {
+      self.tod_timer += 1;
+      if self.tod_timer >= self.tod_cycles {
+        self.tod_timer = 0;
+        self.tod_pulse();
+      }
+    }
+  }
+
+  fn irq(&self) -> bool {
+    self.icr & self.icr_mask != 0
+  }
+}
@@ -0,0 +1,385 @@
+use alloc::{string::String, vec::Vec};
+
+use crate::vm::defs::Byte;
+
+// instruction bits
+const CMD_CLEAR: Byte = 0x01;
+const CMD_HOME: Byte = 0x02;
+const CMD_ENTRY_MODE: Byte = 0x04;
+const CMD_DISPLAY: Byte = 0x08;
+const CMD_SHIFT: Byte = 0x10;
+const CMD_FUNCTION: Byte = 0x20;
+const CMD_CGRAM: Byte = 0x40;
+const CMD_DDRAM: Byte = 0x80;
+
+// execution times in microseconds
+const SLOW_US: u32 = 1520;
+const FAST_US: u32 = 37;
+
+const LINE_LEN: usize = 40;
+
+// Hitachi HD44780 character LCD controller with the A00 character rom,
+// it isn't on the bus, the machine drives its pins from a port
+pub struct Hd44780 {
+  cols: usize,
+  rows: usize,
+
+  ddram: [Byte; 0x80],
+  cgram: [Byte; 0x40],
+  // address counter and whether it points into CGRAM
+  ac: Byte,
+  cgram_selected: bool,
+  // display start offset within a line
+  shift: usize,
+
+  increment: bool,
+  shift_on_write: bool,
+  display_on: bool,
+  cursor_on: bool,
+  blink_on: bool,
+  eight_bit: bool,
+  two_lines: bool,
+
+  // pin state
+  e: bool,
+  // 4-bit mode transfers the high nibble first
+  pending_nibble: Option<Byte>,
+  read_low_nibble: bool,
+
+  cycles_per_us: u32,
+  busy: u32,
+}
+
+impl Hd44780 {
+  // 16x2, 20x4 and the other common modules
+  pub fn new(cols: usize, rows: usize) -> Self {
+    assert!(
+      matches!(rows, 1 | 2 | 4) && (1..=LINE_LEN).contains(&cols),
+      "unsupported LCD geometry {}x{}",
+      cols,
+      rows
+    );
+
+    Self {
+      cols,
+      rows,
+
+      ddram: [0x20; 0x80],
+      cgram: [0; 0x40],
+      ac: 0,
+      cgram_selected: false,
+      shift: 0,
+
+      increment: true,
+      shift_on_write: false,
+      display_on: false,
+      cursor_on: false,
+      blink_on: false,
+      eight_bit: true,
+      two_lines: false,
+
+      e: false,
+      pending_nibble: None,
+      read_low_nibble: false,
+
+      cycles_per_us: 1,
+      busy: 0,
+    }
+  }
+
+  // the internal reset circuit, it leaves the contents alone
+  pub fn reset(&mut self) {
+    let (ddram, cgram) = (self.ddram, self.cgram);
+    *self = Self {
+      ddram,
+      cgram,
+      cycles_per_us: self.cycles_per_us,
+      ..Self::new(self.cols, self.rows)
+    };
+  }
+
+  // how many ticks make a microsecond, used to time the busy flag
+  pub fn set_cycles_per_us(&mut self, cycles: u32) {
+    self.cycles_per_us = cycles;
+  }
+
+  pub fn tick(&mut self) {
+    self.busy = self.busy.saturating_sub(1);
+  }
+
+  /* ------- pins -------- */
+  // drives the RS, R/W and E pins and the data bus, writes latch on the falling edge of E
+  // and while E is high a read returns what the LCD puts on the bus
+  pub fn pins(&mut self, rs: bool, rw: bool, e: bool, data: Byte) -> Option<Byte> {
+    let falling = self.e && !e;
+    let rising = !self.e && e;
+    self.e = e;
+
+    if rw {
+      if !e {
+        return None;
+      }
+      let data = self.bus_read(rs, rising);
+      return Some(data);
+    }
+
+    if falling {
+      self.bus_write(rs, data);
+    }
+
+    None
+  }
+  /* ------- pins -------- */
+
+  fn bus_read(&mut self, rs: bool, strobe: bool) -> Byte {
+    let data = if rs {
+      self.peek_data()
+    } else {
+      (if self.busy > 0 { 0x80 } else { 0x00 }) | self.ac
+    };
+
+    if self.eight_bit {
+      if strobe && rs {
+        self.advance();
+      }
+      return data;
+    }
+
+    // high nibble first, the data read only advances after the low one
+    let low = self.read_low_nibble;
+    if strobe {
+      self.read_low_nibble = !low;
+      if low && rs {
+        self.advance();
+      }
+    }
+    if low {
+      data << 4
+    } else {
+      data & 0xF0
+    }
+  }
+
+  fn bus_write(&mut self, rs: bool, data: Byte) {
+    let data = if self.eight_bit {
+      data
+    } else {
+      match self.pending_nibble.take() {
+        Some(high) => high | (data >> 4),
+        None => {
+          self.pending_nibble = Some(data & 0xF0);
+          return;
+        }
+      }
+    };
+
+    // the controller ignores the bus until it's done
+    if self.busy > 0 {
+      return;
+    }
+
+    if rs {
+      self.write_data(data);
+    } else {
+      self.command(data);
+    }
+  }
+
+  fn command(&mut self, cmd: Byte) {
+    let mut time = FAST_US;
+
+    if cmd & CMD_DDRAM != 0 {
+      self.ac = cmd & 0x7F;
+      self.cgram_selected = false;
+    } else if cmd & CMD_CGRAM != 0 {
+      self.ac = cmd & 0x3F;
+      self.cgram_selected = true;
+    } else if cmd & CMD_FUNCTION != 0 {
+      self.eight_bit = cmd & 0x10 != 0;
+      self.two_lines = cmd & 0x08 != 0;
+      self.pending_nibble = None;
+      self.read_low_nibble = false;
+    } else if cmd & CMD_SHIFT != 0 {
+      let right = cmd & 0x04 != 0;
+      if cmd & 0x08 != 0 {
+        self.shift_display(right);
+      } else {
+        self.move_cursor(right);
+      }
+    } else if cmd & CMD_DISPLAY != 0 {
+      self.display_on = cmd & 0x04 != 0;
+      self.cursor_on = cmd & 0x02 != 0;
+      self.blink_on = cmd & 0x01 != 0;
+    } else if cmd & CMD_ENTRY_MODE != 0 {
+      self.increment = cmd & 0x02 != 0;
+      self.shift_on_write = cmd & 0x01 != 0;
+    } else if cmd & CMD_HOME != 0 {
+      self.ac = 0;
+      self.cgram_selected = false;
+      self.shift = 0;
+      time = SLOW_US;
+    } else if cmd & CMD_CLEAR != 0 {
+      self.ddram = [0x20; 0x80];
+      self.ac = 0;
+      self.cgram_selected = false;
+      self.shift = 0;
+      self.increment = true;
+      time = SLOW_US;
+    }
+
+    self.busy = time * self.cycles_per_us;
+  }
+
+  fn write_data(&mut self, data: Byte) {
+    if self.cgram_selected {
+      self.cgram[self.ac as usize & 0x3F] = data;
+    } else {
+      self.ddram[self.ac as usize] = data;
+    }
+    self.advance();
+    if self.shift_on_write && !self.cgram_selected {
+      self.shift_display(!self.increment);
+    }
+    self.busy = FAST_US * self.cycles_per_us;
+  }
+
+  fn peek_data(&self) -> Byte {
+    if self.cgram_selected {
+      self.cgram[self.ac as usize & 0x3F]
+    } else {
+      self.ddram[self.ac as usize]
+    }
+  }
+
+  fn advance(&mut self) {
+    self.move_cursor(self.increment);
+  }
+
+  fn move_cursor(&mut self, right: bool) {
+    if self.cgram_selected {
+      self.ac = if right {
+        self.ac + 1
+      } else {
+        self.ac.wrapping_sub(1)
+      } & 0x3F;
+      return;
+    }
+
+    // two line mode has two 40 character lines at $00 and $40, one line mode has 80 at $00
+    self.ac = match (self.two_lines, right, self.ac) {
+      (true, true, 0x27) => 0x40,
+      (true, true, 0x67) => 0x00,
+      (true, false, 0x00) => 0x67,
+      (true, false, 0x40) => 0x27,
+      (false, true, 0x4F) => 0x00,
+      (false, false, 0x00) => 0x4F,
+      (_, true, ac) => ac + 1,
+      (_, false, ac) => ac - 1,
+    };
+  }
+
+  fn shift_display(&mut self, right: bool) {
+    self.shift = if right {
+      (self.shift + LINE_LEN - 1) % LINE_LEN
+    } else {
+      (self.shift + 1) % LINE_LEN
+    };
+  }
+
+  // DDRAM address of a position on the glass, 4 row modules split each line in two
+  fn address(&self, row: usize, col: usize) -> usize {
+    if !self.two_lines {
+      let pos = row * self.cols + col + self.shift;
+      return pos % (LINE_LEN * 2);
+    }
+
+    let (line, offset) = match self.rows {
+      4 => (row % 2, (row / 2) * self.cols),
+      _ => (row, 0),
+    };
+    line * 0x40 + (offset + col + self.shift) % LINE_LEN
+  }
+
+  /* ------- glass -------- */
+  pub fn cols(&self) -> usize {
+    self.cols
+  }
+
+  pub fn rows(&self) -> usize {
+    self.rows
+  }
+
+  pub fn display_on(&self) -> bool {
+    self.display_on
+  }
+
+  pub fn busy(&self) -> bool {
+    self.busy > 0
+  }
+
+  // character codes on a row, as shown on the glass
+  pub fn row(&self, row: usize) -> Vec<Byte> {
+    (0..self.cols)
+      .map(|col| self.ddram[self.address(row, col)])
+      .collect()
+  }
+
+  // a row as text, blank while the display is off
+  pub fn line(&self, row: usize) -> String {
+    if !self.display_on {
+      return " ".repeat(self.cols);
+    }
+    self.row(row).into_iter().map(Self::glyph).collect()
+  }
+
+  pub fn lines(&self) -> Vec<String> {
+    (0..self.rows).map(|row| self.line(row)).collect()
+  }
+
+  // row and column of the cursor when it's shown and on the glass
+  pub fn cursor(&self) -> Option<(usize, usize)> {
+    if !self.display_on || !(self.cursor_on || self.blink_on) || self.cgram_selected {
+      return None;
+    }
+    (0..self.rows)
+      .flat_map(|row| (0..self.cols).map(move |col| (row, col)))
+      .find(|&(row, col)| self.address(row, col) == self.ac as usize)
+  }
+
+  pub fn ddram(&self) -> &[Byte] {
+    &self.ddram
+  }
+
+  pub fn cgram(&self) -> &[Byte] {
+    &self.cgram
+  }
+
+  // closest unicode for a code in the A00 (japanese) character rom,
+  // custom characters and katakana don't have one
+  pub fn glyph(code: Byte) -> char {
+    match code {
+      0x00..=0x0F => '▒',
+      0x5C => '¥',
+      0x7E => '→',
+      0x7F => '←',
+      0x20..=0x7D => code as char,
+      0xA5 => '·',
+      0xDF => '°',
+      0xE0 => 'α',
+      0xE2 => 'β',
+      0xE3 => 'ε',
+      0xE4 => 'µ',
+      0xE5 => 'σ',
+      0xE6 => 'ρ',
+      0xF2 => 'θ',
+      0xF3 => '∞',
+      0xF4 => 'Ω',
+      0xF6 => 'Σ',
+      0xF7 => 'π',
+      0xFD => '÷',
+      0xFF => '█',
+      _ => '?',
+    }
+  }
+  /* ------- glass -------- */
+}
@@ -0,0 +1,82 @@
+use alloc::rc::Rc;
+use core::cell::RefCell;
+
+use super::{
+  defs::{Byte, Word},
+  dma::Dma,
+};
+
+mod acia;
+mod cia;
+mod lcd;
+mod pia;
+mod port6510;
+mod riot;
+mod rom;
+mod rriot;
+mod serial;
+mod test;
+mod via;
+
+pub use acia::Acia;
+pub use cia::Cia;
+pub use lcd::Hd44780;
+pub use pia::Pia;
+pub use port6510::Port6510;
+pub use riot::Riot;
+pub use rom::Rom;
+pub use rriot::Rriot;
+#[cfg(feature = "std")]
+pub use serial::HostSerial;
+pub use serial::SerialHost;
+pub use via::Via;
+
+// a memory mapped peripheral, `addr` is relative to the start of the mapping
+pub trait Device {
+  fn read(&mut self, addr: Word) -> Byte;
+  fn write(&mut self, addr: Word, data: Byte);
+
+  // same as `read` but without side effects, for debuggers
+  fn peek(&self, addr: Word) -> Byte;
+
+  // called once per cpu cycle
+  fn tick(&mut self) {}
+
+  // level of the (active) irq output
+  fn irq(&self) -> bool {
+    false
+  }
+
+  // level of the rdy output, low stops the cpu on its next read for DMA
+  fn rdy(&self) -> bool {
+    true
+  }
+
+  // level of the (active) set overflow output, like the 1541's byte ready
+  fn so(&self) -> bool {
+    false
+  }
+
+  // SYNC, the cpu is fetching the opcode at `addr`
+  fn sync(&mut self, _: Word) {}
+
+  // a transfer the device wants the bus for, asked after every write to it and tick
+  fn dma(&mut self) -> Option<Dma> {
+    None
+  }
+}
+
+// devices are shared so the host can still poke at their pins once mapped
+pub type DeviceRef = Rc<RefCell<dyn Device>>;
+
+pub struct Mapping {
+  pub start: Word,
+  pub len: usize,
+  pub device: DeviceRef,
+}
+
+impl Mapping {
+  pub fn contains(&self, addr: Word) -> bool {
+    addr >= self.start && ((addr - self.start) as usize) < self.len
+  }
+}
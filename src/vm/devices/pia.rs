@@ -0,0 +1,222 @@
+use super::Device;
+use crate::vm::defs::{Byte, Word};
+
+// control register bits
+const CR_C1_IRQ: Byte = 0x01;
+const CR_C1_RISING: Byte = 0x02;
+const CR_OR_SELECT: Byte = 0x04;
+const CR_C2_OUTPUT: Byte = 0x20;
+const CR_IRQ2: Byte = 0x40;
+const CR_IRQ1: Byte = 0x80;
+
+struct Port {
+  or: Byte,
+  ddr: Byte,
+  cr: Byte,
+  pins: Byte,
+  c1: bool,
+  c2: bool,
+  c2_out: bool,
+  c2_pulse: bool,
+}
+
+impl Port {
+  fn new() -> Self {
+    Self {
+      or: 0,
+      ddr: 0,
+      cr: 0,
+      pins: 0xFF,
+      c1: true,
+      c2: true,
+      c2_out: true,
+      c2_pulse: false,
+    }
+  }
+
+  fn level(&self) -> Byte {
+    (self.or & self.ddr) | (self.pins & !self.ddr)
+  }
+
+  fn read(&mut self) -> Byte {
+    self.cr &= !(CR_IRQ1 | CR_IRQ2);
+    self.level()
+  }
+
+  fn set_c1(&mut self, level: bool) {
+    if level != self.c1 && level == (self.cr & CR_C1_RISING != 0) {
+      self.cr |= CR_IRQ1;
+      // handshake mode waits for the active C1 edge
+      if self.cr & 0x38 == 0x20 {
+        self.c2_out = true;
+      }
+    }
+    self.c1 = level;
+  }
+
+  fn set_c2(&mut self, level: bool) {
+    if self.cr & CR_C2_OUTPUT == 0 && level != self.c2 && level == (self.cr & 0x10 != 0) {
+      self.cr |= CR_IRQ2;
+    }
+    self.c2 = level;
+  }
+
+  fn c2(&self) -> bool {
+    match self.cr & 0x38 {
+      0x20 | 0x28 => self.c2_out,
+      0x30 => false,
+      0x38 => true,
+      _ => self.c2,
+    }
+  }
+
+  fn handshake(&mut self) {
+    match self.cr & 0x38 {
+      0x20 => self.c2_out = false,
+      0x28 => {
+        self.c2_out = false;
+        self.c2_pulse = true;
+      }
+      _ => {}
+    }
+  }
+
+  fn tick(&mut self) {
+    if self.c2_pulse {
+      self.c2_pulse = false;
+    } else if self.cr & 0x38 == 0x28 {
+      self.c2_out = true;
+    }
+  }
+
+  fn irq(&self) -> bool {
+    (self.cr & CR_IRQ1 != 0 && self.cr & CR_C1_IRQ != 0)
+      || (self.cr & CR_IRQ2 != 0 && self.cr & 0x28 == 0x08)
+  }
+}
+
+// MC6821 peripheral interface adapter
+pub struct Pia {
+  a: Port,
+  b: Port,
+}
+
+impl Default for Pia {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Pia {
+  pub fn new() -> Self {
+    Self {
+      a: Port::new(),
+      b: Port::new(),
+    }
+  }
+
+  pub fn reset(&mut self) {
+    *self = Self::new();
+  }
+
+  /* ------- pins -------- */
+  pub fn port_a(&self) -> Byte {
+    self.a.level()
+  }
+
+  pub fn port_b(&self) -> Byte {
+    self.b.level()
+  }
+
+  pub fn set_port_a(&mut self, data: Byte) {
+    self.a.pins = data;
+  }
+
+  pub fn set_port_b(&mut self, data: Byte) {
+    self.b.pins = data;
+  }
+
+  pub fn set_ca1(&mut self, level: bool) {
+    self.a.set_c1(level);
+  }
+
+  pub fn set_ca2(&mut self, level: bool) {
+    self.a.set_c2(level);
+  }
+
+  pub fn set_cb1(&mut self, level: bool) {
+    self.b.set_c1(level);
+  }
+
+  pub fn set_cb2(&mut self, level: bool) {
+    self.b.set_c2(level);
+  }
+
+  pub fn ca2(&self) -> bool {
+    self.a.c2()
+  }
+
+  pub fn cb2(&self) -> bool {
+    self.b.c2()
+  }
+
+  pub fn irq_a(&self) -> bool {
+    self.a.irq()
+  }
+
+  pub fn irq_b(&self) -> bool {
+    self.b.irq()
+  }
+  /* ------- pins -------- */
+}
+
+impl Device for Pia {
+  fn read(&mut self, addr: Word) -> Byte {
+    match addr & 0x03 {
+      // CA2 handshakes on reads of port A
+      0x00 if self.a.cr & CR_OR_SELECT != 0 => {
+        self.a.handshake();
+        self.a.read()
+      }
+      0x02 if self.b.cr & CR_OR_SELECT != 0 => self.b.read(),
+      reg => self.peek(reg),
+    }
+  }
+
+  fn write(&mut self, addr: Word, data: Byte) {
+    match addr & 0x03 {
+      0x00 if self.a.cr & CR_OR_SELECT != 0 => self.a.or = data,
+      0x00 => self.a.ddr = data,
+      // CB2 handshakes on writes to port B
+      0x02 if self.b.cr & CR_OR_SELECT != 0 => {
+        self.b.or = data;
+        self.b.handshake();
+      }
+      0x02 => self.b.ddr = data,
+      // the irq flags are read only
+      0x01 => self.a.cr = (self.a.cr & 0xC0) | (data & 0x3F),
+      _ => self.b.cr = (self.b.cr & 0xC0) | (data & 0x3F),
+    }
+  }
+
+  fn peek(&self, addr: Word) -> Byte {
+    match addr & 0x03 {
+      0x00 if self.a.cr & CR_OR_SELECT != 0 => self.a.level(),
+      0x00 => self.a.ddr,
+      0x02 if self.b.cr & CR_OR_SELECT != 0 => self.b.level(),
+      0x02 => self.b.ddr,
+      0x01 => self.a.cr,
+      _ => self.b.cr,
+    }
+  }
+
+  fn tick(&mut self) {
+    self.a.tick();
+    self.b.tick();
+  }
+
+  // IRQA and IRQB are usually tied together
+  fn irq(&self) -> bool {
+    self.a.irq() || self.b.irq()
+  }
+}
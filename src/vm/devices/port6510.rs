@@ -0,0 +1,65 @@
+use super::Device;
+use crate::vm::defs::{Byte, Word};
+
+// MOS 6510 on-chip I/O port, the direction register at $0000 and the data register at $0001.
+// only P0-P5 are bonded out, the C64 uses P0-P2 for banking and P3-P5 for the datasette
+pub struct Port6510 {
+  ddr: Byte,
+  data: Byte,
+  pins: Byte,
+}
+
+impl Default for Port6510 {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Port6510 {
+  pub fn new() -> Self {
+    Self {
+      ddr: 0,
+      data: 0,
+      // P0-P2 are pulled up and so is the cassette sense on P4
+      pins: 0x17,
+    }
+  }
+
+  pub fn reset(&mut self) {
+    self.ddr = 0;
+    self.data = 0;
+  }
+
+  /* ------- pins -------- */
+  // levels on the port, P6 and P7 read 0 unless they are outputs
+  pub fn port(&self) -> Byte {
+    (self.data & self.ddr) | (self.pins & !self.ddr)
+  }
+
+  pub fn set_port(&mut self, data: Byte) {
+    self.pins = data & 0x3F;
+  }
+  /* ------- pins -------- */
+}
+
+impl Device for Port6510 {
+  fn read(&mut self, addr: Word) -> Byte {
+    self.peek(addr)
+  }
+
+  fn write(&mut self, addr: Word, data: Byte) {
+    if addr & 0x01 == 0 {
+      self.ddr = data;
+    } else {
+      self.data = data;
+    }
+  }
+
+  fn peek(&self, addr: Word) -> Byte {
+    if addr & 0x01 == 0 {
+      self.ddr
+    } else {
+      self.port()
+    }
+  }
+}
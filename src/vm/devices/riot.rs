@@ -0,0 +1,267 @@
+use super::Device;
+use crate::vm::defs::{Byte, Word};
+
+// interrupt flags
+const IF_TIMER: Byte = 0x80;
+const IF_PA7: Byte = 0x40;
+
+const PRESCALERS: [u16; 4] = [1, 8, 64, 1024];
+
+// an I/O port, pins whose DDR bit is set drive the output register
+pub struct Port {
+  pub data: Byte,
+  pub ddr: Byte,
+  pins: Byte,
+}
+
+impl Port {
+  pub fn new() -> Self {
+    Self {
+      data: 0,
+      ddr: 0,
+      pins: 0xFF,
+    }
+  }
+
+  pub fn reset(&mut self) {
+    self.data = 0;
+    self.ddr = 0;
+  }
+
+  pub fn read(&self) -> Byte {
+    (self.data & self.ddr) | (self.pins & !self.ddr)
+  }
+
+  pub fn set_pins(&mut self, data: Byte) {
+    self.pins = data;
+  }
+}
+
+// the interval timer of the 6530 and 6532, counts down every 1, 8, 64 or 1024 cycles
+// and every cycle once it has passed zero
+pub struct IntervalTimer {
+  timer: Byte,
+  prescaler: u16,
+  prescale_count: u16,
+  irq_enabled: bool,
+  flag: bool,
+}
+
+impl IntervalTimer {
+  pub fn new() -> Self {
+    Self {
+      timer: 0xFF,
+      prescaler: 1024,
+      prescale_count: 0,
+      irq_enabled: false,
+      flag: false,
+    }
+  }
+
+  pub fn reset(&mut self) {
+    self.irq_enabled = false;
+    self.flag = false;
+  }
+
+  pub fn value(&self) -> Byte {
+    self.timer
+  }
+
+  // set once the timer passes zero
+  pub fn flag(&self) -> bool {
+    self.flag
+  }
+
+  // A0-A1 pick the prescaler and A3 enables the irq
+  pub fn write(&mut self, addr: Word, data: Byte) {
+    self.timer = data;
+    self.prescaler = PRESCALERS[(addr & 0x03) as usize];
+    self.prescale_count = 0;
+    self.irq_enabled = addr & 0x08 != 0;
+    self.flag = false;
+  }
+
+  // reading the timer clears the flag, A3 sets the irq enable
+  pub fn read(&mut self, addr: Word) -> Byte {
+    self.irq_enabled = addr & 0x08 != 0;
+    self.flag = false;
+    self.timer
+  }
+
+  pub fn tick(&mut self) {
+    if self.prescale_count > 0 {
+      self.prescale_count -= 1;
+      return;
+    }
+
+    let (timer, underflow) = self.timer.overflowing_sub(1);
+    self.timer = timer;
+    if underflow {
+      // after the interval is over it keeps counting every cycle
+      self.flag = true;
+      self.prescaler = 1;
+    }
+    self.prescale_count = self.prescaler - 1;
+  }
+
+  pub fn irq(&self) -> bool {
+    self.irq_enabled && self.flag
+  }
+}
+
+// MOS 6532 RAM-I/O-timer
+pub struct Riot {
+  ram: [Byte; 128],
+  // address bit wired to the RS pin, set selects I/O and clear selects RAM
+  rs: Word,
+
+  a: Port,
+  b: Port,
+  timer: IntervalTimer,
+
+  edge_positive: bool,
+  pa7_irq: bool,
+  pa7_flag: bool,
+}
+
+impl Default for Riot {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Riot {
+  // RS on A9, like the atari 2600
+  pub fn new() -> Self {
+    Self::with_rs(0x0200)
+  }
+
+  pub fn with_rs(rs: Word) -> Self {
+    Self {
+      ram: [0; 128],
+      rs,
+
+      a: Port::new(),
+      b: Port::new(),
+      timer: IntervalTimer::new(),
+
+      edge_positive: false,
+      pa7_irq: false,
+      pa7_flag: false,
+    }
+  }
+
+  pub fn reset(&mut self) {
+    self.a.reset();
+    self.b.reset();
+    self.timer.reset();
+    self.pa7_irq = false;
+    self.edge_positive = false;
+    self.pa7_flag = false;
+  }
+
+  /* ------- pins -------- */
+  pub fn port_a(&self) -> Byte {
+    self.a.read()
+  }
+
+  pub fn port_b(&self) -> Byte {
+    self.b.read()
+  }
+
+  pub fn set_port_a(&mut self, data: Byte) {
+    let before = self.port_a() & 0x80 != 0;
+    self.a.set_pins(data);
+    let after = self.port_a() & 0x80 != 0;
+    if before != after && after == self.edge_positive {
+      self.pa7_flag = true;
+    }
+  }
+
+  pub fn set_port_b(&mut self, data: Byte) {
+    self.b.set_pins(data);
+  }
+  /* ------- pins -------- */
+
+  pub fn ram(&self) -> &[Byte] {
+    &self.ram
+  }
+
+  fn flags(&self) -> Byte {
+    let mut flags = 0;
+    if self.timer.flag() {
+      flags |= IF_TIMER;
+    }
+    if self.pa7_flag {
+      flags |= IF_PA7;
+    }
+    flags
+  }
+}
+
+impl Device for Riot {
+  fn read(&mut self, addr: Word) -> Byte {
+    if addr & self.rs == 0 {
+      return self.ram[(addr & 0x7F) as usize];
+    }
+
+    match addr & 0x05 {
+      0x04 => self.timer.read(addr),
+      0x05 => {
+        let flags = self.flags();
+        self.pa7_flag = false;
+        flags
+      }
+      _ => self.peek(addr),
+    }
+  }
+
+  fn write(&mut self, addr: Word, data: Byte) {
+    if addr & self.rs == 0 {
+      self.ram[(addr & 0x7F) as usize] = data;
+      return;
+    }
+
+    if addr & 0x04 == 0 {
+      match addr & 0x03 {
+        0x00 => self.a.data = data,
+        0x01 => self.a.ddr = data,
+        0x02 => self.b.data = data,
+        _ => self.b.ddr = data,
+      }
+    } else if addr & 0x10 != 0 {
+      self.timer.write(addr, data);
+    } else {
+      // edge detect control
+      self.pa7_irq = addr & 0x01 != 0;
+      self.edge_positive = addr & 0x02 != 0;
+    }
+  }
+
+  fn peek(&self, addr: Word) -> Byte {
+    if addr & self.rs == 0 {
+      return self.ram[(addr & 0x7F) as usize];
+    }
+
+    if addr & 0x04 == 0 {
+      match addr & 0x03 {
+        0x00 => self.port_a(),
+        0x01 => self.a.ddr,
+        0x02 => self.port_b(),
+        _ => self.b.ddr,
+      }
+    } else if addr & 0x01 == 0 {
+      self.timer.value()
+    } else {
+      self.flags()
+    }
+  }
+
+  fn tick(&mut self) {
+    self.timer.tick();
+  }
+
+  fn irq(&self) -> bool {
+    self.timer.irq() || (self.pa7_irq && self.pa7_flag)
+  }
+}
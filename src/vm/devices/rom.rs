@@ -0,0 +1,39 @@
+use alloc::vec::Vec;
+
+use super::Device;
+use crate::vm::{
+  defs::{Byte, Word},
+  error::Error,
+};
+
+// read only memory, mirrored across the whole mapping and deaf to writes
+pub struct Rom {
+  data: Vec<Byte>,
+}
+
+impl Rom {
+  pub fn new(data: &[u8]) -> Result<Self, Error> {
+    if data.is_empty() {
+      return Err(Error::RomSize { rom: "a", len: 0 });
+    }
+    Ok(Self {
+      data: data.to_vec(),
+    })
+  }
+
+  pub fn data(&self) -> &[Byte] {
+    &self.data
+  }
+}
+
+impl Device for Rom {
+  fn read(&mut self, addr: Word) -> Byte {
+    self.peek(addr)
+  }
+
+  fn write(&mut self, _: Word, _: Byte) {}
+
+  fn peek(&self, addr: Word) -> Byte {
+    self.data[addr as usize % self.data.len()]
+  }
+}
@@ -0,0 +1,107 @@
+use super::{
+  riot::{IntervalTimer, Port},
+  Device,
+};
+use crate::vm::defs::{Byte, Word};
+
+const IF_TIMER: Byte = 0x80;
+
+// MOS 6530 RAM-ROM-I/O-timer, just the I/O and timer registers, its 64 bytes of RAM
+// and 1K mask ROM are plain memory and get mapped on their own. the ports and timer
+// are the 6532's
+pub struct Rriot {
+  a: Port,
+  b: Port,
+  timer: IntervalTimer,
+}
+
+impl Default for Rriot {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Rriot {
+  pub fn new() -> Self {
+    Self {
+      a: Port::new(),
+      b: Port::new(),
+      timer: IntervalTimer::new(),
+    }
+  }
+
+  pub fn reset(&mut self) {
+    self.a.reset();
+    self.b.reset();
+    self.timer.reset();
+  }
+
+  /* ------- pins -------- */
+  pub fn port_a(&self) -> Byte {
+    self.a.read()
+  }
+
+  pub fn port_b(&self) -> Byte {
+    self.b.read()
+  }
+
+  pub fn set_port_a(&mut self, data: Byte) {
+    self.a.set_pins(data);
+  }
+
+  pub fn set_port_b(&mut self, data: Byte) {
+    self.b.set_pins(data);
+  }
+  /* ------- pins -------- */
+}
+
+impl Device for Rriot {
+  fn read(&mut self, addr: Word) -> Byte {
+    if addr & 0x05 == 0x04 {
+      return self.timer.read(addr);
+    }
+    self.peek(addr)
+  }
+
+  fn write(&mut self, addr: Word, data: Byte) {
+    if addr & 0x04 == 0 {
+      match addr & 0x03 {
+        0x00 => self.a.data = data,
+        0x01 => self.a.ddr = data,
+        0x02 => self.b.data = data,
+        _ => self.b.ddr = data,
+      }
+      return;
+    }
+
+    self.timer.write(addr, data);
+  }
+
+  fn peek(&self, addr: Word) -> Byte {
+    if addr & 0x04 == 0 {
+      return match addr & 0x03 {
+        0x00 => self.port_a(),
+        0x01 => self.a.ddr,
+        0x02 => self.port_b(),
+        _ => self.b.ddr,
+      };
+    }
+
+    if addr & 0x01 == 0 {
+      self.timer.value()
+    } else if self.timer.flag() {
+      IF_TIMER
+    } else {
+      0
+    }
+  }
+
+  fn tick(&mut self) {
+    self.timer.tick();
+  }
+
+  // the irq comes out on PB7, the board decides where it goes
+  fn irq(&self) -> bool {
+    self.timer.irq()
+  }
+}
@@ -0,0 +1,112 @@
+#[cfg(feature = "std")]
+use std::{
+  fs::File,
+  io::{self, Read, Write},
+  path::Path,
+  sync::mpsc::{self, Receiver},
+  thread,
+};
+
+use crate::vm::defs::Byte;
+
+// the far end of a serial line
+pub trait SerialHost {
+  fn send(&mut self, data: Byte);
+  fn recv(&mut self) -> Option<Byte>;
+}
+
+// bridges a serial device to host streams through std, input is read on its own thread
+// so `recv` never blocks the emulation
+#[cfg(feature = "std")]
+pub struct HostSerial {
+  output: Box<dyn Write>,
+  input: Option<Receiver<Byte>>,
+}
+
+#[cfg(feature = "std")]
+impl HostSerial {
+  pub fn new<R: Read + Send + 'static>(output: Box<dyn Write>, input: Option<R>) -> Self {
+    Self {
+      output,
+      input: input.map(spawn_reader),
+    }
+  }
+
+  pub fn stdio() -> Self {
+    Self::new(Box::new(io::stdout()), Some(io::stdin()))
+  }
+
+  // transmit only, received bytes never arrive
+  pub fn file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+    let file = File::create(path)?;
+    Ok(Self::new::<File>(Box::new(file), None))
+  }
+
+  // opens a pseudo-terminal and returns the path of its slave side,
+  // connect to it with `screen`, `minicom` or `picocom`
+  #[cfg(target_os = "linux")]
+  pub fn pty() -> io::Result<(Self, String)> {
+    use std::os::unix::io::FromRawFd;
+
+    // SAFETY: plain libc calls on a descriptor we own, checked for errors
+    unsafe {
+      let fd = libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY);
+      if fd < 0 {
+        return Err(io::Error::last_os_error());
+      }
+      let master = File::from_raw_fd(fd);
+      if libc::grantpt(fd) != 0 || libc::unlockpt(fd) != 0 {
+        return Err(io::Error::last_os_error());
+      }
+
+      // raw, or the line discipline echoes and rewrites what the guest sends
+      let mut termios = std::mem::zeroed::<libc::termios>();
+      if libc::tcgetattr(fd, &mut termios) != 0 {
+        return Err(io::Error::last_os_error());
+      }
+      libc::cfmakeraw(&mut termios);
+      if libc::tcsetattr(fd, libc::TCSANOW, &termios) != 0 {
+        return Err(io::Error::last_os_error());
+      }
+
+      let mut name = [0 as libc::c_char; 128];
+      if libc::ptsname_r(fd, name.as_mut_ptr(), name.len()) != 0 {
+        return Err(io::Error::last_os_error());
+      }
+      let path = std::ffi::CStr::from_ptr(name.as_ptr())
+        .to_string_lossy()
+        .into_owned();
+
+      let reader = master.try_clone()?;
+      Ok((Self::new(Box::new(master), Some(reader)), path))
+    }
+  }
+}
+
+#[cfg(feature = "std")]
+impl SerialHost for HostSerial {
+  fn send(&mut self, data: Byte) {
+    // a closed terminal shouldn't take the emulator down with it
+    let _ = self.output.write_all(&[data]);
+    let _ = self.output.flush();
+  }
+
+  fn recv(&mut self) -> Option<Byte> {
+    self.input.as_ref()?.try_recv().ok()
+  }
+}
+
+#[cfg(feature = "std")]
+fn spawn_reader<R: Read + Send + 'static>(mut input: R) -> Receiver<Byte> {
+  let (tx, rx) = mpsc::channel();
+  thread::spawn(move || {
+    let mut buf = [0; 64];
+    while let Ok(n) = input.read(&mut buf) {
+      if n == 0 || buf[..n].iter().any(|b| tx.send(*b).is_err()) {
+        break;
+      }
+    }
+  });
+
+  rx
+}
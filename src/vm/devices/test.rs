@@ -0,0 +1,698 @@
+#[cfg(test)]
+mod tests {
+  use std::{cell::RefCell, collections::VecDeque, fs, rc::Rc};
+
+  use crate::vm::{
+    devices::{Acia, Cia, Device, Hd44780, HostSerial, Riot, Rom, SerialHost, Via},
+    Error, Mem, CPU,
+  };
+
+  #[test]
+  fn rom_mirrors_and_ignores_writes() {
+    let mut rom = Rom::new(&[0x11, 0x22]).unwrap();
+    rom.write(0x0, 0x55);
+    assert_eq!(rom.read(0x0), 0x11, "a write reached the ROM");
+    assert_eq!(rom.read(0x3), 0x22, "the ROM isn't mirrored");
+    assert_eq!(
+      Rom::new(&[]).err(),
+      Some(Error::RomSize { rom: "a", len: 0 }),
+      "an empty ROM was made"
+    );
+  }
+
+  #[test]
+  fn via_ports_mix_outputs_and_inputs() {
+    let mut via = Via::new();
+    via.write(0x3, 0xF0); // DDRA
+    via.write(0x1, 0xAA);
+    via.set_port_a(0x05);
+
+    assert_eq!(
+      via.port_a(),
+      0xA5,
+      "port A pins are {:02X} instead of A5",
+      via.port_a()
+    );
+    assert_eq!(via.read(0x1), 0xA5, "IRA didn't read the pin levels");
+  }
+
+  #[test]
+  fn via_t1_one_shot_raises_irq_once() {
+    let mut via = Via::new();
+    via.write(0xE, 0x80 | 0x40); // enable T1
+    via.write(0x4, 0x02);
+    via.write(0x5, 0x00);
+
+    for _ in 0..2 {
+      via.tick();
+      assert!(!via.irq(), "T1 fired early");
+    }
+    via.tick();
+    assert!(via.irq(), "T1 didn't fire after N + 1 cycles");
+    assert_eq!(via.peek(0xD), 0xC0, "IFR doesn't show T1 and IRQ");
+
+    via.read(0x4);
+    assert!(!via.irq(), "reading T1C-L didn't clear the flag");
+
+    for _ in 0..0x20000 {
+      via.tick();
+    }
+    assert!(!via.irq(), "one-shot T1 fired twice");
+  }
+
+  #[test]
+  fn via_t1_free_run_toggles_pb7() {
+    let mut via = Via::new();
+    via.write(0xB, 0xC0); // free run + PB7
+    via.write(0x4, 0x03);
+    via.write(0x5, 0x00);
+    assert_eq!(
+      via.port_b() & 0x80,
+      0x00,
+      "PB7 didn't go low when T1 started"
+    );
+
+    let mut edges = 0;
+    let mut last = via.port_b() & 0x80;
+    for _ in 0..50 {
+      via.tick();
+      if via.port_b() & 0x80 != last {
+        edges += 1;
+        last = via.port_b() & 0x80;
+      }
+    }
+    // period is N + 2
+    assert_eq!(edges, 10, "PB7 toggled {} times instead of 10", edges);
+  }
+
+  #[test]
+  fn via_t2_counts_pb6_pulses() {
+    let mut via = Via::new();
+    via.write(0xB, 0x20);
+    via.write(0x8, 0x03);
+    via.write(0x9, 0x00);
+
+    for i in 0..3 {
+      via.tick();
+      assert_eq!(via.peek(0xD) & 0x20, 0x00, "T2 fired after {} pulses", i);
+      via.set_port_b(0xBF);
+      via.set_port_b(0xFF);
+    }
+    assert_eq!(via.peek(0xD) & 0x20, 0x20, "T2 didn't fire after 3 pulses");
+  }
+
+  #[test]
+  fn via_ier_set_and_clear() {
+    let mut via = Via::new();
+    via.write(0xE, 0x80 | 0x12);
+    via.write(0xE, 0x02);
+    assert_eq!(
+      via.peek(0xE),
+      0x90,
+      "IER is {:02X} instead of 90",
+      via.peek(0xE)
+    );
+  }
+
+  #[test]
+  fn via_ca1_edge_and_handshake() {
+    let mut via = Via::new();
+    via.write(0xC, 0x08); // CA1 negative edge, CA2 handshake
+    via.read(0x1);
+    assert!(!via.ca2(), "CA2 didn't go low after reading ORA");
+
+    via.set_ca1(false);
+    assert_eq!(via.peek(0xD) & 0x02, 0x02, "CA1 edge didn't set IFR");
+    assert!(via.ca2(), "CA2 didn't go high on the active CA1 edge");
+  }
+
+  #[test]
+  fn via_shift_out_under_phi2() {
+    let mut via = Via::new();
+    via.write(0xB, 0x18);
+    via.write(0xA, 0xA5);
+
+    let mut bits = 0u8;
+    for _ in 0..16 {
+      via.tick();
+      if via.cb1() {
+        bits = (bits << 1) | via.cb2() as u8;
+      }
+    }
+    assert_eq!(bits, 0xA5, "shifted out {:02X} instead of A5", bits);
+    assert_eq!(via.peek(0xD) & 0x04, 0x04, "SR flag isn't set after 8 bits");
+  }
+
+  #[test]
+  fn mapped_via_drives_irq_line() {
+    let via = Rc::new(RefCell::new(Via::new()));
+    let mut mem = Mem::new();
+    mem.map_device(0x6000, 0x2000, via.clone());
+
+    mem.write(0x600E, 0xC0);
+    mem.write(0x7FF4, 0x01); // mirrored T1C-L
+    mem.write(0x6005, 0x00);
+    mem.tick();
+    assert!(!mem.irq(), "irq line is active too early");
+    mem.tick();
+    assert!(mem.irq(), "T1 didn't reach the irq line");
+    assert_eq!(
+      via.borrow().peek(0xD),
+      0xC0,
+      "host can't see the mapped via"
+    );
+  }
+
+  #[test]
+  fn acia_receive_raises_irq() {
+    let mut acia = Acia::new();
+    acia.write(0x2, 0x09); // DTR, rx irq, no tx irq
+    acia.receive(b'A');
+    acia.receive(b'B');
+    acia.tick();
+
+    assert!(acia.irq(), "received byte didn't raise irq");
+    assert_eq!(
+      acia.read(0x1) & 0x88,
+      0x88,
+      "status doesn't show RDRF and IRQ"
+    );
+    assert!(!acia.irq(), "reading status didn't clear irq");
+    assert_eq!(acia.read(0x0), b'A', "received the wrong byte");
+
+    acia.tick();
+    assert_eq!(acia.read(0x0), b'B', "second byte was lost");
+    assert_eq!(
+      acia.peek(0x1) & 0x08,
+      0x00,
+      "RDRF still set after reading data"
+    );
+  }
+
+  #[test]
+  fn acia_transmit_takes_char_time() {
+    let mut acia = Acia::new();
+    acia.set_char_cycles(10);
+    acia.write(0x0, b'x');
+    assert_eq!(acia.peek(0x1) & 0x10, 0x00, "TDRE still set while sending");
+
+    for _ in 0..10 {
+      acia.tick();
+    }
+    assert_eq!(
+      acia.peek(0x1) & 0x10,
+      0x10,
+      "TDRE not set after the char time"
+    );
+    assert_eq!(acia.take_output(), b"x", "byte didn't reach the host");
+  }
+
+  // both ends of a serial line
+  #[derive(Clone, Default)]
+  struct Line {
+    sent: Rc<RefCell<Vec<u8>>>,
+    incoming: Rc<RefCell<VecDeque<u8>>>,
+  }
+
+  impl SerialHost for Line {
+    fn send(&mut self, data: u8) {
+      self.sent.borrow_mut().push(data);
+    }
+
+    fn recv(&mut self) -> Option<u8> {
+      self.incoming.borrow_mut().pop_front()
+    }
+  }
+
+  #[test]
+  fn acia_talks_to_its_host() {
+    let line = Line::default();
+    let mut acia = Acia::with_host(Box::new(line.clone()));
+    line.incoming.borrow_mut().extend(b"hi");
+
+    acia.tick();
+    assert_eq!(acia.peek(0x1) & 0x08, 0x00, "received with DTR off");
+
+    acia.write(0x2, 0x13); // DTR, no rx irq, echo
+    acia.tick();
+    assert!(!acia.irq(), "rx irq wasn't disabled");
+    assert_eq!(acia.read(0x0), b'h');
+    acia.tick();
+    assert_eq!(acia.read(0x0), b'i');
+
+    acia.write(0x0, b'!');
+    assert_eq!(*line.sent.borrow(), b"hi!", "echo or transmit went missing");
+    assert!(acia.take_output().is_empty(), "host bytes were buffered");
+  }
+
+  #[test]
+  fn acia_tx_irq_and_programmed_reset() {
+    let mut acia = Acia::new();
+    acia.write(0x3, 0x1F);
+    acia.write(0x2, 0x17); // DTR, no rx irq, tx irq, echo
+    assert!(acia.irq(), "empty transmitter didn't raise irq");
+    assert_eq!(
+      acia.read(0x1) & 0x90,
+      0x90,
+      "status doesn't show TDRE and IRQ"
+    );
+    assert!(!acia.irq(), "reading status didn't clear irq");
+
+    // a write to the status register resets everything but the control register
+    // and the top bits of the command register
+    acia.write(0x1, 0x00);
+    assert_eq!(acia.peek(0x2), 0x00, "command wasn't reset");
+    assert_eq!(acia.peek(0x3), 0x1F, "control was reset");
+
+    acia.write(0x2, 0x03);
+    acia.write(0x0, b'x');
+    assert!(!acia.irq(), "tx irq fired while disabled");
+  }
+
+  #[test]
+  fn acia_overruns_without_flow_control() {
+    let mut acia = Acia::new();
+    acia.set_flow_control(false);
+    acia.write(0x2, 0x03);
+    acia.receive(b'A');
+    acia.receive(b'B');
+    acia.tick();
+    acia.tick();
+
+    assert_eq!(
+      acia.peek(0x1) & 0x0C,
+      0x0C,
+      "status doesn't show RDRF and overrun"
+    );
+    assert_eq!(acia.read(0x0), b'A', "overrun replaced the unread byte");
+    assert_eq!(
+      acia.peek(0x1) & 0x0C,
+      0x00,
+      "reading data didn't clear them"
+    );
+    acia.tick();
+    assert_eq!(acia.peek(0x1) & 0x08, 0x00, "the dropped byte arrived");
+  }
+
+  #[test]
+  fn host_serial_file_bridge() {
+    let path = std::env::temp_dir().join(format!("g6502-serial-{}", std::process::id()));
+    let mut host = HostSerial::file(&path).unwrap();
+    b"ok\n".iter().for_each(|b| host.send(*b));
+    assert_eq!(host.recv(), None, "a file received something");
+    drop(host);
+
+    assert_eq!(fs::read(&path).unwrap(), b"ok\n");
+    fs::remove_file(path).unwrap();
+  }
+
+  #[cfg(target_os = "linux")]
+  #[test]
+  fn host_serial_pty_bridge() {
+    use std::{
+      io::{Read, Write},
+      thread,
+      time::{Duration, Instant},
+    };
+
+    let (mut host, path) = HostSerial::pty().unwrap();
+    let mut slave = fs::OpenOptions::new()
+      .read(true)
+      .write(true)
+      .open(&path)
+      .unwrap();
+
+    slave.write_all(b"ping").unwrap();
+    let mut received = Vec::new();
+    let start = Instant::now();
+    while received.len() < 4 && start.elapsed() < Duration::from_secs(5) {
+      match host.recv() {
+        Some(data) => received.push(data),
+        None => thread::sleep(Duration::from_millis(1)),
+      }
+    }
+    assert_eq!(received, b"ping", "the pty's input didn't arrive");
+
+    host.send(b'\r');
+    let mut data = [0; 1];
+    slave.read_exact(&mut data).unwrap();
+    assert_eq!(data, *b"\r", "raw mode didn't pass the byte through");
+  }
+
+  #[test]
+  fn riot_timer_prescaler() {
+    let mut riot = Riot::new();
+    riot.write(0x0080, 0x42);
+    assert_eq!(riot.read(0x0080), 0x42, "RIOT RAM didn't keep the value");
+
+    riot.write(0x029D, 0x02); // 8x prescaler, irq enabled
+    for _ in 0..16 {
+      riot.tick();
+    }
+    assert_eq!(riot.peek(0x0284), 0x00, "timer didn't count every 8 cycles");
+    assert!(!riot.irq(), "timer fired before passing zero");
+
+    riot.tick();
+    assert!(riot.irq(), "timer didn't fire after passing zero");
+    riot.tick();
+    assert_eq!(
+      riot.peek(0x0284),
+      0xFE,
+      "timer didn't switch to 1x after the interval"
+    );
+
+    riot.read(0x0284);
+    assert!(!riot.irq(), "reading the timer didn't clear the flag");
+  }
+
+  #[test]
+  fn riot_pa7_edge() {
+    let mut riot = Riot::new();
+    riot.write(0x0285, 0x00); // negative edge, irq enabled
+    riot.set_port_a(0x7F);
+
+    assert!(riot.irq(), "PA7 falling edge didn't raise irq");
+    assert_eq!(riot.read(0x0285) & 0x40, 0x40, "PA7 flag not set");
+    assert!(!riot.irq(), "reading the flags didn't clear PA7");
+  }
+
+  #[test]
+  fn riot_timer_flag_without_irq() {
+    let mut riot = Riot::new();
+    riot.write(0x0294, 0x01); // 1x prescaler, irq disabled
+    riot.tick();
+    riot.tick();
+    assert_eq!(riot.peek(0x0285) & 0x80, 0x80, "timer flag not set");
+    assert!(!riot.irq(), "timer raised a disabled irq");
+
+    // reading the flags leaves the timer flag, reading the timer with A3 clears it
+    riot.read(0x0285);
+    assert_eq!(riot.peek(0x0285) & 0x80, 0x80, "reading flags cleared it");
+    riot.read(0x028C);
+    assert_eq!(riot.peek(0x0285) & 0x80, 0x00, "reading the timer kept it");
+
+    // a read with A3 set enables the irq too
+    riot.write(0x0294, 0x01);
+    riot.tick();
+    assert!(!riot.irq(), "writing the timer didn't disable the irq");
+    riot.read(0x028C);
+    riot.tick();
+    assert!(riot.irq(), "the irq enabled on read didn't fire");
+    riot.write(0x029C, 0x10);
+    assert!(!riot.irq(), "writing the timer didn't clear the flag");
+  }
+
+  #[test]
+  fn riot_ports_mask_by_ddr() {
+    let mut riot = Riot::new();
+    riot.write(0x0281, 0x0F);
+    riot.write(0x0280, 0xA5);
+    riot.set_port_a(0x30);
+    assert_eq!(
+      riot.read(0x0280),
+      0x35,
+      "port A didn't mix outputs and inputs"
+    );
+    assert_eq!(riot.read(0x0281), 0x0F, "DDRA didn't read back");
+
+    riot.write(0x0283, 0xFF);
+    riot.write(0x0282, 0x5A);
+    riot.set_port_b(0x00);
+    assert_eq!(riot.port_b(), 0x5A, "inputs showed through outputs");
+
+    riot.reset();
+    assert_eq!(riot.port_a(), 0x30, "reset didn't make the pins inputs");
+  }
+
+  #[test]
+  fn cia_timer_a_continuous() {
+    let mut cia = Cia::new();
+    cia.write(0xD, 0x81);
+    cia.write(0x4, 0x02);
+    cia.write(0x5, 0x00);
+    cia.write(0xE, 0x11); // start + force load
+
+    for _ in 0..2 {
+      cia.tick();
+    }
+    assert!(!cia.irq(), "timer A fired early");
+    cia.tick();
+    assert!(cia.irq(), "timer A didn't fire after N + 1 cycles");
+    assert_eq!(cia.read(0xD), 0x81, "ICR doesn't show timer A");
+    assert!(!cia.irq(), "reading ICR didn't clear it");
+
+    for _ in 0..3 {
+      cia.tick();
+    }
+    assert!(cia.irq(), "timer A didn't reload from the latch");
+  }
+
+  #[test]
+  fn cia_timer_a_one_shot() {
+    let mut cia = Cia::new();
+    cia.write(0xD, 0x81);
+    cia.write(0x4, 0x01);
+    cia.write(0x5, 0x00);
+    cia.write(0xE, 0x19); // start, one shot, force load
+
+    cia.tick();
+    cia.tick();
+    assert!(cia.irq(), "one shot timer A didn't fire");
+    assert_eq!(cia.peek(0xE) & 0x01, 0x00, "one shot timer A didn't stop");
+    assert_eq!(cia.peek(0x4), 0x01, "timer A didn't reload from the latch");
+    cia.read(0xD);
+
+    for _ in 0..4 {
+      cia.tick();
+    }
+    assert!(!cia.irq(), "stopped timer A fired again");
+
+    // masked off, the flag is still there to poll
+    cia.write(0xD, 0x01);
+    cia.write(0xE, 0x19);
+    cia.tick();
+    cia.tick();
+    assert!(!cia.irq(), "masked timer A raised irq");
+    assert_eq!(cia.read(0xD), 0x01, "masked timer A flag wasn't set");
+  }
+
+  #[test]
+  fn cia_timer_a_drives_pb6() {
+    let mut cia = Cia::new();
+    cia.write(0x3, 0x00);
+    cia.write(0x4, 0x00);
+    cia.write(0x5, 0x00);
+    cia.write(0xE, 0x17); // start, PB6 on, toggle, force load
+
+    assert_eq!(cia.port_b() & 0x40, 0x40, "starting didn't set PB6");
+    cia.tick();
+    assert_eq!(cia.port_b() & 0x40, 0x00, "underflow didn't toggle PB6");
+    cia.tick();
+    assert_eq!(cia.port_b() & 0x40, 0x40, "PB6 didn't toggle back");
+  }
+
+  #[test]
+  fn cia_ports_mask_by_ddr() {
+    let mut cia = Cia::new();
+    cia.write(0x2, 0xF0);
+    cia.write(0x0, 0xAA);
+    cia.set_port_a(0x0F);
+    assert_eq!(cia.read(0x0), 0xAF, "port A didn't mix outputs and inputs");
+
+    cia.write(0x3, 0x00);
+    cia.write(0x1, 0xFF);
+    cia.set_port_b(0x12);
+    assert_eq!(
+      cia.read(0x1),
+      0x12,
+      "input port B showed the output register"
+    );
+    assert_eq!(cia.read(0x3), 0x00, "DDRB didn't read back");
+  }
+
+  #[test]
+  fn cia_timer_b_counts_timer_a() {
+    let mut cia = Cia::new();
+    cia.write(0x4, 0x00);
+    cia.write(0x5, 0x00);
+    cia.write(0x6, 0x01);
+    cia.write(0x7, 0x00);
+    cia.write(0xF, 0x49); // one shot, counts timer A
+    cia.write(0xE, 0x01);
+
+    cia.tick();
+    assert_eq!(
+      cia.peek(0xD) & 0x02,
+      0x00,
+      "timer B fired on the first underflow"
+    );
+    cia.tick();
+    assert_eq!(
+      cia.peek(0xD) & 0x02,
+      0x02,
+      "timer B didn't fire on the second underflow"
+    );
+    assert_eq!(cia.peek(0xF) & 0x01, 0x00, "one shot timer B didn't stop");
+  }
+
+  #[test]
+  fn cia_tod_and_alarm() {
+    let mut cia = Cia::new();
+    cia.write(0xE, 0x80); // 50Hz
+    cia.write(0xB, 0x11);
+    cia.write(0xA, 0x59);
+    cia.write(0x9, 0x59);
+    cia.write(0x8, 0x09);
+
+    cia.write(0xF, 0x80);
+    cia.write(0xB, 0x92);
+    cia.write(0xA, 0x00);
+    cia.write(0x9, 0x00);
+    cia.write(0x8, 0x00);
+    cia.write(0xD, 0x84);
+
+    for _ in 0..5 {
+      cia.tod_pulse();
+    }
+    assert_eq!(cia.read(0xB), 0x92, "TOD didn't roll over to 12 PM");
+    assert_eq!(cia.read(0x8), 0x00, "TOD tenths didn't roll over");
+    assert!(cia.irq(), "alarm didn't raise irq");
+  }
+
+  #[test]
+  fn cia_tod_latches_and_stops() {
+    let mut cia = Cia::new();
+    cia.write(0xE, 0x80); // 50Hz
+    cia.write(0xB, 0x01);
+    cia.write(0xA, 0x00);
+    cia.write(0x9, 0x00);
+    cia.write(0x8, 0x00);
+
+    // reading the hours freezes what's read until the tenths are
+    assert_eq!(cia.read(0xB), 0x01);
+    for _ in 0..5 {
+      cia.tod_pulse();
+    }
+    assert_eq!(cia.read(0x8), 0x00, "TOD read wasn't latched");
+    assert_eq!(
+      cia.read(0x8),
+      0x01,
+      "reading tenths didn't release the latch"
+    );
+
+    // writing the hours stops it until the tenths are written
+    cia.write(0xB, 0x02);
+    for _ in 0..5 {
+      cia.tod_pulse();
+    }
+    assert_eq!(cia.read(0x8), 0x01, "TOD ran after writing the hours");
+    cia.write(0x8, 0x05);
+    for _ in 0..5 {
+      cia.tod_pulse();
+    }
+    assert_eq!(cia.read(0x8), 0x06, "writing the tenths didn't restart TOD");
+
+    // 60Hz counts six pulses to a tenth
+    cia.write(0xE, 0x00);
+    for _ in 0..5 {
+      cia.tod_pulse();
+    }
+    assert_eq!(cia.read(0x8), 0x06, "60Hz TOD counted five pulses");
+    cia.tod_pulse();
+    assert_eq!(cia.read(0x8), 0x07, "60Hz TOD didn't count six pulses");
+  }
+
+  // strobes one 4-bit transfer on the LCD pins
+  fn lcd_nibble(lcd: &mut Hd44780, rs: bool, data: u8) {
+    lcd.pins(rs, false, true, data);
+    lcd.pins(rs, false, false, data);
+  }
+
+  #[test]
+  fn lcd_four_bit_mode() {
+    let mut lcd = Hd44780::new(16, 2);
+    // function set to 4-bit goes in as a single 8-bit transfer
+    lcd_nibble(&mut lcd, false, 0x20);
+    for byte in [0x28, 0x0C, 0x06] {
+      lcd_nibble(&mut lcd, false, byte);
+      lcd_nibble(&mut lcd, false, byte << 4);
+      while lcd.busy() {
+        lcd.tick();
+      }
+    }
+
+    lcd_nibble(&mut lcd, true, b'O');
+    lcd_nibble(&mut lcd, true, b'O' << 4);
+    assert_eq!(
+      lcd.line(0),
+      "O               ",
+      "LCD didn't show the character"
+    );
+    assert!(lcd.busy(), "LCD isn't busy after a write");
+
+    // the next write is dropped while busy
+    lcd_nibble(&mut lcd, true, b'K');
+    lcd_nibble(&mut lcd, true, b'K' << 4);
+    assert_eq!(lcd.line(0).trim_end(), "O", "LCD took a write while busy");
+
+    // busy flag and address counter, high nibble first
+    assert_eq!(lcd.pins(false, true, true, 0xFF), Some(0x80));
+    lcd.pins(false, true, false, 0xFF);
+    assert_eq!(lcd.pins(false, true, true, 0xFF), Some(0x10));
+  }
+
+  // watches SYNC and holds RDY, like a DMA controller
+  #[derive(Default)]
+  struct Dma {
+    fetches: Vec<u16>,
+    busy: bool,
+  }
+
+  impl Device for Dma {
+    fn read(&mut self, _: u16) -> u8 {
+      0
+    }
+
+    fn write(&mut self, _: u16, _: u8) {}
+
+    fn peek(&self, _: u16) -> u8 {
+      0
+    }
+
+    fn rdy(&self) -> bool {
+      !self.busy
+    }
+
+    fn sync(&mut self, addr: u16) {
+      self.fetches.push(addr);
+    }
+  }
+
+  #[test]
+  fn devices_see_sync_and_pull_rdy() {
+    let dma = Rc::new(RefCell::new(Dma::default()));
+    let mut mem = Mem::new();
+    mem.map_device(0xD000, 1, dma.clone());
+    // NOP ; NOP ; NOP
+    mem.load(&[0xEA, 0xEA, 0xEA], 0x0200).unwrap();
+    mem.load(&[0x00, 0x02], 0xFFFC).unwrap();
+    let mut cpu = CPU::new(mem);
+    cpu.cycles = 0;
+
+    for _ in 0..4 {
+      cpu.clock().unwrap();
+    }
+    assert_eq!(
+      dma.borrow().fetches,
+      [0x0200, 0x0201],
+      "SYNC missed a fetch"
+    );
+
+    dma.borrow_mut().busy = true;
+    for _ in 0..4 {
+      cpu.clock().unwrap();
+    }
+    assert_eq!(cpu.pc, 0x0202, "the cpu ran while a device held RDY");
+    assert_eq!(dma.borrow().fetches.len(), 2, "a stalled cycle raised SYNC");
+  }
+}
@@ -0,0 +1,518 @@
+use super::Device;
+use crate::vm::defs::{Byte, Word};
+
+// register select
+const ORB: Word = 0x0;
+const ORA: Word = 0x1;
+const DDRB: Word = 0x2;
+const DDRA: Word = 0x3;
+const T1C_L: Word = 0x4;
+const T1C_H: Word = 0x5;
+const T1L_L: Word = 0x6;
+const T1L_H: Word = 0x7;
+const T2C_L: Word = 0x8;
+const T2C_H: Word = 0x9;
+const SR: Word = 0xA;
+const ACR: Word = 0xB;
+const PCR: Word = 0xC;
+const IFR: Word = 0xD;
+const IER: Word = 0xE;
+const ORA_NH: Word = 0xF;
+
+// interrupt flags
+const IF_CA2: Byte = 0x01;
+const IF_CA1: Byte = 0x02;
+const IF_SR: Byte = 0x04;
+const IF_CB2: Byte = 0x08;
+const IF_CB1: Byte = 0x10;
+const IF_T2: Byte = 0x20;
+const IF_T1: Byte = 0x40;
+
+// MOS 6522 versatile interface adapter
+pub struct Via {
+  ora: Byte,
+  orb: Byte,
+  ddra: Byte,
+  ddrb: Byte,
+  // levels driven onto the pins from outside, inputs float high
+  pins_a: Byte,
+  pins_b: Byte,
+  ira_latch: Byte,
+  irb_latch: Byte,
+
+  t1_counter: Word,
+  t1_latch: Word,
+  t1_armed: bool,
+  t1_reload: bool,
+  pb7: bool,
+
+  t2_counter: Word,
+  t2_latch_lo: Byte,
+  t2_armed: bool,
+
+  sr: Byte,
+  sr_bits: u8,
+  sr_timer: Word,
+  sr_clock: bool,
+
+  acr: Byte,
+  pcr: Byte,
+  ifr: Byte,
+  ier: Byte,
+
+  ca1: bool,
+  ca2: bool,
+  cb1: bool,
+  cb2: bool,
+  ca2_out: bool,
+  cb2_out: bool,
+  ca2_pulse: bool,
+  cb2_pulse: bool,
+}
+
+impl Default for Via {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Via {
+  pub fn new() -> Self {
+    let mut new = Self {
+      ora: 0,
+      orb: 0,
+      ddra: 0,
+      ddrb: 0,
+      pins_a: 0xFF,
+      pins_b: 0xFF,
+      ira_latch: 0,
+      irb_latch: 0,
+
+      t1_counter: 0,
+      t1_latch: 0,
+      t1_armed: false,
+      t1_reload: false,
+      pb7: true,
+
+      t2_counter: 0,
+      t2_latch_lo: 0,
+      t2_armed: false,
+
+      sr: 0,
+      sr_bits: 8,
+      sr_timer: 0,
+      sr_clock: true,
+
+      acr: 0,
+      pcr: 0,
+      ifr: 0,
+      ier: 0,
+
+      ca1: true,
+      ca2: true,
+      cb1: true,
+      cb2: true,
+      ca2_out: true,
+      cb2_out: true,
+      ca2_pulse: false,
+      cb2_pulse: false,
+    };
+    new.reset();
+
+    new
+  }
+
+  // the reset pin clears every register except the timers and shift register
+  pub fn reset(&mut self) {
+    self.ora = 0;
+    self.orb = 0;
+    self.ddra = 0;
+    self.ddrb = 0;
+    self.acr = 0;
+    self.pcr = 0;
+    self.ifr = 0;
+    self.ier = 0;
+    self.t1_armed = false;
+    self.t2_armed = false;
+    self.sr_bits = 8;
+    self.pb7 = true;
+    self.ca2_out = true;
+    self.cb2_out = true;
+  }
+
+  /* ------- pins -------- */
+  // levels on the port A pins, outputs are driven by ORA and inputs by the outside world
+  pub fn port_a(&self) -> Byte {
+    (self.ora & self.ddra) | (self.pins_a & !self.ddra)
+  }
+
+  pub fn port_b(&self) -> Byte {
+    let mut data = (self.orb & self.ddrb) | (self.pins_b & !self.ddrb);
+    if self.acr & 0x80 != 0 {
+      data = (data & 0x7F) | if self.pb7 { 0x80 } else { 0x00 };
+    }
+    data
+  }
+
+  pub fn set_port_a(&mut self, data: Byte) {
+    self.pins_a = data;
+  }
+
+  pub fn set_port_b(&mut self, data: Byte) {
+    // pulse counting mode counts falling edges on PB6
+    if self.acr & 0x20 != 0 && self.pins_b & 0x40 != 0 && data & 0x40 == 0 {
+      self.t2_counter = self.t2_counter.wrapping_sub(1);
+      if self.t2_counter == 0 && self.t2_armed {
+        self.t2_armed = false;
+        self.ifr |= IF_T2;
+      }
+    }
+    self.pins_b = data;
+  }
+
+  pub fn set_ca1(&mut self, level: bool) {
+    if level != self.ca1 && level == (self.pcr & 0x01 != 0) {
+      self.ifr |= IF_CA1;
+      if self.acr & 0x01 != 0 {
+        self.ira_latch = self.port_a();
+      }
+      // read handshake completes on the active CA1 edge
+      if self.pcr & 0x0E == 0x08 {
+        self.ca2_out = true;
+      }
+    }
+    self.ca1 = level;
+  }
+
+  pub fn set_ca2(&mut self, level: bool) {
+    if self.pcr & 0x08 == 0 && level != self.ca2 && level == (self.pcr & 0x04 != 0) {
+      self.ifr |= IF_CA2;
+    }
+    self.ca2 = level;
+  }
+
+  pub fn set_cb1(&mut self, level: bool) {
+    if level != self.cb1 {
+      if level == (self.pcr & 0x10 != 0) {
+        self.ifr |= IF_CB1;
+        if self.acr & 0x02 != 0 {
+          self.irb_latch = self.port_b();
+        }
+        // write handshake completes on the active CB1 edge
+        if self.pcr & 0xE0 == 0x80 {
+          self.cb2_out = true;
+        }
+      }
+      // external shift clock, data moves on the rising edge
+      if self.acr & 0x0C == 0x0C && level {
+        self.shift();
+      }
+    }
+    self.cb1 = level;
+  }
+
+  pub fn set_cb2(&mut self, level: bool) {
+    if self.pcr & 0x80 == 0 && level != self.cb2 && level == (self.pcr & 0x40 != 0) {
+      self.ifr |= IF_CB2;
+    }
+    self.cb2 = level;
+  }
+
+  pub fn ca2(&self) -> bool {
+    match self.pcr & 0x0E {
+      0x08 | 0x0A => self.ca2_out,
+      0x0C => false,
+      0x0E => true,
+      _ => self.ca2,
+    }
+  }
+
+  pub fn cb2(&self) -> bool {
+    if self.acr & 0x10 != 0 {
+      return self.cb2_out;
+    }
+    match self.pcr & 0xE0 {
+      0x80 | 0xA0 => self.cb2_out,
+      0xC0 => false,
+      0xE0 => true,
+      _ => self.cb2,
+    }
+  }
+
+  // shift register clock as seen on CB1 when the via drives it
+  pub fn cb1(&self) -> bool {
+    match self.acr & 0x1C {
+      0x00 | 0x0C | 0x1C => self.cb1,
+      _ => self.sr_clock,
+    }
+  }
+  /* ------- pins -------- */
+
+  fn ifr(&self) -> Byte {
+    if self.ifr & self.ier & 0x7F != 0 {
+      self.ifr | 0x80
+    } else {
+      self.ifr
+    }
+  }
+
+  fn ira(&self) -> Byte {
+    if self.acr & 0x01 != 0 {
+      self.ira_latch
+    } else {
+      self.port_a()
+    }
+  }
+
+  fn irb(&self) -> Byte {
+    let input = if self.acr & 0x02 != 0 {
+      self.irb_latch
+    } else {
+      self.port_b()
+    };
+    (self.orb & self.ddrb) | (input & !self.ddrb)
+  }
+
+  // CA2/CB2 in "independent interrupt" mode keep their flag on port access
+  fn clear_ca_flags(&mut self) {
+    self.ifr &= !IF_CA1;
+    if self.pcr & 0x0A != 0x02 {
+      self.ifr &= !IF_CA2;
+    }
+  }
+
+  fn clear_cb_flags(&mut self) {
+    self.ifr &= !IF_CB1;
+    if self.pcr & 0xA0 != 0x20 {
+      self.ifr &= !IF_CB2;
+    }
+  }
+
+  fn ca2_handshake(&mut self) {
+    match self.pcr & 0x0E {
+      0x08 => self.ca2_out = false,
+      0x0A => {
+        self.ca2_out = false;
+        self.ca2_pulse = true;
+      }
+      _ => {}
+    }
+  }
+
+  fn cb2_handshake(&mut self) {
+    match self.pcr & 0xE0 {
+      0x80 => self.cb2_out = false,
+      0xA0 => {
+        self.cb2_out = false;
+        self.cb2_pulse = true;
+      }
+      _ => {}
+    }
+  }
+
+  fn start_shift(&mut self) {
+    self.ifr &= !IF_SR;
+    if self.acr & 0x1C != 0 {
+      self.sr_bits = 0;
+      self.sr_timer = self.t2_latch_lo as Word;
+    }
+  }
+
+  fn shift(&mut self) {
+    let mode = (self.acr >> 2) & 0x07;
+    // free running output never stops
+    if self.sr_bits >= 8 && mode != 0b100 {
+      return;
+    }
+
+    if mode & 0b100 != 0 {
+      let out = self.sr & 0x80 != 0;
+      self.sr = self.sr.rotate_left(1);
+      self.cb2_out = out;
+    } else {
+      self.sr = (self.sr << 1) | self.cb2 as Byte;
+    }
+
+    if mode != 0b100 {
+      self.sr_bits += 1;
+      if self.sr_bits == 8 {
+        self.ifr |= IF_SR;
+      }
+    }
+  }
+
+  fn tick_shift(&mut self) {
+    let mode = (self.acr >> 2) & 0x07;
+    let t2_rate = matches!(mode, 0b001 | 0b100 | 0b101);
+    let phi2 = matches!(mode, 0b010 | 0b110);
+    if !(t2_rate || phi2) || (self.sr_bits >= 8 && mode != 0b100) {
+      return;
+    }
+
+    if t2_rate {
+      if self.sr_timer > 0 {
+        self.sr_timer -= 1;
+        return;
+      }
+      self.sr_timer = self.t2_latch_lo as Word;
+    }
+
+    // one shift per full cycle of the CB1 clock
+    self.sr_clock = !self.sr_clock;
+    if self.sr_clock {
+      self.shift();
+    }
+  }
+}
+
+impl Device for Via {
+  fn read(&mut self, addr: Word) -> Byte {
+    match addr & 0x0F {
+      ORB => {
+        self.clear_cb_flags();
+        self.irb()
+      }
+      ORA => {
+        self.clear_ca_flags();
+        self.ca2_handshake();
+        self.ira()
+      }
+      T1C_L => {
+        self.ifr &= !IF_T1;
+        self.t1_counter as Byte
+      }
+      T2C_L => {
+        self.ifr &= !IF_T2;
+        self.t2_counter as Byte
+      }
+      SR => {
+        self.start_shift();
+        self.sr
+      }
+      reg => self.peek(reg),
+    }
+  }
+
+  fn write(&mut self, addr: Word, data: Byte) {
+    match addr & 0x0F {
+      ORB => {
+        self.orb = data;
+        self.clear_cb_flags();
+        self.cb2_handshake();
+      }
+      ORA => {
+        self.ora = data;
+        self.clear_ca_flags();
+        self.ca2_handshake();
+      }
+      DDRB => self.ddrb = data,
+      DDRA => self.ddra = data,
+      T1C_L | T1L_L => self.t1_latch = (self.t1_latch & 0xFF00) | data as Word,
+      T1C_H => {
+        self.t1_latch = (self.t1_latch & 0x00FF) | (data as Word) << 8;
+        self.t1_counter = self.t1_latch;
+        self.t1_armed = true;
+        self.t1_reload = false;
+        self.ifr &= !IF_T1;
+        self.pb7 = false;
+      }
+      T1L_H => {
+        self.t1_latch = (self.t1_latch & 0x00FF) | (data as Word) << 8;
+        self.ifr &= !IF_T1;
+      }
+      T2C_L => self.t2_latch_lo = data,
+      T2C_H => {
+        self.t2_counter = (data as Word) << 8 | self.t2_latch_lo as Word;
+        self.t2_armed = true;
+        self.ifr &= !IF_T2;
+      }
+      SR => {
+        self.sr = data;
+        self.start_shift();
+      }
+      ACR => self.acr = data,
+      PCR => self.pcr = data,
+      // writing a one clears the flag
+      IFR => self.ifr &= !(data & 0x7F),
+      IER => {
+        if data & 0x80 != 0 {
+          self.ier |= data & 0x7F;
+        } else {
+          self.ier &= !data;
+        }
+      }
+      _ => {
+        self.ora = data;
+      }
+    }
+  }
+
+  fn peek(&self, addr: Word) -> Byte {
+    match addr & 0x0F {
+      ORB => self.irb(),
+      ORA | ORA_NH => self.ira(),
+      DDRB => self.ddrb,
+      DDRA => self.ddra,
+      T1C_L => self.t1_counter as Byte,
+      T1C_H => (self.t1_counter >> 8) as Byte,
+      T1L_L => self.t1_latch as Byte,
+      T1L_H => (self.t1_latch >> 8) as Byte,
+      T2C_L => self.t2_counter as Byte,
+      T2C_H => (self.t2_counter >> 8) as Byte,
+      SR => self.sr,
+      ACR => self.acr,
+      PCR => self.pcr,
+      IFR => self.ifr(),
+      _ => self.ier | 0x80,
+    }
+  }
+
+  fn tick(&mut self) {
+    if self.ca2_pulse {
+      self.ca2_pulse = false;
+    } else if self.pcr & 0x0E == 0x0A {
+      self.ca2_out = true;
+    }
+    if self.cb2_pulse {
+      self.cb2_pulse = false;
+    } else if self.pcr & 0xE0 == 0xA0 {
+      self.cb2_out = true;
+    }
+
+    // T1, free running mode reloads from the latch one cycle after the underflow
+    if self.t1_reload {
+      self.t1_reload = false;
+      self.t1_counter = self.t1_latch;
+    } else {
+      let (counter, underflow) = self.t1_counter.overflowing_sub(1);
+      self.t1_counter = counter;
+      if underflow {
+        if self.acr & 0x40 != 0 {
+          self.ifr |= IF_T1;
+          self.pb7 = !self.pb7;
+          self.t1_reload = true;
+        } else if self.t1_armed {
+          self.ifr |= IF_T1;
+          self.pb7 = true;
+          self.t1_armed = false;
+        }
+      }
+    }
+
+    // T2, in pulse counting mode it's decremented by PB6 instead
+    if self.acr & 0x20 == 0 {
+      let (counter, underflow) = self.t2_counter.overflowing_sub(1);
+      self.t2_counter = counter;
+      if underflow && self.t2_armed {
+        self.ifr |= IF_T2;
+        self.t2_armed = false;
+      }
+    }
+
+    self.tick_shift();
+  }
+
+  fn irq(&self) -> bool {
+    self.ifr & self.ier & 0x7F != 0
+  }
+}
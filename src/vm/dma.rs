@@ -0,0 +1,56 @@
+use super::defs::{Byte, Word};
+
+// a transfer that takes the bus from the cpu. it holds the bus for `idle` cycles,
+// then copies `len` bytes from `src` to `dst` with a read and a write cycle each.
+// `src` and `dst` move on with every byte, unless `dst` is a port
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Dma {
+  pub src: Word,
+  pub dst: Word,
+  pub len: u16,
+  pub port: bool,
+  pub idle: u16,
+  // reads only happen on even cycles, so starting on an odd one costs a cycle
+  pub align: bool,
+}
+
+impl Dma {
+  pub fn copy(src: Word, dst: Word, len: u16) -> Self {
+    Self {
+      src,
+      dst,
+      len,
+      ..Self::default()
+    }
+  }
+
+  // every byte goes to the same address, like a device's data register
+  pub fn to_port(src: Word, port: Word, len: u16) -> Self {
+    Self {
+      port: true,
+      ..Self::copy(src, port, len)
+    }
+  }
+
+  // keeps the cpu off the bus without moving anything, like the VIC-II's badlines
+  pub fn hold(cycles: u16) -> Self {
+    Self {
+      idle: cycles,
+      ..Self::default()
+    }
+  }
+
+  // the NES's sprite DMA, a write to $4014 copies page `page` to OAMDATA after a
+  // halt cycle and an alignment cycle when it starts on an odd one. 513 or 514 cycles
+  pub fn nes_oam(page: Byte) -> Self {
+    Self {
+      idle: 1,
+      align: true,
+      ..Self::to_port((page as Word) << 8, 0x2004, 0x100)
+    }
+  }
+
+  pub fn is_done(&self) -> bool {
+    self.idle == 0 && self.len == 0
+  }
+}
@@ -0,0 +1,73 @@
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io;
+
+use super::defs::{Byte, Word};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+  // `len` bytes loaded at `addr` would run past the end of memory
+  OutOfBounds { addr: usize, len: usize },
+  // a ROM image that isn't a size the machine can take
+  RomSize { rom: &'static str, len: usize },
+  // more or less RAM than the machine can be fitted with
+  RamSize { machine: &'static str, kib: usize },
+  // a PRG shorter than its two byte load address
+  MissingLoadAddress,
+  // the opcode at `pc` isn't a documented one and the cpu was told to stop on them
+  IllegalOpcode { pc: Word, opcode: Byte },
+  // a push or pull that would have wrapped the stack pointer, `pc` is the instruction's
+  StackOverflow { pc: Word },
+  StackUnderflow { pc: Word },
+  // a bank window that was never added
+  NoWindow { window: usize },
+  // a bank past the end of its window's `banks`
+  NoBank { bank: usize, banks: usize },
+  // a clock frequency that's zero, negative or not a number
+  Frequency,
+}
+
+impl Error {
+  // whether the cpu cycle that returned the error still ran. an illegal opcode is turned
+  // down before its cycle starts, the stack errors come once the instruction has run
+  pub fn cycle_ran(&self) -> bool {
+    matches!(
+      self,
+      Error::StackOverflow { .. } | Error::StackUnderflow { .. }
+    )
+  }
+}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Error::OutOfBounds { addr, len } => {
+        write!(f, "{} bytes at ${:04X} don't fit in memory", len, addr)
+      }
+      Error::RomSize { rom, len } => write!(f, "{} ROM can't be {} bytes", rom, len),
+      Error::RamSize { machine, kib } => write!(f, "{} can't have {}K of RAM", machine, kib),
+      Error::MissingLoadAddress => write!(f, "PRG file is missing its load address"),
+      Error::IllegalOpcode { pc, opcode } => {
+        write!(f, "illegal opcode ${:02X} at ${:04X}", opcode, pc)
+      }
+      Error::StackOverflow { pc } => write!(f, "stack overflow at ${:04X}", pc),
+      Error::StackUnderflow { pc } => write!(f, "stack underflow at ${:04X}", pc),
+      Error::NoWindow { window } => write!(f, "there's no bank window {}", window),
+      Error::NoBank { bank, banks } => {
+        write!(f, "there's no bank {}, the window has {}", bank, banks)
+      }
+      Error::Frequency => write!(f, "a clock needs a frequency above 0"),
+    }
+  }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+// so the binaries' io::Result loops can use `?` on the machines
+#[cfg(feature = "std")]
+impl From<Error> for io::Error {
+  fn from(err: Error) -> Self {
+    io::Error::new(io::ErrorKind::Other, err)
+  }
+}
@@ -0,0 +1,131 @@
+use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+
+use crate::vm::{
+  defs::{Byte, Word},
+  devices::{Device, DeviceRef, Pia, Rom},
+  error::Error,
+  Mem, CPU,
+};
+
+pub const PIA_BASE: Word = 0xD010;
+pub const WOZMON_BASE: Word = 0xFF00;
+pub const BASIC_BASE: Word = 0xE000;
+
+// Apple-1: RAM from $0000, 4K more at $E000 for BASIC, the 6821 keyboard/display
+// PIA at $D010-$D013 and WozMon at $FF00
+pub struct Apple1 {
+  pub cpu: CPU,
+  pia: Rc<RefCell<Pia>>,
+  keys: VecDeque<Byte>,
+  output: Vec<Byte>,
+}
+
+impl Apple1 {
+  // the 14.318MHz crystal divided by 14
+  pub const CLOCK_HZ: u32 = 1_023_000;
+
+  pub fn new(ram_kib: usize, wozmon: &[u8]) -> Result<Self, Error> {
+    if !(4..=32).contains(&ram_kib) {
+      return Err(Error::RamSize {
+        machine: "Apple-1",
+        kib: ram_kib,
+      });
+    }
+    if wozmon.is_empty() || wozmon.len() > 0x100 {
+      return Err(Error::RomSize {
+        rom: "WozMon",
+        len: wozmon.len(),
+      });
+    }
+
+    let pia = Rc::new(RefCell::new(Pia::new()));
+    // unpopulated sockets, the data bus floats high
+    let empty = Rc::new(RefCell::new(Rom::new(&[0xFF])?));
+    let ram_end = ram_kib * 1024;
+
+    let mut mem = Mem::new();
+    mem.map_device(PIA_BASE, 4, pia.clone());
+    // neither PIA irq pin is connected
+    let pia_dev: DeviceRef = pia.clone();
+    mem.set_irq_wired(&pia_dev, false);
+    mem.map_device(WOZMON_BASE, 0x100, Rc::new(RefCell::new(Rom::new(wozmon)?)));
+    mem.map_device(ram_end as Word, PIA_BASE as usize - ram_end, empty.clone());
+    mem.map_device(
+      PIA_BASE + 4,
+      (BASIC_BASE - PIA_BASE - 4) as usize,
+      empty.clone(),
+    );
+    mem.map_device(
+      BASIC_BASE + 0x1000,
+      (WOZMON_BASE - BASIC_BASE - 0x1000) as usize,
+      empty,
+    );
+
+    let mut new = Self {
+      cpu: CPU::new(mem),
+      pia,
+      keys: VecDeque::new(),
+      output: Vec::new(),
+    };
+    new.reset();
+
+    Ok(new)
+  }
+
+  // Integer BASIC lives in RAM at $E000, it's loaded from tape on the real thing
+  pub fn load_basic(&mut self, data: &[u8]) -> Result<(), Error> {
+    self.cpu.mem.load(data, BASIC_BASE)
+  }
+
+  pub fn reset(&mut self) {
+    let mut pia = self.pia.borrow_mut();
+    pia.reset();
+    // PB7 is the display busy line, the terminal is always ready
+    pia.set_port_b(0x7F);
+    drop(pia);
+
+    self.keys.clear();
+    self.cpu.reset();
+  }
+
+  pub fn step(&mut self) -> Result<(), Error> {
+    self.cpu.clock()?;
+
+    let mut pia = self.pia.borrow_mut();
+
+    // the keyboard strobes CA1 once the last key was read
+    if pia.peek(0x01) & 0x80 == 0 {
+      if let Some(key) = self.keys.pop_front() {
+        pia.set_port_a(key | 0x80);
+        pia.set_ca1(false);
+        pia.set_ca1(true);
+      }
+    }
+
+    // a write to the display drops CB2, ack it on CB1 like the terminal section does
+    if !pia.cb2() {
+      self.output.push(pia.port_b() & 0x7F);
+      pia.set_cb1(false);
+      pia.set_cb1(true);
+    }
+
+    Ok(())
+  }
+
+  // queues an ascii key, the keyboard only has upper case and uses CR and '_' as rubout
+  pub fn key_press(&mut self, key: Byte) {
+    let key = match key {
+      b'\n' => b'\r',
+      0x08 | 0x7F => b'_',
+      _ => key.to_ascii_uppercase(),
+    };
+    if key < 0x80 {
+      self.keys.push_back(key);
+    }
+  }
+
+  // characters sent to the display since the last call, CR is a newline
+  pub fn take_output(&mut self) -> Vec<Byte> {
+    std::mem::take(&mut self.output)
+  }
+}
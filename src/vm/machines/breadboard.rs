@@ -0,0 +1,101 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::vm::{
+  defs::{Byte, Word},
+  devices::{Device, Hd44780, Rom, Via},
+  error::Error,
+  Mem, CPU,
+};
+
+pub const RAM_SIZE: usize = 0x4000;
+pub const VIA_BASE: Word = 0x6000;
+pub const ROM_BASE: Word = 0x8000;
+
+// LCD control lines on VIA port A, the data bus is all of port B
+pub const LCD_E: Byte = 0x80;
+pub const LCD_RW: Byte = 0x40;
+pub const LCD_RS: Byte = 0x20;
+
+const DDRA: Word = 0x03;
+
+const CYCLES_PER_US: u32 = Breadboard::CLOCK_HZ / 1_000_000;
+
+// the breadboard 6502 computer: 16K RAM at $0000, a 6522 VIA at $6000 mirrored up to
+// $7FFF, a 32K EEPROM at $8000 and a 16x2 HD44780 LCD hanging off the VIA
+pub struct Breadboard {
+  pub cpu: CPU,
+  via: Rc<RefCell<Via>>,
+  lcd: Hd44780,
+}
+
+impl Breadboard {
+  // the 1MHz crystal
+  pub const CLOCK_HZ: u32 = 1_000_000;
+
+  pub fn new(rom: &[u8]) -> Result<Self, Error> {
+    // the EEPROM holds 32K
+    if rom.is_empty() || rom.len() > 0x8000 {
+      return Err(Error::RomSize {
+        rom: "EEPROM",
+        len: rom.len(),
+      });
+    }
+
+    let via = Rc::new(RefCell::new(Via::new()));
+    let mut mem = Mem::new();
+    // nothing answers between the RAM and the VIA
+    mem.map_device(
+      RAM_SIZE as Word,
+      VIA_BASE as usize - RAM_SIZE,
+      Rc::new(RefCell::new(Rom::new(&[0xFF])?)),
+    );
+    mem.map_device(VIA_BASE, (ROM_BASE - VIA_BASE) as usize, via.clone());
+    mem.map_device(ROM_BASE, 0x8000, Rc::new(RefCell::new(Rom::new(rom)?)));
+
+    let mut lcd = Hd44780::new(16, 2);
+    lcd.set_cycles_per_us(CYCLES_PER_US);
+
+    let mut new = Self {
+      cpu: CPU::new(mem),
+      via,
+      lcd,
+    };
+    new.reset();
+
+    Ok(new)
+  }
+
+  // the reset button, the LCD has no reset line so it keeps going
+  pub fn reset(&mut self) {
+    self.via.borrow_mut().reset();
+    self.cpu.reset();
+  }
+
+  pub fn step(&mut self) -> Result<(), Error> {
+    self.cpu.clock()?;
+    self.lcd.tick();
+
+    let mut via = self.via.borrow_mut();
+    // the control lines have no pull-ups, only driven pins count
+    let ctrl = via.port_a() & via.peek(DDRA);
+    let bus = self.lcd.pins(
+      ctrl & LCD_RS != 0,
+      ctrl & LCD_RW != 0,
+      ctrl & LCD_E != 0,
+      via.port_b(),
+    );
+    // pull-ups hold the bus high when the LCD lets go of it
+    via.set_port_b(bus.unwrap_or(0xFF));
+
+    Ok(())
+  }
+
+  pub fn lcd(&self) -> &Hd44780 {
+    &self.lcd
+  }
+
+  // for buttons and anything else wired to the VIA
+  pub fn via(&self) -> Rc<RefCell<Via>> {
+    self.via.clone()
+  }
+}
@@ -0,0 +1,421 @@
+use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+
+use crate::vm::{
+  defs::{Byte, Word},
+  devices::{Cia, Device, DeviceRef, Port6510, Rom},
+  error::Error,
+  Bank, CpuStatus, Line, Mem, Scheduler, CPU,
+};
+
+pub const BASIC_BASE: Word = 0xA000;
+pub const IO_BASE: Word = 0xD000;
+pub const KERNAL_BASE: Word = 0xE000;
+
+// KERNAL jump table entries that get trapped
+pub const CHRIN: Word = 0xFFCF;
+pub const CHROUT: Word = 0xFFD2;
+pub const GETIN: Word = 0xFFE4;
+
+// KERNAL variables, the current input and output device numbers
+const DFLTN: Word = 0x0099;
+const DFLTO: Word = 0x009A;
+const KEYBOARD: Byte = 0;
+const SCREEN: Byte = 3;
+
+// BASIC program pointers, a program ends where its variables start
+const BASIC_START: Word = 0x0801;
+const VARTAB: Word = 0x002D;
+
+// PAL timing
+const CYCLES_PER_LINE: u16 = 63;
+const LINES: u16 = 312;
+const TOD_CYCLES: u32 = C64::CLOCK_HZ / 50;
+
+// the processor side of a C64: a 6510 whose port drives the PLA that banks BASIC, the
+// character ROM or I/O and the KERNAL over RAM. the VIC-II and SID are only register stubs,
+// screen and keyboard I/O goes through traps on the KERNAL's CHRIN, CHROUT and GETIN
+pub struct C64 {
+  pub cpu: CPU,
+  pub scheduler: Scheduler,
+  port: Rc<RefCell<Port6510>>,
+  io: Vec<DeviceRef>,
+  // BASIC, character ROM and KERNAL windows
+  windows: [usize; 3],
+  // P0-P2 the PLA was last set up for
+  pla: Option<Byte>,
+
+  traps: bool,
+  echo: bool,
+  waiting: bool,
+  input: VecDeque<Byte>,
+  output: Vec<Byte>,
+}
+
+impl C64 {
+  // the PAL clock
+  pub const CLOCK_HZ: u32 = 985_248;
+
+  pub fn new(basic: &[u8], kernal: &[u8], chargen: &[u8]) -> Result<Self, Error> {
+    for (rom, data, len) in [
+      ("BASIC", basic, 0x2000),
+      ("KERNAL", kernal, 0x2000),
+      ("character", chargen, 0x1000),
+    ] {
+      if data.len() != len {
+        return Err(Error::RomSize {
+          rom,
+          len: data.len(),
+        });
+      }
+    }
+
+    let mut mem = Mem::new();
+    let port = Rc::new(RefCell::new(Port6510::new()));
+    mem.map_device(0x0000, 2, port.clone());
+
+    let windows = [
+      mem.add_window(BASIC_BASE, 0x2000, vec![Bank::rom(basic)], Some(0))?,
+      mem.add_window(IO_BASE, 0x1000, vec![Bank::rom(chargen)], Some(0))?,
+      mem.add_window(KERNAL_BASE, 0x2000, vec![Bank::rom(kernal)], Some(0))?,
+    ];
+
+    let mut cia1 = Cia::new();
+    cia1.set_tod_cycles(TOD_CYCLES);
+    let mut cia2 = Cia::new();
+    cia2.set_tod_cycles(TOD_CYCLES);
+    let cia2 = Rc::new(RefCell::new(cia2));
+
+    let io: Vec<DeviceRef> = vec![
+      Rc::new(RefCell::new(Vic::new())),
+      Rc::new(RefCell::new(Sid)),
+      Rc::new(RefCell::new(ColorRam::new())),
+      Rc::new(RefCell::new(cia1)),
+      cia2.clone(),
+      // I/O1 and I/O2 with no cartridge in
+      Rc::new(RefCell::new(Rom::new(&[0xFF])?)),
+    ];
+    let ranges = [
+      (0xD000, 0x400),
+      (0xD400, 0x400),
+      (0xD800, 0x400),
+      (0xDC00, 0x100),
+      (0xDD00, 0x100),
+      (0xDE00, 0x200),
+    ];
+    for (device, (start, len)) in io.iter().zip(ranges) {
+      mem.map_device(start, len, device.clone());
+    }
+    // CIA2 is wired to NMI instead
+    let mut scheduler = Scheduler::new();
+    scheduler.attach(&mut mem, cia2, 1, Some(Line::Nmi));
+
+    let mut new = Self {
+      cpu: CPU::new(mem),
+      scheduler,
+      port,
+      io,
+      windows,
+      pla: None,
+
+      traps: true,
+      echo: true,
+      waiting: false,
+      input: VecDeque::new(),
+      output: Vec::new(),
+    };
+    new.reset();
+
+    Ok(new)
+  }
+
+  pub fn reset(&mut self) {
+    self.port.borrow_mut().reset();
+    self.update_pla();
+    self.scheduler.reset();
+    self.waiting = false;
+    self.input.clear();
+    self.cpu.reset();
+  }
+
+  // turns the KERNAL traps off, for running the real screen editor
+  pub fn set_traps(&mut self, on: bool) {
+    self.traps = on;
+  }
+
+  // whether CHRIN shows typed characters like the screen editor does
+  pub fn set_echo(&mut self, on: bool) {
+    self.echo = on;
+  }
+
+  pub fn step(&mut self) -> Result<(), Error> {
+    if self.cpu.cycles == 0 && self.traps && self.trap() {
+      return Ok(());
+    }
+    self.waiting = false;
+
+    self.scheduler.step(&mut self.cpu)?;
+    self.update_pla();
+
+    Ok(())
+  }
+
+  // P0-P2 are LORAM, HIRAM and CHAREN
+  fn update_pla(&mut self) {
+    let lines = self.port.borrow().port() & 0x07;
+    if self.pla == Some(lines) {
+      return;
+    }
+    self.pla = Some(lines);
+
+    let loram = lines & 0x01 != 0;
+    let hiram = lines & 0x02 != 0;
+    let charen = lines & 0x04 != 0;
+    let [basic, chargen, kernal] = self.windows;
+
+    // each window has its ROM as bank 0, so selecting it can't fail
+    let mem = &mut self.cpu.mem;
+    let _ = mem.select_bank(basic, (loram && hiram).then_some(0));
+    let _ = mem.select_bank(kernal, hiram.then_some(0));
+    // with both LORAM and HIRAM low it's all RAM
+    let _ = mem.select_bank(chargen, ((loram || hiram) && !charen).then_some(0));
+    for device in &self.io {
+      mem.set_device_enabled(device, (loram || hiram) && charen);
+    }
+  }
+
+  // runs a trapped KERNAL routine on the host, returns true to hold the cpu
+  fn trap(&mut self) -> bool {
+    let mem = &self.cpu.mem;
+    if mem.selected_bank(self.windows[2]).is_none() {
+      return false;
+    }
+
+    match self.cpu.pc {
+      CHROUT if mem.peek(DFLTO) == SCREEN => self.output.push(self.cpu.reg_a),
+      GETIN if mem.peek(DFLTN) == KEYBOARD => {
+        let key = self.input.pop_front().unwrap_or(0);
+        self.set_a(key);
+      }
+      CHRIN if mem.peek(DFLTN) == KEYBOARD => match self.input.pop_front() {
+        Some(key) => {
+          if self.echo {
+            self.output.push(key);
+          }
+          self.set_a(key);
+        }
+        // hold it here until there's a key
+        None => {
+          self.waiting = true;
+          return true;
+        }
+      },
+      _ => return false,
+    }
+
+    // the routines return with carry clear for no error
+    self.cpu.status.clear_flag(CpuStatus::C);
+    let lo = self.cpu.pull() as Word;
+    let hi = self.cpu.pull() as Word;
+    self.cpu.pc = ((hi << 8) | lo).wrapping_add(1);
+
+    false
+  }
+
+  fn set_a(&mut self, data: Byte) {
+    self.cpu.reg_a = data;
+    for (flag, set) in [(CpuStatus::Z, data == 0), (CpuStatus::N, data & 0x80 != 0)] {
+      if set {
+        self.cpu.status.set_flag(flag);
+      } else {
+        self.cpu.status.clear_flag(flag);
+      }
+    }
+  }
+
+  // loads a PRG file, BASIC programs also get their end pointers set up so they can be RUN
+  pub fn load_prg(&mut self, prg: &[u8]) -> Result<Word, Error> {
+    if prg.len() < 2 {
+      return Err(Error::MissingLoadAddress);
+    }
+    let start = prg[0] as Word | (prg[1] as Word) << 8;
+    let data = &prg[2..];
+    // the end pointers point past the program, so that has to be an address too
+    let end = start as u32 + data.len() as u32;
+    if start == BASIC_START && end > 0xFFFF {
+      return Err(Error::OutOfBounds {
+        addr: start as usize,
+        len: data.len(),
+      });
+    }
+    self.cpu.mem.load(data, start)?;
+
+    if start == BASIC_START {
+      let end = end as Word;
+      // VARTAB, ARYTAB and STREND
+      for ptr in [VARTAB, VARTAB + 2, VARTAB + 4] {
+        self.cpu.mem.write(ptr, end as Byte);
+        self.cpu.mem.write(ptr + 1, (end >> 8) as Byte);
+      }
+    }
+
+    Ok(start)
+  }
+
+  /* ------- host side -------- */
+  // queues an ascii key for CHRIN and GETIN
+  pub fn key_press(&mut self, key: Byte) {
+    if let Some(key) = ascii_to_petscii(key) {
+      self.input.push_back(key);
+    }
+  }
+
+  // true while CHRIN is holding for a key
+  pub fn waiting_for_input(&self) -> bool {
+    self.waiting
+  }
+
+  // PETSCII printed on the screen since the last call
+  pub fn take_output(&mut self) -> Vec<Byte> {
+    std::mem::take(&mut self.output)
+  }
+  /* ------- host side -------- */
+}
+
+// ascii as typed on the host, letters go in unshifted so they show as upper case
+pub fn ascii_to_petscii(key: Byte) -> Option<Byte> {
+  match key {
+    b'\n' | b'\r' => Some(0x0D),
+    0x08 | 0x7F => Some(0x14),
+    b'a'..=b'z' => Some(key.to_ascii_uppercase()),
+    0x20..=0x5D => Some(key),
+    _ => None,
+  }
+}
+
+// the printable part of PETSCII in the upper case/graphics set
+pub fn petscii_to_ascii(code: Byte) -> Option<char> {
+  match code {
+    0x0D => Some('\n'),
+    0x5C => Some('£'),
+    0x5E => Some('↑'),
+    0x5F => Some('←'),
+    0xA0 => Some(' '),
+    0x20..=0x5D => Some(code as char),
+    _ => None,
+  }
+}
+
+// VIC-II registers and the raster counter, enough for the KERNAL's PAL check and
+// raster interrupts, there is no video
+struct Vic {
+  regs: [Byte; 0x40],
+  raster: u16,
+  cycle: u16,
+  compare: u16,
+  irq_flags: Byte,
+}
+
+impl Vic {
+  fn new() -> Self {
+    Self {
+      regs: [0; 0x40],
+      raster: 0,
+      cycle: 0,
+      compare: 0,
+      irq_flags: 0,
+    }
+  }
+}
+
+impl Device for Vic {
+  fn read(&mut self, addr: Word) -> Byte {
+    self.peek(addr)
+  }
+
+  fn write(&mut self, addr: Word, data: Byte) {
+    let reg = (addr & 0x3F) as usize;
+    match reg {
+      0x11 => self.compare = (self.compare & 0xFF) | ((data as u16 & 0x80) << 1),
+      0x12 => {
+        self.compare = (self.compare & 0x100) | data as u16;
+        return;
+      }
+      // writing a 1 acknowledges an interrupt
+      0x19 => {
+        self.irq_flags &= !data;
+        return;
+      }
+      _ => {}
+    }
+    self.regs[reg] = data;
+  }
+
+  fn peek(&self, addr: Word) -> Byte {
+    let reg = (addr & 0x3F) as usize;
+    match reg {
+      0x11 => (self.regs[reg] & 0x7F) | ((self.raster >> 1) as Byte & 0x80),
+      0x12 => self.raster as Byte,
+      0x19 => self.irq_flags | 0x70 | if self.irq() { 0x80 } else { 0x00 },
+      0x1A => self.regs[reg] | 0xF0,
+      0x2F..=0x3F => 0xFF,
+      _ => self.regs[reg],
+    }
+  }
+
+  fn tick(&mut self) {
+    self.cycle += 1;
+    if self.cycle < CYCLES_PER_LINE {
+      return;
+    }
+
+    self.cycle = 0;
+    self.raster = (self.raster + 1) % LINES;
+    if self.raster == self.compare {
+      self.irq_flags |= 0x01;
+    }
+  }
+
+  fn irq(&self) -> bool {
+    self.irq_flags & self.regs[0x1A] & 0x0F != 0
+  }
+}
+
+// the SID's registers are write only and there's no sound, the readable ones read 0
+struct Sid;
+
+impl Device for Sid {
+  fn read(&mut self, addr: Word) -> Byte {
+    self.peek(addr)
+  }
+
+  fn write(&mut self, _: Word, _: Byte) {}
+
+  fn peek(&self, _: Word) -> Byte {
+    0
+  }
+}
+
+// 1K of 4 bit static RAM
+struct ColorRam {
+  data: [Byte; 0x400],
+}
+
+impl ColorRam {
+  fn new() -> Self {
+    Self { data: [0; 0x400] }
+  }
+}
+
+impl Device for ColorRam {
+  fn read(&mut self, addr: Word) -> Byte {
+    self.peek(addr)
+  }
+
+  fn write(&mut self, addr: Word, data: Byte) {
+    self.data[addr as usize & 0x3FF] = data & 0x0F;
+  }
+
+  fn peek(&self, addr: Word) -> Byte {
+    self.data[addr as usize & 0x3FF]
+  }
+}
@@ -0,0 +1,363 @@
+use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+
+use crate::vm::{
+  defs::{Byte, Word},
+  devices::{DeviceRef, Rom, Rriot},
+  error::Error,
+  Mem, CPU,
+};
+
+pub const RRIOT_003_BASE: Word = 0x1700;
+pub const RRIOT_002_BASE: Word = 0x1740;
+pub const MONITOR_BASE: Word = 0x1C00;
+
+// keypad codes as the monitor's GETKEY returns them, 0-F are the hex keys
+pub const KEY_AD: Byte = 0x10;
+pub const KEY_DA: Byte = 0x11;
+pub const KEY_PLUS: Byte = 0x12;
+pub const KEY_GO: Byte = 0x13;
+pub const KEY_PC: Byte = 0x14;
+
+// A13-A15 aren't decoded, the vectors at $FFFA are read through this mirror of the top 2K
+const MIRROR: Word = 0xE000;
+
+// 1MHz, a key is held long enough for the monitor to debounce it
+const KEY_CYCLES: u32 = 50_000;
+// a digit has to stay lit this long to be seen, the scan blanks between digits
+const LATCH_CYCLES: u64 = 100;
+// and goes dark once the program stops scanning it
+const FADE_CYCLES: u64 = 100_000;
+// 1200 baud, the monitor measures the rate from the first RUBOUT
+const BIT_CYCLES: u32 = 833;
+
+// KIM-1: 1K RAM at $0000, the 6530-003 and 6530-002 RRIOTs at $1700 and $1740 with their RAM
+// at $1780 and their ROMs at $1800 and $1C00, a six digit display and 23 key keypad on the
+// 002's ports and a bit-banged TTY interface on PA7/PB0, the rest of memory is expansion RAM
+pub struct Kim1 {
+  pub cpu: CPU,
+  rriot_002: Rc<RefCell<Rriot>>,
+  rriot_003: Rc<RefCell<Rriot>>,
+  tty: bool,
+
+  single_step: bool,
+  nmi_pending: bool,
+  cycles: u64,
+
+  keys: VecDeque<Byte>,
+  // the key being held, or none between keys
+  held: Option<Byte>,
+  key_timer: u32,
+
+  digits: [(Byte, u64); 6],
+  scan: (Option<usize>, Byte),
+  scan_since: u64,
+
+  tty_in: TtyIn,
+  tty_out: TtyOut,
+  output: Vec<Byte>,
+}
+
+impl Kim1 {
+  // the 1MHz crystal
+  pub const CLOCK_HZ: u32 = 1_000_000;
+
+  // `rom` is the 002 monitor ROM alone or the 003 and 002 ROMs back to back,
+  // `tty` puts in the jumper that starts the monitor in TTY mode
+  pub fn new(rom: &[u8], tty: bool) -> Result<Self, Error> {
+    if rom.len() != 0x400 && rom.len() != 0x800 {
+      return Err(Error::RomSize {
+        rom: "KIM-1",
+        len: rom.len(),
+      });
+    }
+
+    let rriot_002 = Rc::new(RefCell::new(Rriot::new()));
+    let rriot_003 = Rc::new(RefCell::new(Rriot::new()));
+    let rom_base = 0x2000 - rom.len() as Word;
+    let rom: DeviceRef = Rc::new(RefCell::new(Rom::new(rom)?));
+
+    let mut mem = Mem::new();
+    // K1-K4 are left for expansion, nothing answers on a bare board
+    mem.map_device(
+      0x0400,
+      (RRIOT_003_BASE - 0x0400) as usize,
+      Rc::new(RefCell::new(Rom::new(&[0xFF])?)),
+    );
+    for base in [0x0000, MIRROR] {
+      mem.map_device(base + RRIOT_003_BASE, 0x40, rriot_003.clone());
+      mem.map_device(base + RRIOT_002_BASE, 0x40, rriot_002.clone());
+      mem.map_device(base + rom_base, 0x2000 - rom_base as usize, rom.clone());
+    }
+
+    let mut new = Self {
+      cpu: CPU::new(mem),
+      rriot_002,
+      rriot_003,
+      tty,
+
+      single_step: false,
+      nmi_pending: false,
+      cycles: 0,
+
+      keys: VecDeque::new(),
+      held: None,
+      key_timer: 0,
+
+      digits: [(0, 0); 6],
+      scan: (None, 0),
+      scan_since: 0,
+
+      tty_in: TtyIn::new(),
+      tty_out: TtyOut::new(),
+      output: Vec::new(),
+    };
+    new.wire_timer_irq(false);
+    new.reset();
+
+    Ok(new)
+  }
+
+  // the RS key
+  pub fn reset(&mut self) {
+    self.rriot_002.borrow_mut().reset();
+    self.rriot_003.borrow_mut().reset();
+    self.keys.clear();
+    self.held = None;
+    self.nmi_pending = false;
+    self.tty_in = TtyIn::new();
+    self.cpu.reset();
+
+    // a RUBOUT lets the monitor measure the baud rate
+    if self.tty {
+      self.tty_in.queue.push_back(0x7F);
+    }
+  }
+
+  // the ST key
+  pub fn stop(&mut self) {
+    self.nmi_pending = true;
+  }
+
+  // the SST switch, it stops after every instruction outside the monitor
+  pub fn set_single_step(&mut self, on: bool) {
+    self.single_step = on;
+  }
+
+  pub fn single_step(&self) -> bool {
+    self.single_step
+  }
+
+  // both RRIOTs put their timer irq on PB7, a jumper takes it to the cpu
+  pub fn wire_timer_irq(&mut self, wired: bool) {
+    let rriot_002: DeviceRef = self.rriot_002.clone();
+    let rriot_003: DeviceRef = self.rriot_003.clone();
+    self.cpu.mem.set_irq_wired(&rriot_002, wired);
+    self.cpu.mem.set_irq_wired(&rriot_003, wired);
+  }
+
+  // the 003's ports are free for applications
+  pub fn rriot_003(&self) -> Rc<RefCell<Rriot>> {
+    self.rriot_003.clone()
+  }
+
+  pub fn step(&mut self) -> Result<(), Error> {
+    self.cpu.clock()?;
+    self.cycles += 1;
+
+    // NMI happens at the end of the instruction that was fetched while it was asserted
+    if self.cpu.cycles == 0 {
+      if self.nmi_pending {
+        self.nmi_pending = false;
+        self.cpu.nmi();
+      } else if self.single_step && self.cpu.pc & 0x1FFF < MONITOR_BASE {
+        self.nmi_pending = true;
+      }
+    }
+
+    self.step_keypad();
+    self.tty_in.tick();
+
+    let mut rriot = self.rriot_002.borrow_mut();
+    let port_b = rriot.port_b();
+    let rx = self.tty_in.line();
+
+    // PB1-PB4 go through a 74145, 0-2 select a keypad row, 3 the TTY jumper and 4-9 a digit
+    let select = (port_b >> 1) & 0x0F;
+    let mut pins = if rx { 0xFF } else { 0x7F };
+    match select {
+      0..=2 => {
+        if let Some(key) = self.held.filter(|key| key / 7 == select) {
+          pins &= !(0x40 >> (key % 7));
+        }
+      }
+      3 if self.tty => pins &= !0x01,
+      _ => {}
+    }
+    rriot.set_port_a(pins);
+
+    let digit = (4..=9).contains(&select).then(|| (select - 4) as usize);
+    let segments = rriot.port_a() & 0x7F;
+    drop(rriot);
+    self.scan_digit(digit, segments);
+
+    // the keyboard and printer share the current loop so typed characters echo
+    if let Some(data) = self.tty_out.sample(port_b & 0x01 != 0 && rx) {
+      self.output.push(data);
+    }
+
+    Ok(())
+  }
+
+  fn step_keypad(&mut self) {
+    if self.key_timer > 0 {
+      self.key_timer -= 1;
+      return;
+    }
+
+    // hold a key then let go of it for as long
+    self.held = match self.held {
+      Some(_) => None,
+      None => self.keys.pop_front(),
+    };
+    if self.held.is_some() || !self.keys.is_empty() {
+      self.key_timer = KEY_CYCLES;
+    }
+  }
+
+  fn scan_digit(&mut self, digit: Option<usize>, segments: Byte) {
+    if self.scan != (digit, segments) {
+      self.scan = (digit, segments);
+      self.scan_since = self.cycles;
+    }
+
+    if let Some(digit) = digit {
+      if self.cycles - self.scan_since >= LATCH_CYCLES {
+        self.digits[digit] = (segments, self.cycles);
+      }
+    }
+  }
+
+  /* ------- front panel -------- */
+  // presses and releases a keypad key, 0-F or one of the KEY_* codes
+  pub fn key_press(&mut self, key: Byte) {
+    if key <= KEY_PC {
+      self.keys.push_back(key);
+    }
+  }
+
+  // segments of the six digits, left to right, bit 0 is segment a and bit 6 is g
+  pub fn display(&self) -> [Byte; 6] {
+    let mut display = [0; 6];
+    for (shown, &(segments, lit_at)) in display.iter_mut().zip(self.digits.iter()) {
+      if self.cycles - lit_at < FADE_CYCLES {
+        *shown = segments;
+      }
+    }
+
+    display
+  }
+  /* ------- front panel -------- */
+
+  /* ------- tty -------- */
+  pub fn tty(&self) -> bool {
+    self.tty
+  }
+
+  pub fn tty_send(&mut self, data: Byte) {
+    self.tty_in.queue.push_back(data);
+  }
+
+  // characters printed on the TTY since the last call
+  pub fn take_output(&mut self) -> Vec<Byte> {
+    std::mem::take(&mut self.output)
+  }
+  /* ------- tty -------- */
+}
+
+// serializes host characters onto PA7, a start bit, 8 data bits and 2 stop bits
+struct TtyIn {
+  queue: VecDeque<Byte>,
+  frame: u16,
+  bits: u8,
+  timer: u32,
+}
+
+impl TtyIn {
+  fn new() -> Self {
+    Self {
+      queue: VecDeque::new(),
+      frame: 0,
+      bits: 0,
+      timer: 0,
+    }
+  }
+
+  // the line idles high
+  fn line(&self) -> bool {
+    self.bits == 0 || self.frame & 0x01 != 0
+  }
+
+  fn tick(&mut self) {
+    if self.bits == 0 {
+      if let Some(data) = self.queue.pop_front() {
+        self.frame = 0x600 | (data as u16) << 1;
+        self.bits = 11;
+        self.timer = BIT_CYCLES;
+      }
+      return;
+    }
+
+    self.timer -= 1;
+    if self.timer == 0 {
+      self.frame >>= 1;
+      self.bits -= 1;
+      self.timer = BIT_CYCLES;
+    }
+  }
+}
+
+// deserializes what the monitor bit-bangs on PB0, sampling in the middle of each bit
+struct TtyOut {
+  last: bool,
+  data: Byte,
+  bits: u8,
+  timer: u32,
+}
+
+impl TtyOut {
+  fn new() -> Self {
+    Self {
+      last: true,
+      data: 0,
+      bits: 0,
+      timer: 0,
+    }
+  }
+
+  fn sample(&mut self, line: bool) -> Option<Byte> {
+    let last = self.last;
+    self.last = line;
+
+    if self.bits == 0 {
+      // a start bit, the first data bit is a bit and a half away
+      if last && !line {
+        self.data = 0;
+        self.bits = 8;
+        self.timer = BIT_CYCLES + BIT_CYCLES / 2;
+      }
+      return None;
+    }
+
+    self.timer -= 1;
+    if self.timer > 0 {
+      return None;
+    }
+
+    self.data = (self.data >> 1) | if line { 0x80 } else { 0x00 };
+    self.bits -= 1;
+    self.timer = BIT_CYCLES;
+
+    // the TTY is 7 bit
+    (self.bits == 0).then_some(self.data & 0x7F)
+  }
+}
@@ -0,0 +1,16 @@
+mod apple1;
+mod breadboard;
+mod c64;
+mod kim1;
+mod test;
+mod test_machine;
+
+pub use apple1::Apple1;
+pub use breadboard::Breadboard;
+pub use c64::{ascii_to_petscii, petscii_to_ascii, C64};
+pub use kim1::{Kim1, KEY_AD, KEY_DA, KEY_GO, KEY_PC, KEY_PLUS};
+pub use test_machine::{
+  Assertion, TestMachine, TestReport, HOST_ASSERT, HOST_BASE, HOST_EXIT, HOST_FILE, HOST_IN,
+  HOST_LABEL, HOST_OUT, HOST_READ, HOST_REGISTERS, HOST_STATUS, STATUS_EOF, STATUS_FILE_ERROR,
+  STATUS_INPUT,
+};
@@ -0,0 +1,482 @@
+#[cfg(test)]
+mod tests {
+  use crate::vm::machines::{
+    Apple1, Assertion, Breadboard, Kim1, TestMachine, C64, HOST_ASSERT, HOST_BASE, HOST_EXIT,
+    HOST_FILE, HOST_LABEL, HOST_OUT, HOST_READ, HOST_REGISTERS, HOST_STATUS, KEY_GO,
+  };
+  use crate::vm::Error;
+
+  // sets up the PIA like WozMon and echoes every key
+  #[rustfmt::skip]
+  const ECHO_ROM: [u8; 0x100] = {
+    let mut rom = [0xEA; 0x100];
+    let prog = [
+      0xA0, 0x7F,       // LDY #$7F
+      0x8C, 0x12, 0xD0, // STY DSP
+      0xA9, 0xA7,       // LDA #$A7
+      0x8D, 0x11, 0xD0, // STA KBDCR
+      0x8D, 0x13, 0xD0, // STA DSPCR
+      0xAD, 0x11, 0xD0, // loop: LDA KBDCR
+      0x10, 0xFB,       // BPL loop
+      0xAD, 0x10, 0xD0, // LDA KBD
+      0x2C, 0x12, 0xD0, // echo: BIT DSP
+      0x30, 0xFB,       // BMI echo
+      0x8D, 0x12, 0xD0, // STA DSP
+      0x4C, 0x0D, 0xFF, // JMP loop
+    ];
+    let mut i = 0;
+    while i < prog.len() {
+      rom[i] = prog[i];
+      i += 1;
+    }
+    // reset vector
+    rom[0xFC] = 0x00;
+    rom[0xFD] = 0xFF;
+    rom
+  };
+
+  #[test]
+  fn apple1_echoes_keys() {
+    let mut apple1 = Apple1::new(4, &ECHO_ROM).unwrap();
+    apple1.key_press(b'h');
+    apple1.key_press(b'i');
+    apple1.key_press(b'\n');
+
+    for _ in 0..1000 {
+      apple1.step().unwrap();
+    }
+
+    assert_eq!(
+      apple1.take_output(),
+      b"HI\r",
+      "Apple-1 didn't echo the keys"
+    );
+  }
+
+  #[test]
+  fn apple1_unpopulated_ram() {
+    let mut apple1 = Apple1::new(4, &ECHO_ROM).unwrap();
+    apple1.cpu.mem.write(0x0FFF, 0x12);
+    apple1.cpu.mem.write(0x1000, 0x34);
+    apple1.cpu.mem.write(0xE000, 0x56);
+
+    assert_eq!(apple1.cpu.mem.read(0x0FFF), 0x12, "RAM at $0FFF is missing");
+    assert_eq!(
+      apple1.cpu.mem.read(0x1000),
+      0xFF,
+      "RAM past 4K is populated"
+    );
+    assert_eq!(
+      apple1.cpu.mem.read(0xE000),
+      0x56,
+      "RAM for BASIC is missing"
+    );
+  }
+
+  #[test]
+  fn apple1_rejects_bad_sizes() {
+    assert_eq!(
+      Apple1::new(64, &ECHO_ROM).err(),
+      Some(Error::RamSize {
+        machine: "Apple-1",
+        kib: 64
+      })
+    );
+    assert_eq!(
+      Apple1::new(8, &[0xEA; 0x200]).err(),
+      Some(Error::RomSize {
+        rom: "WozMon",
+        len: 0x200
+      })
+    );
+  }
+
+  // initializes the LCD polling the busy flag and prints "Hi"
+  #[rustfmt::skip]
+  const HELLO_ROM: [u8; 0x78] = [
+    0xA2, 0xFF,       // LDX #$FF
+    0x9A,             // TXS
+    0xA9, 0xFF,       // LDA #$FF
+    0x8D, 0x02, 0x60, // STA DDRB
+    0xA9, 0xE0,       // LDA #%11100000
+    0x8D, 0x03, 0x60, // STA DDRA
+    0xA9, 0x38,       // LDA #%00111000 ; 8-bit, 2 lines
+    0x20, 0x4C, 0x80, // JSR lcd_instruction
+    0xA9, 0x0E,       // LDA #%00001110 ; display and cursor on
+    0x20, 0x4C, 0x80, // JSR lcd_instruction
+    0xA9, 0x06,       // LDA #%00000110 ; increment
+    0x20, 0x4C, 0x80, // JSR lcd_instruction
+    0xA9, b'H',       // LDA #'H'
+    0x20, 0x62, 0x80, // JSR print_char
+    0xA9, b'i',       // LDA #'i'
+    0x20, 0x62, 0x80, // JSR print_char
+    0x4C, 0x26, 0x80, // loop: JMP loop
+    0x48,             // lcd_wait: PHA
+    0xA9, 0x00,       // LDA #0
+    0x8D, 0x02, 0x60, // STA DDRB
+    0xA9, 0x40,       // busy: LDA #RW
+    0x8D, 0x01, 0x60, // STA PORTA
+    0xA9, 0xC0,       // LDA #RW|E
+    0x8D, 0x01, 0x60, // STA PORTA
+    0xAD, 0x00, 0x60, // LDA PORTB
+    0x29, 0x80,       // AND #$80
+    0xD0, 0xEF,       // BNE busy
+    0xA9, 0x40,       // LDA #RW
+    0x8D, 0x01, 0x60, // STA PORTA
+    0xA9, 0xFF,       // LDA #$FF
+    0x8D, 0x02, 0x60, // STA DDRB
+    0x68,             // PLA
+    0x60,             // RTS
+    0x20, 0x29, 0x80, // lcd_instruction: JSR lcd_wait
+    0x8D, 0x00, 0x60, // STA PORTB
+    0xA9, 0x00,       // LDA #0
+    0x8D, 0x01, 0x60, // STA PORTA
+    0xA9, 0x80,       // LDA #E
+    0x8D, 0x01, 0x60, // STA PORTA
+    0xA9, 0x00,       // LDA #0
+    0x8D, 0x01, 0x60, // STA PORTA
+    0x60,             // RTS
+    0x20, 0x29, 0x80, // print_char: JSR lcd_wait
+    0x8D, 0x00, 0x60, // STA PORTB
+    0xA9, 0x20,       // LDA #RS
+    0x8D, 0x01, 0x60, // STA PORTA
+    0xA9, 0xA0,       // LDA #RS|E
+    0x8D, 0x01, 0x60, // STA PORTA
+    0xA9, 0x20,       // LDA #RS
+    0x8D, 0x01, 0x60, // STA PORTA
+    0x60,             // RTS
+  ];
+
+  fn breadboard_rom() -> Vec<u8> {
+    let mut rom = vec![0xEA; 0x8000];
+    rom[..HELLO_ROM.len()].copy_from_slice(&HELLO_ROM);
+    // reset vector
+    rom[0x7FFC] = 0x00;
+    rom[0x7FFD] = 0x80;
+    rom
+  }
+
+  #[test]
+  fn breadboard_prints_to_lcd() {
+    let mut breadboard = Breadboard::new(&breadboard_rom()).unwrap();
+    for _ in 0..20_000 {
+      breadboard.step().unwrap();
+    }
+
+    assert_eq!(
+      breadboard.lcd().lines(),
+      vec!["Hi              ", "                "],
+      "LCD doesn't show the greeting"
+    );
+    assert_eq!(
+      breadboard.lcd().cursor(),
+      Some((0, 2)),
+      "LCD cursor isn't after the greeting"
+    );
+  }
+
+  #[test]
+  fn breadboard_address_decoding() {
+    let mut breadboard = Breadboard::new(&breadboard_rom()).unwrap();
+    let mem = &mut breadboard.cpu.mem;
+    mem.write(0x3FFF, 0x12);
+    mem.write(0x4000, 0x34);
+    mem.write(0x8000, 0x56);
+    // DDRB through a mirror of the VIA
+    mem.write(0x7FF2, 0x78);
+
+    assert_eq!(mem.read(0x3FFF), 0x12, "RAM at $3FFF is missing");
+    assert_eq!(mem.read(0x4000), 0xFF, "there shouldn't be RAM at $4000");
+    assert_eq!(mem.read(0x8000), 0xA2, "ROM at $8000 took a write");
+    assert_eq!(mem.read(0x6002), 0x78, "VIA isn't mirrored up to $7FFF");
+  }
+
+  // lights a "1" on the last digit and reads keypad row 2 into $00
+  #[rustfmt::skip]
+  const KIM_SCAN: [u8; 0x2E] = [
+    0xA9, 0x1E,       // LDA #$1E
+    0x8D, 0x43, 0x17, // STA PBDD
+    0xA9, 0x7F,       // loop: LDA #$7F
+    0x8D, 0x41, 0x17, // STA PADD
+    0xA9, 0x12,       // LDA #$12 ; digit 5
+    0x8D, 0x42, 0x17, // STA SBD
+    0xA9, 0x06,       // LDA #$06 ; segments b and c
+    0x8D, 0x40, 0x17, // STA SAD
+    0xA0, 0x40,       // LDY #$40
+    0x88,             // delay: DEY
+    0xD0, 0xFD,       // BNE delay
+    0xA9, 0x00,       // LDA #$00
+    0x8D, 0x40, 0x17, // STA SAD
+    0x8D, 0x41, 0x17, // STA PADD
+    0xA9, 0x04,       // LDA #$04 ; row 2
+    0x8D, 0x42, 0x17, // STA SBD
+    0xAD, 0x40, 0x17, // LDA SAD
+    0x85, 0x00,       // STA $00
+    0x4C, 0x05, 0x1C, // JMP loop
+  ];
+
+  fn kim1_rom() -> Vec<u8> {
+    let mut rom = vec![0xEA; 0x400];
+    rom[..KIM_SCAN.len()].copy_from_slice(&KIM_SCAN);
+    // reset vector
+    rom[0x3FC] = 0x00;
+    rom[0x3FD] = 0x1C;
+    rom
+  }
+
+  #[test]
+  fn kim1_display_and_keypad() {
+    let mut kim1 = Kim1::new(&kim1_rom(), false).unwrap();
+    assert_eq!(
+      kim1.cpu.pc, 0x1C00,
+      "reset vector wasn't read through the mirror"
+    );
+
+    for _ in 0..20_000 {
+      kim1.step().unwrap();
+    }
+    assert_eq!(
+      kim1.display(),
+      [0, 0, 0, 0, 0, 0x06],
+      "display isn't showing the digit"
+    );
+    assert_eq!(
+      kim1.cpu.mem.peek(0x00),
+      0xFF,
+      "a key is down before any was pressed"
+    );
+
+    kim1.key_press(KEY_GO);
+    for _ in 0..10_000 {
+      kim1.step().unwrap();
+    }
+    assert_eq!(kim1.cpu.mem.peek(0x00), 0xFD, "GO isn't on PA1 of row 2");
+
+    for _ in 0..100_000 {
+      kim1.step().unwrap();
+    }
+    assert_eq!(kim1.cpu.mem.peek(0x00), 0xFF, "GO was never released");
+
+    assert_eq!(
+      Kim1::new(&[0xEA; 0x300], false).err(),
+      Some(Error::RomSize {
+        rom: "KIM-1",
+        len: 0x300
+      }),
+      "a short ROM was taken"
+    );
+  }
+
+  #[test]
+  fn kim1_tty_echo() {
+    let mut kim1 = Kim1::new(&kim1_rom(), true).unwrap();
+    kim1.tty_send(b'K');
+    for _ in 0..30_000 {
+      kim1.step().unwrap();
+    }
+
+    // the rubout that sets the baud rate comes first
+    assert_eq!(
+      kim1.take_output(),
+      [0x7F, b'K'],
+      "TTY didn't echo the input"
+    );
+  }
+
+  // echoes keys from GETIN to CHROUT, the jump table entries are plain RTS
+  #[rustfmt::skip]
+  const C64_ECHO: [u8; 0x16] = [
+    0xA2, 0xFF,       // LDX #$FF
+    0x9A,             // TXS
+    0xA9, 0x03,       // LDA #3
+    0x85, 0x9A,       // STA DFLTO
+    0xA9, 0x00,       // LDA #0
+    0x85, 0x99,       // STA DFLTN
+    0x20, 0xE4, 0xFF, // loop: JSR GETIN
+    0xF0, 0xFB,       // BEQ loop
+    0x20, 0xD2, 0xFF, // JSR CHROUT
+    0x4C, 0x0B, 0xE0, // JMP loop
+  ];
+
+  fn c64() -> C64 {
+    let mut kernal = vec![0x60; 0x2000];
+    kernal[..C64_ECHO.len()].copy_from_slice(&C64_ECHO);
+    // reset vector
+    kernal[0x1FFC] = 0x00;
+    kernal[0x1FFD] = 0xE0;
+
+    C64::new(&[0xBA; 0x2000], &kernal, &[0xC4; 0x1000]).unwrap()
+  }
+
+  #[test]
+  fn c64_kernal_traps() {
+    let mut c64 = c64();
+    c64.key_press(b'h');
+    c64.key_press(b'i');
+    for _ in 0..1000 {
+      c64.step().unwrap();
+    }
+
+    assert_eq!(c64.take_output(), b"HI", "GETIN and CHROUT weren't trapped");
+  }
+
+  #[test]
+  fn c64_loads_prgs() {
+    let mut c64 = c64();
+    assert_eq!(c64.load_prg(&[0x01]), Err(Error::MissingLoadAddress));
+
+    // a BASIC program gets its end pointers at VARTAB
+    assert_eq!(c64.load_prg(&[0x01, 0x08, 0x00, 0x00, 0x00]), Ok(0x0801));
+    assert_eq!(c64.cpu.mem.peek(0x002D), 0x04);
+    assert_eq!(c64.cpu.mem.peek(0x002E), 0x08);
+
+    // one that runs to $FFFF has no address past it for them
+    let mut prg = vec![0x01, 0x08];
+    prg.resize(2 + 0x10000 - 0x0801, 0x00);
+    assert_eq!(
+      c64.load_prg(&prg),
+      Err(Error::OutOfBounds {
+        addr: 0x0801,
+        len: 0x10000 - 0x0801
+      })
+    );
+    prg.pop();
+    assert_eq!(
+      c64.load_prg(&prg),
+      Ok(0x0801),
+      "BASIC up to $FFFE didn't load"
+    );
+    assert_eq!(c64.cpu.mem.peek(0x002E), 0xFF);
+
+    // anything else can fill memory to the end
+    let mut prg = vec![0x00, 0xC0];
+    prg.resize(2 + 0x4000, 0xEA);
+    assert_eq!(
+      c64.load_prg(&prg),
+      Ok(0xC000),
+      "PRG ending at $FFFF didn't load"
+    );
+    prg.push(0xEA);
+    assert!(c64.load_prg(&prg).is_err(), "PRG past $FFFF loaded");
+  }
+
+  #[test]
+  fn c64_pla_banking() {
+    let mut c64 = c64();
+    let basic = c64.cpu.mem.read(0xA000);
+    assert_eq!(basic, 0xBA, "BASIC isn't banked in after reset");
+    assert_eq!(
+      c64.cpu.mem.read(0xD020),
+      0x00,
+      "I/O isn't banked in after reset"
+    );
+
+    // writes under ROM land in RAM
+    c64.cpu.mem.write(0xA000, 0x12);
+    assert_eq!(c64.cpu.mem.peek_ram(0xA000), 0x12, "write didn't reach RAM");
+
+    // CHAREN low swaps the character ROM in for I/O
+    c64.cpu.mem.write(0x0000, 0x2F);
+    c64.cpu.mem.write(0x0001, 0x33);
+    c64.step().unwrap();
+    assert_eq!(
+      c64.cpu.mem.read(0xD020),
+      0xC4,
+      "character ROM isn't banked in"
+    );
+
+    // all RAM
+    c64.cpu.mem.write(0x0001, 0x30);
+    c64.step().unwrap();
+    assert_eq!(c64.cpu.mem.read(0xA000), 0x12, "BASIC is still banked in");
+    assert_eq!(
+      c64.cpu.mem.read(0xD020),
+      0x00,
+      "character ROM is still banked in"
+    );
+    assert_eq!(c64.cpu.mem.read(0x0001), 0x30, "port doesn't read back");
+  }
+
+  // LDA #data ; STA addr
+  fn store(program: &mut Vec<u8>, addr: u16, data: u8) {
+    program.extend([0xA9, data, 0x8D, addr as u8, (addr >> 8) as u8]);
+  }
+
+  #[test]
+  fn test_machine_reports_asserts_and_exit() {
+    let mut program = Vec::new();
+    store(&mut program, HOST_OUT, b'O');
+    store(&mut program, HOST_OUT, b'K');
+    for ch in b"sum" {
+      store(&mut program, HOST_LABEL, *ch);
+    }
+    // CLC ; LDA #1 ; ADC #1 ; EOR #2 ; then A is 0 when the sum is right
+    program.extend([0x18, 0xA9, 0x01, 0x69, 0x01, 0x49, 0x02]);
+    // BNE +2 ; LDA #1 ; STA HOST_ASSERT
+    program.extend([0xD0, 0x02, 0xA9, 0x01, 0x8D, 0xF2, 0xFF]);
+    store(&mut program, HOST_ASSERT, 0);
+    store(&mut program, HOST_EXIT, 3);
+
+    let mut machine = TestMachine::new();
+    machine.load(&program, 0x0200).unwrap();
+    let report = machine.run(10_000);
+
+    assert_eq!(report.exit, Some(3), "program didn't exit with its status");
+    assert_eq!(report.output, b"OK", "output didn't reach the host");
+    assert_eq!(
+      report.assertions,
+      [
+        Assertion {
+          name: "sum".to_string(),
+          passed: true
+        },
+        Assertion {
+          name: "assertion 2".to_string(),
+          passed: false
+        }
+      ],
+      "assertions weren't recorded"
+    );
+    assert!(!report.passed(), "a failed assertion passed the test");
+  }
+
+  #[test]
+  fn test_machine_reads_files() {
+    let path = std::env::temp_dir().join("g6502_test_machine_file");
+    std::fs::write(&path, b"file!").unwrap();
+
+    let mut program = Vec::new();
+    for ch in path.to_str().unwrap().bytes() {
+      store(&mut program, HOST_FILE, ch);
+    }
+    store(&mut program, HOST_FILE, 0);
+    let loop_start = 0x0200 + program.len() as u16;
+    // loop: LDA STATUS ; LSR A ; BCS done ; LDA READ ; STA OUT ; JMP loop
+    program.extend([0xAD, HOST_STATUS as u8, 0xFF, 0x4A, 0xB0, 0x09]);
+    program.extend([0xAD, HOST_READ as u8, 0xFF, 0x8D, HOST_OUT as u8, 0xFF]);
+    program.extend([0x4C, loop_start as u8, (loop_start >> 8) as u8]);
+    store(&mut program, HOST_EXIT, 0);
+
+    let mut machine = TestMachine::new();
+    machine.load(&program, 0x0200).unwrap();
+    let report = machine.run(100_000);
+    std::fs::remove_file(&path).ok();
+
+    assert_eq!(
+      report.output, b"file!",
+      "file wasn't read through the host port"
+    );
+    assert!(report.passed(), "program didn't exit cleanly");
+  }
+
+  #[test]
+  fn test_machine_maps_only_its_registers() {
+    let mut machine = TestMachine::new();
+    let mem = &mut machine.cpu.mem;
+    let after = HOST_BASE + HOST_REGISTERS as u16;
+    mem.write(after, 0x5A);
+    mem.write(after + 1, 0xA5);
+    assert_eq!(mem.read(after), 0x5A, "byte past HOST_IN isn't RAM");
+    assert_eq!(mem.read(after + 1), 0xA5, "byte past HOST_IN isn't RAM");
+  }
+}
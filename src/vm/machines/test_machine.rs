@@ -0,0 +1,265 @@
+use std::{cell::RefCell, collections::VecDeque, fs, rc::Rc};
+
+use crate::vm::{
+  defs::{Byte, Word},
+  devices::Device,
+  Error, Mem, CPU,
+};
+
+// host trap registers, right below the vectors
+pub const HOST_BASE: Word = 0xFFF0;
+// write a character to the output
+pub const HOST_OUT: Word = HOST_BASE;
+// write the exit status, the machine stops
+pub const HOST_EXIT: Word = HOST_BASE + 1;
+// write 0 to fail an assertion and anything else to pass it
+pub const HOST_ASSERT: Word = HOST_BASE + 2;
+// write the name of the next assertion a character at a time
+pub const HOST_LABEL: Word = HOST_BASE + 3;
+// write a file name a character at a time, 0 opens it
+pub const HOST_FILE: Word = HOST_BASE + 4;
+// read the open file a byte at a time
+pub const HOST_READ: Word = HOST_BASE + 5;
+// read the STATUS_* bits
+pub const HOST_STATUS: Word = HOST_BASE + 6;
+// read the next input byte, 0 when there is none
+pub const HOST_IN: Word = HOST_BASE + 7;
+// HOST_OUT through HOST_IN
+pub const HOST_REGISTERS: usize = 8;
+
+pub const STATUS_EOF: Byte = 0x01;
+pub const STATUS_FILE_ERROR: Byte = 0x02;
+pub const STATUS_INPUT: Byte = 0x04;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Assertion {
+  pub name: String,
+  pub passed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestReport {
+  // `None` when the program ran out of cycles before exiting
+  pub exit: Option<Byte>,
+  pub output: Vec<Byte>,
+  pub assertions: Vec<Assertion>,
+  pub cycles: u64,
+  // what stopped the cpu, if anything did
+  pub error: Option<Error>,
+  // a JAM or the Halt policy stopped it
+  pub halted: bool,
+}
+
+impl TestReport {
+  // exited with 0 and nothing failed
+  pub fn passed(&self) -> bool {
+    self.exit == Some(0) && self.error.is_none() && self.assertions.iter().all(|a| a.passed)
+  }
+
+  pub fn failures(&self) -> impl Iterator<Item = &Assertion> {
+    self.assertions.iter().filter(|a| !a.passed)
+  }
+}
+
+// 64K of RAM with the host trap registers at $FFF0, for running assembled
+// test programs from `cargo test` or the testrun binary
+pub struct TestMachine {
+  pub cpu: CPU,
+  host: Rc<RefCell<HostPort>>,
+  cycles: u64,
+}
+
+impl Default for TestMachine {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl TestMachine {
+  pub fn new() -> Self {
+    let host = Rc::new(RefCell::new(HostPort::new()));
+    let mut mem = Mem::new();
+    mem.map_device(HOST_BASE, HOST_REGISTERS, host.clone());
+
+    Self {
+      cpu: CPU::new(mem),
+      host,
+      cycles: 0,
+    }
+  }
+
+  // loads `program` at `addr` and points the reset vector at it
+  pub fn load(&mut self, program: &[u8], addr: Word) -> Result<(), Error> {
+    self.cpu.mem.load(program, addr)?;
+    self
+      .cpu
+      .mem
+      .load(&[addr as Byte, (addr >> 8) as Byte], 0xFFFC)?;
+    self.cpu.reset();
+    Ok(())
+  }
+
+  // bytes for HOST_IN
+  pub fn input(&mut self, data: &[u8]) {
+    self.host.borrow_mut().input.extend(data);
+  }
+
+  pub fn exited(&self) -> bool {
+    self.host.borrow().exit.is_some()
+  }
+
+  pub fn cycles(&self) -> u64 {
+    self.cycles
+  }
+
+  pub fn step(&mut self) -> Result<(), Error> {
+    if self.exited() {
+      return Ok(());
+    }
+    self.cpu.clock()?;
+    self.cycles += 1;
+    Ok(())
+  }
+
+  // runs until the program exits, the cpu stops with an error or halts, or `max_cycles`
+  // have gone by
+  pub fn run(&mut self, max_cycles: u64) -> TestReport {
+    let end = self.cycles + max_cycles;
+    while !self.exited() && !self.cpu.halted() && self.cycles < end {
+      if let Err(err) = self.step() {
+        return self.report_error(Some(err));
+      }
+    }
+
+    self.report()
+  }
+
+  pub fn report(&self) -> TestReport {
+    self.report_error(None)
+  }
+
+  fn report_error(&self, error: Option<Error>) -> TestReport {
+    let host = self.host.borrow();
+    TestReport {
+      exit: host.exit,
+      output: host.output.clone(),
+      assertions: host.assertions.clone(),
+      cycles: self.cycles,
+      error,
+      halted: self.cpu.halted(),
+    }
+  }
+}
+
+struct HostPort {
+  output: Vec<Byte>,
+  input: VecDeque<Byte>,
+  exit: Option<Byte>,
+
+  assertions: Vec<Assertion>,
+  label: String,
+
+  file_name: Vec<Byte>,
+  file: Option<(Vec<Byte>, usize)>,
+  file_error: bool,
+}
+
+impl HostPort {
+  fn new() -> Self {
+    Self {
+      output: Vec::new(),
+      input: VecDeque::new(),
+      exit: None,
+
+      assertions: Vec::new(),
+      label: String::new(),
+
+      file_name: Vec::new(),
+      file: None,
+      file_error: false,
+    }
+  }
+
+  fn open(&mut self) {
+    let name = String::from_utf8_lossy(&self.file_name).into_owned();
+    self.file_name.clear();
+    match fs::read(&name) {
+      Ok(data) => {
+        self.file = Some((data, 0));
+        self.file_error = false;
+      }
+      Err(_) => {
+        self.file = None;
+        self.file_error = true;
+      }
+    }
+  }
+}
+
+impl Device for HostPort {
+  fn read(&mut self, addr: Word) -> Byte {
+    let data = self.peek(addr);
+    match addr + HOST_BASE {
+      HOST_READ => {
+        if let Some((file, pos)) = &mut self.file {
+          *pos = (*pos + 1).min(file.len());
+        }
+      }
+      HOST_IN => {
+        self.input.pop_front();
+      }
+      _ => {}
+    }
+
+    data
+  }
+
+  fn write(&mut self, addr: Word, data: Byte) {
+    match addr + HOST_BASE {
+      HOST_OUT => self.output.push(data),
+      HOST_EXIT => self.exit = Some(data),
+      HOST_ASSERT => {
+        let name = match std::mem::take(&mut self.label) {
+          label if label.is_empty() => format!("assertion {}", self.assertions.len() + 1),
+          label => label,
+        };
+        self.assertions.push(Assertion {
+          name,
+          passed: data != 0,
+        });
+      }
+      HOST_LABEL => self.label.push(data as char),
+      HOST_FILE if data == 0 => self.open(),
+      HOST_FILE => self.file_name.push(data),
+      _ => {}
+    }
+  }
+
+  fn peek(&self, addr: Word) -> Byte {
+    match addr + HOST_BASE {
+      HOST_READ => match &self.file {
+        Some((file, pos)) => file.get(*pos).copied().unwrap_or(0),
+        None => 0,
+      },
+      HOST_STATUS => {
+        let eof = match &self.file {
+          Some((file, pos)) => *pos >= file.len(),
+          None => true,
+        };
+        let mut status = 0;
+        if eof {
+          status |= STATUS_EOF;
+        }
+        if self.file_error {
+          status |= STATUS_FILE_ERROR;
+        }
+        if !self.input.is_empty() {
+          status |= STATUS_INPUT;
+        }
+        status
+      }
+      HOST_IN => self.input.front().copied().unwrap_or(0),
+      _ => 0,
+    }
+  }
+}
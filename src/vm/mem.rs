@@ -1,33 +1,639 @@
-use super::defs::{Byte, Word, MEM_SIZE};
+#[cfg(feature = "alloc")]
+use alloc::{boxed::Box, collections::VecDeque, rc::Rc, vec, vec::Vec};
+use core::ops::{Deref, DerefMut};
 
+#[cfg(feature = "alloc")]
+use super::{
+  bank::{Bank, BankRegister, BankWindow},
+  coverage::Coverage,
+  devices::{DeviceRef, Mapping},
+  dma::Dma,
+  observer::{BusEvent, ObserverRef},
+};
+use super::{
+  coverage::Access,
+  defs::{Byte, Word, MEM_SIZE},
+  error::Error,
+};
+
+// the flat memory, a buffer smaller than 64K is mirrored through the address space
+enum Ram {
+  #[cfg(feature = "alloc")]
+  Owned(Box<[Byte]>),
+  Borrowed(&'static mut [Byte]),
+}
+
+impl Deref for Ram {
+  type Target = [Byte];
+
+  fn deref(&self) -> &[Byte] {
+    match self {
+      #[cfg(feature = "alloc")]
+      Ram::Owned(data) => data,
+      Ram::Borrowed(data) => data,
+    }
+  }
+}
+
+impl DerefMut for Ram {
+  fn deref_mut(&mut self) -> &mut [Byte] {
+    match self {
+      #[cfg(feature = "alloc")]
+      Ram::Owned(data) => data,
+      Ram::Borrowed(data) => data,
+    }
+  }
+}
+
+// without alloc the bus is only RAM, devices and banks need it
 pub struct Mem {
-  data: Vec<Byte>,
+  data: Ram,
+  #[cfg(feature = "alloc")]
+  windows: Vec<BankWindow>,
+  #[cfg(feature = "alloc")]
+  bank_registers: Vec<BankRegister>,
+  #[cfg(feature = "alloc")]
+  devices: Vec<Mapping>,
+  #[cfg(feature = "alloc")]
+  clocked: Vec<DeviceRef>,
+  // devices clocked by something else, like a scheduler
+  #[cfg(feature = "alloc")]
+  unclocked: Vec<DeviceRef>,
+  // devices whose irq output isn't connected to the cpu
+  #[cfg(feature = "alloc")]
+  unwired: Vec<DeviceRef>,
+  // devices that are banked out of the address space but still run
+  #[cfg(feature = "alloc")]
+  disabled: Vec<DeviceRef>,
+  // reads and writes per address, only counted while a debugger asks for them
+  #[cfg(feature = "alloc")]
+  accesses: Option<Vec<u32>>,
+  #[cfg(feature = "alloc")]
+  coverage: Option<Box<Coverage>>,
+
+  // transfers waiting for the bus, the front one is running, with the byte it read
+  #[cfg(feature = "alloc")]
+  dma: VecDeque<Dma>,
+  #[cfg(feature = "alloc")]
+  dma_data: Option<Byte>,
+  #[cfg(feature = "alloc")]
+  dma_cycles: u64,
+  // ticks since power on, for DMA that starts on even cycles
+  #[cfg(feature = "alloc")]
+  cycle: u64,
+
+  #[cfg(feature = "alloc")]
+  observers: Vec<ObserverRef>,
+  // accesses ahead of the clock, the cpu makes all of an instruction's on its first
+  // cycle and each one is a cycle on the bus
+  #[cfg(feature = "alloc")]
+  bus_cycles: u64,
+}
+
+#[cfg(feature = "alloc")]
+impl Default for Mem {
+  fn default() -> Self {
+    Self::new()
+  }
 }
 
 impl Mem {
+  // 64K of RAM
+  #[cfg(feature = "alloc")]
   pub fn new() -> Self {
+    Self::from_ram(Ram::Owned(vec![0; MEM_SIZE].into_boxed_slice()))
+  }
+
+  // runs on the caller's buffer, for targets without an allocator
+  pub fn with_ram(ram: &'static mut [Byte]) -> Result<Self, Error> {
+    if ram.is_empty() {
+      return Err(Error::RamSize {
+        machine: "the cpu",
+        kib: 0,
+      });
+    }
+    Ok(Self::from_ram(Ram::Borrowed(ram)))
+  }
+
+  fn from_ram(data: Ram) -> Self {
     Self {
-      data: vec![0; MEM_SIZE],
+      data,
+      #[cfg(feature = "alloc")]
+      windows: Vec::new(),
+      #[cfg(feature = "alloc")]
+      bank_registers: Vec::new(),
+      #[cfg(feature = "alloc")]
+      devices: Vec::new(),
+      #[cfg(feature = "alloc")]
+      clocked: Vec::new(),
+      #[cfg(feature = "alloc")]
+      unclocked: Vec::new(),
+      #[cfg(feature = "alloc")]
+      unwired: Vec::new(),
+      #[cfg(feature = "alloc")]
+      disabled: Vec::new(),
+      #[cfg(feature = "alloc")]
+      accesses: None,
+      #[cfg(feature = "alloc")]
+      coverage: None,
+
+      #[cfg(feature = "alloc")]
+      dma: VecDeque::new(),
+      #[cfg(feature = "alloc")]
+      dma_data: None,
+      #[cfg(feature = "alloc")]
+      dma_cycles: 0,
+      #[cfg(feature = "alloc")]
+      cycle: 0,
+
+      #[cfg(feature = "alloc")]
+      observers: Vec::new(),
+      #[cfg(feature = "alloc")]
+      bus_cycles: 0,
     }
   }
 
   pub fn reset(&mut self) {
-    for byte in &mut self.data {
+    for byte in self.data.iter_mut() {
       *byte = 0;
     }
+
+    #[cfg(feature = "alloc")]
+    for window in &mut self.windows {
+      window.reset();
+    }
+  }
+
+  // where `addr` lands in RAM
+  fn index(&self, addr: Word) -> usize {
+    addr as usize % self.data.len()
+  }
+
+  pub fn read(&mut self, addr: Word) -> Byte {
+    self.cover(addr, Access::READ);
+    let data = self.bus_read(addr);
+    self.observe(addr, data, Access::READ);
+    data
+  }
+
+  // a read of the program, `access` says whether it's an opcode or an operand
+  pub fn fetch(&mut self, addr: Word, access: Access) -> Byte {
+    self.cover(addr, access);
+    let data = self.bus_read(addr);
+    self.observe(addr, data, access);
+    data
   }
 
-  pub fn read(&self, addr: Word) -> Byte {
-    self.data[addr as usize]
+  fn bus_read(&mut self, addr: Word) -> Byte {
+    self.count_access(addr);
+    #[cfg(feature = "alloc")]
+    if let Some(mapping) = self.mapping(addr) {
+      return mapping.device.borrow_mut().read(addr - mapping.start);
+    }
+
+    self.peek(addr)
   }
 
   pub fn write(&mut self, addr: Word, data: Byte) {
-    self.data[addr as usize] = data;
+    self.cover(addr, Access::WRITTEN);
+    self.count_access(addr);
+    self.observe(addr, data, Access::WRITTEN);
+    #[cfg(feature = "alloc")]
+    if self.bus_write(addr, data) {
+      return;
+    }
+
+    let index = self.index(addr);
+    self.data[index] = data;
   }
 
-  pub fn load(&mut self, data: &[u8], offset: Word) {
-    for (i, byte) in data.iter().enumerate() {
-      self.data[i + offset as usize] = *byte;
+  // a write to a device, a bank register or a bank, false when it's RAM's
+  #[cfg(feature = "alloc")]
+  fn bus_write(&mut self, addr: Word, data: Byte) -> bool {
+    if let Some(mapping) = self.mapping(addr) {
+      let mut device = mapping.device.borrow_mut();
+      device.write(addr - mapping.start, data);
+      // a register write is what usually starts a transfer
+      if let Some(dma) = device.dma() {
+        drop(device);
+        self.start_dma(dma);
+      }
+      return true;
+    }
+
+    for reg in &self.bank_registers {
+      if reg.addr == addr {
+        let window = &mut self.windows[reg.window];
+        let count = window.banks().len();
+        // wrapped around the banks there are, so it can't miss
+        if count > 0 {
+          let _ = window.select(Some((data & reg.mask) as usize % count));
+        }
+        return true;
+      }
+    }
+
+    match self.windows.iter_mut().find(|w| w.contains(addr)) {
+      Some(window) => window.write(addr, data),
+      None => false,
+    }
+  }
+
+  // copies `data` to `offset` in RAM, underneath any devices and banks
+  pub fn load(&mut self, data: &[u8], offset: Word) -> Result<(), Error> {
+    let start = offset as usize;
+    if start + data.len() > MEM_SIZE {
+      return Err(Error::OutOfBounds {
+        addr: start,
+        len: data.len(),
+      });
+    }
+
+    for (i, &byte) in data.iter().enumerate() {
+      let index = self.index(offset.wrapping_add(i as Word));
+      self.data[index] = byte;
+    }
+    Ok(())
+  }
+
+  /* ------- debugger access -------- */
+  // what the cpu would see at `addr` right now
+  pub fn peek(&self, addr: Word) -> Byte {
+    #[cfg(feature = "alloc")]
+    if let Some(data) = self.bus_peek(addr) {
+      return data;
+    }
+
+    self.peek_ram(addr)
+  }
+
+  // the flat memory underneath the windows
+  pub fn peek_ram(&self, addr: Word) -> Byte {
+    self.data[self.index(addr)]
+  }
+
+  #[cfg_attr(not(feature = "alloc"), allow(unused_variables))]
+  fn count_access(&mut self, addr: Word) {
+    #[cfg(feature = "alloc")]
+    if let Some(accesses) = &mut self.accesses {
+      accesses[addr as usize] = accesses[addr as usize].saturating_add(1);
+    }
+  }
+
+  #[cfg_attr(not(feature = "alloc"), allow(unused_variables))]
+  fn cover(&mut self, addr: Word, access: Access) {
+    #[cfg(feature = "alloc")]
+    if let Some(coverage) = &mut self.coverage {
+      coverage.mark(addr, access);
+    }
+  }
+  /* ------- debugger access -------- */
+
+  /* ------- observers -------- */
+  // a cycle the cpu spends on the bus without using the data, only observers see it.
+  // devices don't, so reading a register twice doesn't clear its flags early
+  #[cfg_attr(not(feature = "alloc"), allow(unused_variables))]
+  pub fn dummy_read(&mut self, addr: Word) {
+    #[cfg(feature = "alloc")]
+    if !self.observers.is_empty() {
+      let data = self.peek(addr);
+      self.observe(addr, data, Access::READ | Access::DUMMY);
+    }
+  }
+
+  // the unmodified value a read-modify-write puts back before the result
+  pub fn dummy_write(&mut self, addr: Word, data: Byte) {
+    self.observe(addr, data, Access::WRITTEN | Access::DUMMY);
+  }
+
+  #[cfg_attr(not(feature = "alloc"), allow(unused_variables))]
+  fn observe(&mut self, addr: Word, data: Byte, access: Access) {
+    #[cfg(feature = "alloc")]
+    if !self.observers.is_empty() {
+      let event = BusEvent {
+        addr,
+        data,
+        cycle: self.cycle + self.bus_cycles,
+        access,
+      };
+      self.bus_cycles += 1;
+      for observer in &self.observers {
+        observer.borrow_mut().access(&event);
+      }
+    }
+  }
+  /* ------- observers -------- */
+
+  // nothing can pull the irq line without devices
+  #[cfg(not(feature = "alloc"))]
+  pub fn irq(&self) -> bool {
+    false
+  }
+
+  #[cfg(not(feature = "alloc"))]
+  pub fn tick(&mut self) {}
+
+  #[cfg(not(feature = "alloc"))]
+  pub fn rdy(&self) -> bool {
+    true
+  }
+
+  #[cfg(not(feature = "alloc"))]
+  pub fn so(&self) -> bool {
+    false
+  }
+
+  #[cfg(not(feature = "alloc"))]
+  pub fn sync(&mut self, _: Word) {}
+
+  #[cfg(not(feature = "alloc"))]
+  pub fn dma_active(&self) -> bool {
+    false
+  }
+
+  #[cfg(not(feature = "alloc"))]
+  pub fn dma_cycle(&mut self) {}
+}
+
+#[cfg(feature = "alloc")]
+impl Mem {
+  /* ------- banking -------- */
+  // maps `banks` over `len` bytes starting at `start`, returns the window id.
+  // windows are searched in the order they were added
+  pub fn add_window(
+    &mut self,
+    start: Word,
+    len: usize,
+    banks: Vec<Bank>,
+    selected: Option<usize>,
+  ) -> Result<usize, Error> {
+    let window = BankWindow::new(start, len, banks, selected);
+    if let Some(bank) = selected {
+      window.bank(bank)?;
     }
+    self.windows.push(window);
+    Ok(self.windows.len() - 1)
+  }
+
+  // writes to `addr` will switch the bank of `window` instead of reaching memory
+  pub fn add_bank_register(&mut self, addr: Word, window: usize, mask: Byte) -> Result<(), Error> {
+    self.window(window)?;
+    self
+      .bank_registers
+      .push(BankRegister { addr, window, mask });
+    Ok(())
+  }
+
+  pub fn select_bank(&mut self, window: usize, bank: Option<usize>) -> Result<(), Error> {
+    self.window_mut(window)?.select(bank)
+  }
+
+  pub fn selected_bank(&self, window: usize) -> Option<usize> {
+    self.windows.get(window).and_then(BankWindow::selected)
+  }
+
+  pub fn windows(&self) -> &[BankWindow] {
+    &self.windows
+  }
+
+  pub fn window(&self, window: usize) -> Result<&BankWindow, Error> {
+    self.windows.get(window).ok_or(Error::NoWindow { window })
+  }
+
+  pub fn window_mut(&mut self, window: usize) -> Result<&mut BankWindow, Error> {
+    self
+      .windows
+      .get_mut(window)
+      .ok_or(Error::NoWindow { window })
+  }
+  /* ------- banking -------- */
+
+  /* ------- devices -------- */
+  // devices take priority over banks and flat memory,
+  // the same device can be mapped more than once to mirror it
+  pub fn map_device(&mut self, start: Word, len: usize, device: DeviceRef) {
+    // mirrors shouldn't run a device twice as fast
+    if !self.clocked.iter().any(|d| Rc::ptr_eq(d, &device)) {
+      self.clocked.push(device.clone());
+    }
+    self.devices.push(Mapping { start, len, device });
+  }
+
+  fn mapping(&self, addr: Word) -> Option<&Mapping> {
+    self
+      .devices
+      .iter()
+      .find(|m| m.contains(addr) && !self.disabled.iter().any(|d| Rc::ptr_eq(d, &m.device)))
+  }
+
+  // a disabled device lets accesses through to the banks and memory underneath,
+  // like the C64's I/O area when the PLA maps RAM or the character ROM there
+  pub fn set_device_enabled(&mut self, device: &DeviceRef, enabled: bool) {
+    self.disabled.retain(|d| !Rc::ptr_eq(d, device));
+    if !enabled {
+      self.disabled.push(device.clone());
+    }
+  }
+
+  pub fn tick(&mut self) {
+    self.cycle += 1;
+    self.bus_cycles = self.bus_cycles.saturating_sub(1);
+    for device in &self.clocked {
+      let clocked = !self.unclocked.iter().any(|u| Rc::ptr_eq(u, device));
+      let mut device = device.borrow_mut();
+      if clocked {
+        device.tick();
+      }
+      if let Some(dma) = device.dma() {
+        queue_dma(&mut self.dma, dma);
+      }
+    }
+  }
+
+  // an unclocked device stays mapped but isn't ticked with the cpu
+  pub fn set_device_clocked(&mut self, device: &DeviceRef, clocked: bool) {
+    self.unclocked.retain(|d| !Rc::ptr_eq(d, device));
+    if !clocked {
+      self.unclocked.push(device.clone());
+    }
+  }
+
+  // boards like the apple-1 leave some irq pins floating
+  pub fn set_irq_wired(&mut self, device: &DeviceRef, wired: bool) {
+    self.unwired.retain(|d| !Rc::ptr_eq(d, device));
+    if !wired {
+      self.unwired.push(device.clone());
+    }
+  }
+
+  // the irq line is wired-or between all devices
+  pub fn irq(&self) -> bool {
+    self
+      .clocked
+      .iter()
+      .filter(|d| !self.unwired.iter().any(|u| Rc::ptr_eq(u, d)))
+      .any(|d| d.borrow().irq())
+  }
+
+  // RDY is open collector, any device can pull it low
+  pub fn rdy(&self) -> bool {
+    self.clocked.iter().all(|d| d.borrow().rdy())
+  }
+
+  pub fn so(&self) -> bool {
+    self.clocked.iter().any(|d| d.borrow().so())
+  }
+
+  // tells every device the cpu is fetching the opcode at `addr`
+  pub fn sync(&mut self, addr: Word) {
+    for device in &self.clocked {
+      device.borrow_mut().sync(addr);
+    }
+  }
+  /* ------- devices -------- */
+
+  /* ------- dma -------- */
+  // queues a transfer, it gets the bus once the ones before it are done and the
+  // cpu isn't writing
+  pub fn start_dma(&mut self, dma: Dma) {
+    queue_dma(&mut self.dma, dma);
+  }
+
+  pub fn dma_active(&self) -> bool {
+    !self.dma.is_empty()
+  }
+
+  // the cycles transfers have taken from the cpu
+  pub fn dma_cycles(&self) -> u64 {
+    self.dma_cycles
+  }
+
+  // runs a cycle of the transfer at the front of the queue instead of the cpu's
+  pub fn dma_cycle(&mut self) {
+    let dma = match self.dma.front_mut() {
+      Some(dma) => dma,
+      None => return,
+    };
+    self.dma_cycles += 1;
+
+    if dma.idle > 0 {
+      dma.idle -= 1;
+    } else if let Some(data) = self.dma_data.take() {
+      let dst = dma.dst;
+      dma.src = dma.src.wrapping_add(1);
+      if !dma.port {
+        dma.dst = dma.dst.wrapping_add(1);
+      }
+      dma.len -= 1;
+      self.write(dst, data);
+    } else if !dma.align || self.cycle % 2 == 0 {
+      let src = dma.src;
+      self.dma_data = Some(self.read(src));
+    }
+
+    if self.dma_data.is_none() && self.dma.front().map_or(false, Dma::is_done) {
+      self.dma.pop_front();
+    }
+  }
+  /* ------- dma -------- */
+
+  /* ------- debugger access -------- */
+  // a device's or a bank's byte, none when it's RAM's
+  fn bus_peek(&self, addr: Word) -> Option<Byte> {
+    if let Some(mapping) = self.mapping(addr) {
+      return Some(mapping.device.borrow().peek(addr - mapping.start));
+    }
+
+    self
+      .windows
+      .iter()
+      .find(|w| w.contains(addr))
+      .and_then(|w| w.read(addr))
+  }
+
+  // reads any bank, selected or not
+  pub fn peek_bank(&self, window: usize, bank: usize, offset: usize) -> Result<Byte, Error> {
+    let bank = self.window(window)?.bank(bank)?;
+    bank.data().get(offset).copied().ok_or(Error::OutOfBounds {
+      addr: offset,
+      len: 1,
+    })
+  }
+
+  // writes any bank, ignoring write protection
+  pub fn poke_bank(
+    &mut self,
+    window: usize,
+    bank: usize,
+    offset: usize,
+    data: Byte,
+  ) -> Result<(), Error> {
+    self.load_bank(window, bank, &[data], offset)
+  }
+
+  pub fn load_bank(
+    &mut self,
+    window: usize,
+    bank: usize,
+    data: &[u8],
+    offset: usize,
+  ) -> Result<(), Error> {
+    let dest = self.window_mut(window)?.bank_mut(bank)?.data_mut();
+    let dest = dest
+      .get_mut(offset..offset + data.len())
+      .ok_or(Error::OutOfBounds {
+        addr: offset,
+        len: data.len(),
+      })?;
+    dest.copy_from_slice(data);
+    Ok(())
+  }
+
+  pub fn set_access_counting(&mut self, on: bool) {
+    self.accesses = on.then(|| vec![0; MEM_SIZE]);
+  }
+
+  // bus reads and writes of `addr` since counting started, peeks don't count
+  pub fn access_count(&self, addr: Word) -> u32 {
+    self.accesses.as_ref().map_or(0, |a| a[addr as usize])
+  }
+
+  pub fn clear_access_counts(&mut self) {
+    if let Some(accesses) = &mut self.accesses {
+      accesses.iter_mut().for_each(|count| *count = 0);
+    }
+  }
+
+  // starts recording how each byte is used, a new record each time
+  pub fn start_coverage(&mut self) {
+    self.coverage = Some(Box::new(Coverage::new()));
+  }
+
+  pub fn coverage(&self) -> Option<&Coverage> {
+    self.coverage.as_deref()
+  }
+
+  pub fn stop_coverage(&mut self) -> Option<Coverage> {
+    self.coverage.take().map(|coverage| *coverage)
+  }
+  /* ------- debugger access -------- */
+
+  /* ------- observers -------- */
+  // observers see accesses in the order they're added
+  pub fn add_observer(&mut self, observer: ObserverRef) {
+    self.observers.push(observer);
+  }
+
+  pub fn remove_observer(&mut self, observer: &ObserverRef) {
+    self.observers.retain(|o| !Rc::ptr_eq(o, observer));
+  }
+  /* ------- observers -------- */
+}
+
+// transfers with nothing to do don't take a cycle
+#[cfg(feature = "alloc")]
+fn queue_dma(queue: &mut VecDeque<Dma>, dma: Dma) {
+  if !dma.is_done() {
+    queue.push_back(dma);
   }
 }
@@ -1,32 +1,151 @@
+// the cpu, its policies and a RAM-only bus build without alloc, everything else needs it
+// and the machines need std
+#[cfg(feature = "alloc")]
+mod bank;
+mod coverage;
 mod cpu;
 mod defs;
+#[cfg(feature = "alloc")]
+pub mod devices;
+#[cfg(feature = "alloc")]
+mod dma;
+mod error;
+#[cfg(feature = "std")]
+pub mod machines;
 mod mem;
+#[cfg(feature = "alloc")]
+mod observer;
+#[cfg(feature = "alloc")]
+mod profiler;
+#[cfg(feature = "alloc")]
+mod scheduler;
+#[cfg(feature = "alloc")]
+mod symbols;
+mod test;
+#[cfg(feature = "std")]
+mod throttle;
 
-pub use cpu::CPU;
+#[cfg(feature = "alloc")]
+use alloc::collections::BTreeSet;
+
+#[cfg(feature = "alloc")]
+pub use bank::{Bank, BankRegister, BankWindow};
+pub use coverage::Access;
+#[cfg(feature = "alloc")]
+pub use coverage::Coverage;
+#[cfg(feature = "alloc")]
+pub use cpu::{disassemble, disassemble_with, trace_line, Disassembly};
+pub use cpu::{CpuStatus, IllegalOpcodePolicy, StackPolicy, CPU};
+pub use defs::{Byte, Word, MEM_SIZE};
+#[cfg(feature = "alloc")]
+pub use dma::Dma;
+pub use error::Error;
 pub use mem::Mem;
+#[cfg(feature = "alloc")]
+pub use observer::{BusEvent, Observer, ObserverRef};
+#[cfg(feature = "alloc")]
+pub use profiler::{PcStats, Profiler, Routine};
+#[cfg(feature = "alloc")]
+pub use scheduler::{Event, Line, Scheduler};
+#[cfg(feature = "alloc")]
+pub use symbols::{ParseError, SourceLine, Symbols};
+#[cfg(feature = "std")]
+pub use throttle::{Speed, Throttle};
 
-use self::defs::Word;
+#[cfg(feature = "alloc")]
+pub struct Vm {
+  pub cpu: CPU,
+  pub scheduler: Scheduler,
+  breakpoints: BTreeSet<Word>,
+  cycles: u64,
+}
 
-struct Vm {
-  cpu: CPU,
+#[cfg(feature = "alloc")]
+impl Default for Vm {
+  fn default() -> Self {
+    Self::new()
+  }
 }
 
+#[cfg(feature = "alloc")]
 impl Vm {
-  fn new() -> Self {
+  pub fn new() -> Self {
     let mem = Mem::new();
-    Self { cpu: CPU::new(mem) }
+    Self {
+      cpu: CPU::new(mem),
+      scheduler: Scheduler::new(),
+      breakpoints: BTreeSet::new(),
+      cycles: 0,
+    }
   }
 
-  fn reset(&mut self) {
+  pub fn reset(&mut self) {
+    self.scheduler.reset();
     self.cpu.reset();
   }
 
-  fn load(&mut self, data: &[u8], offset: Word) {
-    self.cpu.mem.load(data, offset);
+  pub fn load(&mut self, data: &[u8], offset: Word) -> Result<(), Error> {
+    self.cpu.mem.load(data, offset)
+  }
+
+  // a cpu cycle, and however many master cycles that takes
+  pub fn step(&mut self) -> Result<(), Error> {
+    let result = self.scheduler.step(&mut self.cpu);
+    match &result {
+      Err(err) if !err.cycle_ran() => {}
+      _ => self.cycles += 1,
+    }
+    result
   }
 
-  fn step(&mut self) {
-    self.cpu.clock();
+  pub fn cycles(&self) -> u64 {
+    self.cycles
   }
 
+  // finishes the current instruction, or the reset sequence, and runs the next one
+  pub fn step_instruction(&mut self) -> Result<(), Error> {
+    while self.cpu.cycles > 0 {
+      self.step()?;
+    }
+    self.step()?;
+    while self.cpu.cycles > 0 {
+      self.step()?;
+    }
+    Ok(())
+  }
+
+  // runs for up to `max_cycles`, stopping before the instruction at a breakpoint
+  // and returning its address. step off a breakpoint before running again
+  pub fn run(&mut self, max_cycles: u64) -> Result<Option<Word>, Error> {
+    for _ in 0..max_cycles {
+      if self.cpu.cycles == 0 && self.breakpoints.contains(&self.cpu.pc) {
+        return Ok(Some(self.cpu.pc));
+      }
+      self.step()?;
+    }
+
+    Ok(None)
+  }
+
+  /* ------- breakpoints -------- */
+  pub fn add_breakpoint(&mut self, addr: Word) {
+    self.breakpoints.insert(addr);
+  }
+
+  pub fn remove_breakpoint(&mut self, addr: Word) {
+    self.breakpoints.remove(&addr);
+  }
+
+  // returns whether there's a breakpoint at `addr` now
+  pub fn toggle_breakpoint(&mut self, addr: Word) -> bool {
+    if !self.breakpoints.remove(&addr) {
+      self.breakpoints.insert(addr);
+    }
+    self.breakpoints.contains(&addr)
+  }
+
+  pub fn breakpoints(&self) -> impl Iterator<Item = Word> + '_ {
+    self.breakpoints.iter().copied()
+  }
+  /* ------- breakpoints -------- */
 }
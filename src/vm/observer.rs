@@ -0,0 +1,26 @@
+use alloc::rc::Rc;
+use core::cell::RefCell;
+
+use super::{
+  coverage::Access,
+  defs::{Byte, Word},
+};
+
+// one cycle's access to the bus. `access` is OPCODE, OPERAND, READ or WRITTEN, with
+// DUMMY for the reads and write-backs the 6502 makes without using them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BusEvent {
+  pub addr: Word,
+  pub data: Byte,
+  pub cycle: u64,
+  pub access: Access,
+}
+
+// sees every access in the order the cpu makes them, tracers, watchpoints and the like
+// can be built on it without touching the cpu
+pub trait Observer {
+  fn access(&mut self, event: &BusEvent);
+}
+
+// shared so the host can look at what an observer gathered while it's attached
+pub type ObserverRef = Rc<RefCell<dyn Observer>>;
@@ -0,0 +1,345 @@
+use alloc::{
+  collections::BTreeMap,
+  format,
+  string::{String, ToString},
+  vec,
+  vec::Vec,
+};
+use core::{cmp::Reverse, fmt::Write};
+
+use super::{
+  cpu::disassemble_with,
+  defs::{Byte, Word},
+  mem::Mem,
+  symbols::Symbols,
+};
+
+const JSR: Byte = 0x20;
+const BRK: Byte = 0x00;
+const RTS: Byte = 0x60;
+const RTI: Byte = 0x40;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PcStats {
+  pub executions: u64,
+  pub cycles: u64,
+}
+
+// a subroutine summed over every place it was called from, inclusive cycles count
+// recursive calls once
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Routine {
+  pub addr: Word,
+  pub calls: u64,
+  pub inclusive: u64,
+  pub exclusive: u64,
+}
+
+// a routine reached through one particular chain of calls
+#[derive(Debug, Clone)]
+struct CallNode {
+  addr: Word,
+  parent: Option<usize>,
+  children: BTreeMap<Word, usize>,
+  calls: u64,
+  exclusive: u64,
+}
+
+impl CallNode {
+  fn new(addr: Word, parent: Option<usize>) -> Self {
+    Self {
+      addr,
+      parent,
+      children: BTreeMap::new(),
+      calls: 0,
+      exclusive: 0,
+    }
+  }
+}
+
+// executions and cycles per pc, and a call tree built from JSR/RTS, BRK/RTI and interrupts.
+// the root of the tree is whatever was running when profiling started
+#[derive(Debug, Clone)]
+pub struct Profiler {
+  pcs: Vec<PcStats>,
+  nodes: Vec<CallNode>,
+  // the node of each routine we're in and the sp it returns to
+  stack: Vec<(usize, Byte)>,
+  instructions: u64,
+  cycles: u64,
+}
+
+impl Default for Profiler {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Profiler {
+  pub fn new() -> Self {
+    Self {
+      pcs: vec![PcStats::default(); 0x10000],
+      nodes: vec![CallNode::new(0, None)],
+      stack: Vec::new(),
+      instructions: 0,
+      cycles: 0,
+    }
+  }
+
+  /* ------- recording -------- */
+  // an instruction at `pc` that took `cycles`, `sp` before it and `next_pc` and
+  // `next_sp` after it
+  pub fn instruction(
+    &mut self,
+    pc: Word,
+    op_code: Byte,
+    cycles: u8,
+    sp: Byte,
+    next_pc: Word,
+    next_sp: Byte,
+  ) {
+    if self.instructions == 0 {
+      self.nodes[0].addr = pc;
+    }
+    self.instructions += 1;
+    self.add_cycles(pc, cycles);
+    self.pcs[pc as usize].executions += 1;
+
+    match op_code {
+      JSR | BRK => self.enter(next_pc, sp),
+      RTS | RTI => self.leave(next_sp),
+      _ => {}
+    }
+  }
+
+  // an IRQ or NMI that went to `vector_pc`, taking `cycles`, with `sp` from before it.
+  // the cycles of the sequence count towards the handler's first instruction
+  pub fn interrupt(&mut self, vector_pc: Word, cycles: u8, sp: Byte) {
+    self.enter(vector_pc, sp);
+    self.add_cycles(vector_pc, cycles);
+  }
+
+  fn add_cycles(&mut self, pc: Word, cycles: u8) {
+    let node = self.current();
+    self.nodes[node].exclusive += cycles as u64;
+    self.pcs[pc as usize].cycles += cycles as u64;
+    self.cycles += cycles as u64;
+  }
+
+  fn current(&self) -> usize {
+    self.stack.last().map_or(0, |&(node, _)| node)
+  }
+
+  fn enter(&mut self, addr: Word, sp: Byte) {
+    let parent = self.current();
+    let node = match self.nodes[parent].children.get(&addr) {
+      Some(&node) => node,
+      None => {
+        self.nodes.push(CallNode::new(addr, Some(parent)));
+        let node = self.nodes.len() - 1;
+        self.nodes[parent].children.insert(addr, node);
+        node
+      }
+    };
+
+    self.nodes[node].calls += 1;
+    self.stack.push((node, sp));
+  }
+
+  // drops every routine the stack has been unwound past, so pulling the return
+  // address before an RTS leaves two routines
+  fn leave(&mut self, sp: Byte) {
+    while let Some(&(_, frame_sp)) = self.stack.last() {
+      if frame_sp > sp {
+        break;
+      }
+      self.stack.pop();
+    }
+  }
+  /* ------- recording -------- */
+
+  /* ------- results -------- */
+  pub fn instructions(&self) -> u64 {
+    self.instructions
+  }
+
+  pub fn cycles(&self) -> u64 {
+    self.cycles
+  }
+
+  pub fn pc(&self, addr: Word) -> PcStats {
+    self.pcs[addr as usize]
+  }
+
+  // every routine that was called, the most inclusive cycles first
+  pub fn routines(&self) -> Vec<Routine> {
+    let inclusive = self.inclusive();
+    let mut routines: BTreeMap<Word, Routine> = BTreeMap::new();
+
+    for (i, node) in self.nodes.iter().enumerate().skip(1) {
+      let routine = routines.entry(node.addr).or_insert(Routine {
+        addr: node.addr,
+        ..Routine::default()
+      });
+      routine.calls += node.calls;
+      routine.exclusive += node.exclusive;
+      if !self.recursive(i) {
+        routine.inclusive += inclusive[i];
+      }
+    }
+
+    let mut routines: Vec<Routine> = routines.into_values().collect();
+    routines.sort_by(|a, b| b.inclusive.cmp(&a.inclusive).then(a.addr.cmp(&b.addr)));
+    routines
+  }
+
+  // each node's cycles with its callees', children always come after their parents
+  fn inclusive(&self) -> Vec<u64> {
+    let mut inclusive: Vec<u64> = self.nodes.iter().map(|node| node.exclusive).collect();
+    for i in (1..self.nodes.len()).rev() {
+      if let Some(parent) = self.nodes[i].parent {
+        inclusive[parent] += inclusive[i];
+      }
+    }
+    inclusive
+  }
+
+  // whether the node's routine is also one of its callers
+  fn recursive(&self, node: usize) -> bool {
+    let addr = self.nodes[node].addr;
+    let mut parent = self.nodes[node].parent;
+    while let Some(i) = parent {
+      if i != 0 && self.nodes[i].addr == addr {
+        return true;
+      }
+      parent = self.nodes[i].parent;
+    }
+    false
+  }
+
+  // hot spots, cycles per label and the subroutines, `top` rows of each
+  pub fn report(&self, mem: &Mem, symbols: Option<&Symbols>, top: usize) -> String {
+    let percent = |cycles: u64| cycles as f64 * 100.0 / self.cycles.max(1) as f64;
+    let mut out = String::new();
+    let _ = writeln!(
+      out,
+      "{} cycles, {} instructions",
+      self.cycles, self.instructions
+    );
+
+    let mut hot: Vec<Word> = (0..=0xFFFF)
+      .filter(|&addr| self.pcs[addr as usize].executions > 0)
+      .collect();
+    hot.sort_by_key(|&addr| Reverse(self.pcs[addr as usize].cycles));
+
+    let _ = writeln!(out, "\nhot spots");
+    let _ = writeln!(
+      out,
+      "  addr  {:<20} {:>10} {:>12} {:>6}  instruction",
+      "location", "count", "cycles", "%"
+    );
+    for &addr in hot.iter().take(top) {
+      let stats = self.pcs[addr as usize];
+      let text = disassemble_with(mem, addr, symbols).text;
+      let _ = writeln!(
+        out,
+        "  {:04X}  {:<20} {:>10} {:>12} {:>6.2}  {}",
+        addr,
+        location(symbols, addr),
+        stats.executions,
+        stats.cycles,
+        percent(stats.cycles),
+        text
+      );
+    }
+
+    if let Some(symbols) = symbols {
+      let mut labels: BTreeMap<&str, PcStats> = BTreeMap::new();
+      for &addr in &hot {
+        if let Some((_, name)) = symbols.containing(addr) {
+          let label = labels.entry(name).or_default();
+          label.executions += self.pcs[addr as usize].executions;
+          label.cycles += self.pcs[addr as usize].cycles;
+        }
+      }
+      let mut labels: Vec<(&str, PcStats)> = labels.into_iter().collect();
+      labels.sort_by_key(|(_, stats)| Reverse(stats.cycles));
+
+      let _ = writeln!(out, "\nlabels");
+      let _ = writeln!(
+        out,
+        "  {:<26} {:>10} {:>12} {:>6}",
+        "label", "count", "cycles", "%"
+      );
+      for (name, stats) in labels.iter().take(top) {
+        let _ = writeln!(
+          out,
+          "  {:<26} {:>10} {:>12} {:>6.2}",
+          name,
+          stats.executions,
+          stats.cycles,
+          percent(stats.cycles)
+        );
+      }
+    }
+
+    let _ = writeln!(out, "\nsubroutines");
+    let _ = writeln!(
+      out,
+      "  {:<26} {:>8} {:>12} {:>6} {:>12} {:>6}",
+      "routine", "calls", "inclusive", "%", "exclusive", "%"
+    );
+    for routine in self.routines().iter().take(top) {
+      let _ = writeln!(
+        out,
+        "  {:<26} {:>8} {:>12} {:>6.2} {:>12} {:>6.2}",
+        name(symbols, routine.addr),
+        routine.calls,
+        routine.inclusive,
+        percent(routine.inclusive),
+        routine.exclusive,
+        percent(routine.exclusive)
+      );
+    }
+
+    out
+  }
+
+  // the call tree as folded stacks, `root;routine;callee cycles` lines for flamegraph.pl
+  // or inferno
+  pub fn folded(&self, symbols: Option<&Symbols>) -> String {
+    let mut out = String::new();
+    for node in &self.nodes {
+      if node.exclusive == 0 {
+        continue;
+      }
+
+      let mut path = vec![name(symbols, node.addr)];
+      let mut parent = node.parent;
+      while let Some(p) = parent {
+        path.push(name(symbols, self.nodes[p].addr));
+        parent = self.nodes[p].parent;
+      }
+      path.reverse();
+      let _ = writeln!(out, "{} {}", path.join(";"), node.exclusive);
+    }
+    out
+  }
+  /* ------- results -------- */
+}
+
+fn name(symbols: Option<&Symbols>, addr: Word) -> String {
+  match symbols.and_then(|s| s.label(addr)) {
+    Some(label) => label.to_string(),
+    None => format!("${:04X}", addr),
+  }
+}
+
+// label+offset of the closest label below `addr`
+fn location(symbols: Option<&Symbols>, addr: Word) -> String {
+  match symbols.and_then(|s| s.containing(addr)) {
+    Some((start, label)) if start == addr => label.to_string(),
+    Some((start, label)) => format!("{}+{}", label, addr - start),
+    None => String::new(),
+  }
+}
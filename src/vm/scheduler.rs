@@ -0,0 +1,256 @@
+use alloc::{boxed::Box, rc::Rc, vec::Vec};
+
+use super::{cpu::CPU, devices::DeviceRef, error::Error, mem::Mem};
+
+// the cpu's interrupt inputs, IRQ is a level and NMI is taken on its falling edge
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Line {
+  Irq,
+  Nmi,
+}
+
+// what a timer does when it expires
+pub enum Event {
+  // sets or releases the scheduler's own irq output
+  Irq(bool),
+  // an edge on the nmi line
+  Nmi,
+  // anything else, like setting a device's vblank flag
+  Call(Box<dyn FnMut(&mut CPU)>),
+}
+
+struct Clocked {
+  device: DeviceRef,
+  divider: u64,
+  line: Option<Line>,
+}
+
+struct Timer {
+  id: usize,
+  at: u64,
+  period: Option<u64>,
+  event: Event,
+}
+
+// a master clock the cpu and the other chips of a system run off, each with its own
+// divider. devices attached here are ticked by it instead of the bus, and their irq
+// outputs and the timers' events drive the cpu's interrupt lines between instructions
+pub struct Scheduler {
+  now: u64,
+  cpu_divider: u64,
+  clocked: Vec<Clocked>,
+  timers: Vec<Timer>,
+  next_id: usize,
+  // the earliest timer, so quiet cycles don't look through them
+  next_at: u64,
+
+  irq: bool,
+  nmi: bool,
+  nmi_pending: bool,
+}
+
+impl Default for Scheduler {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Scheduler {
+  pub fn new() -> Self {
+    Self {
+      now: 0,
+      cpu_divider: 1,
+      clocked: Vec::new(),
+      timers: Vec::new(),
+      next_id: 0,
+      next_at: u64::MAX,
+
+      irq: false,
+      nmi: false,
+      nmi_pending: false,
+    }
+  }
+
+  // the master cycles that have gone by
+  pub fn now(&self) -> u64 {
+    self.now
+  }
+
+  // the cpu runs a cycle every `divider` master cycles
+  pub fn set_cpu_divider(&mut self, divider: u64) {
+    assert!(divider > 0, "a divider can't be 0");
+    self.cpu_divider = divider;
+  }
+
+  pub fn cpu_divider(&self) -> u64 {
+    self.cpu_divider
+  }
+
+  // drops a pending nmi, the cpu's reset doesn't see it
+  pub fn reset(&mut self) {
+    self.nmi_pending = false;
+  }
+
+  /* ------- devices -------- */
+  // ticks `device` every `divider` master cycles with its irq output on `line`, or on
+  // nothing. it's taken off the bus's clock and irq line so it isn't run twice
+  pub fn attach(&mut self, mem: &mut Mem, device: DeviceRef, divider: u64, line: Option<Line>) {
+    assert!(divider > 0, "a divider can't be 0");
+    mem.set_device_clocked(&device, false);
+    mem.set_irq_wired(&device, false);
+    self.detach(&device);
+    self.clocked.push(Clocked {
+      device,
+      divider,
+      line,
+    });
+  }
+
+  // leaves the device unclocked, attach it again or give it back to the bus
+  pub fn detach(&mut self, device: &DeviceRef) {
+    self.clocked.retain(|c| !Rc::ptr_eq(&c.device, device));
+  }
+  /* ------- devices -------- */
+
+  /* ------- timers -------- */
+  // runs `event` `delay` master cycles from now, returns the timer's id
+  pub fn schedule(&mut self, delay: u64, event: Event) -> usize {
+    self.add_timer(delay, None, event)
+  }
+
+  // runs `event` every `period` master cycles, the first time a period from now
+  pub fn schedule_every(&mut self, period: u64, event: Event) -> usize {
+    assert!(period > 0, "a period can't be 0");
+    self.add_timer(period, Some(period), event)
+  }
+
+  // returns whether the timer was still waiting
+  pub fn cancel(&mut self, id: usize) -> bool {
+    let len = self.timers.len();
+    self.timers.retain(|t| t.id != id);
+    self.update_next_at();
+    self.timers.len() != len
+  }
+
+  // master cycles until the timer expires, none once it has
+  pub fn remaining(&self, id: usize) -> Option<u64> {
+    self
+      .timers
+      .iter()
+      .find(|t| t.id == id)
+      .map(|t| t.at - self.now)
+  }
+
+  fn add_timer(&mut self, delay: u64, period: Option<u64>, event: Event) -> usize {
+    let id = self.next_id;
+    self.next_id += 1;
+    self.timers.push(Timer {
+      id,
+      at: self.now + delay,
+      period,
+      event,
+    });
+    self.next_at = self.next_at.min(self.now + delay);
+    id
+  }
+
+  fn update_next_at(&mut self) {
+    self.next_at = self.timers.iter().map(|t| t.at).min().unwrap_or(u64::MAX);
+  }
+
+  // events that are due fire in the order they were scheduled
+  fn fire_timers(&mut self, cpu: &mut CPU) {
+    if self.now < self.next_at {
+      return;
+    }
+
+    let now = self.now;
+    let mut fired = Vec::new();
+    for (i, timer) in self.timers.iter_mut().enumerate() {
+      if timer.at > now {
+        continue;
+      }
+      match &mut timer.event {
+        Event::Irq(level) => self.irq = *level,
+        Event::Nmi => self.nmi_pending = true,
+        Event::Call(call) => call(cpu),
+      }
+      match timer.period {
+        Some(period) => timer.at += period,
+        None => fired.push(i),
+      }
+    }
+    for i in fired.into_iter().rev() {
+      self.timers.remove(i);
+    }
+    self.update_next_at();
+  }
+  /* ------- timers -------- */
+
+  // the scheduler's irq output, wired-or with the bus's
+  pub fn set_irq(&mut self, level: bool) {
+    self.irq = level;
+  }
+
+  pub fn irq(&self) -> bool {
+    self.irq
+      || self
+        .clocked
+        .iter()
+        .any(|c| c.line == Some(Line::Irq) && c.device.borrow().irq())
+  }
+
+  // runs one master cycle, returns whether the cpu ran one of its cycles in it.
+  // the devices tick after the cpu like the ones on the bus, and interrupts are only
+  // taken between instructions
+  pub fn clock(&mut self, cpu: &mut CPU) -> Result<bool, Error> {
+    self.fire_timers(cpu);
+
+    let cpu_cycle = self.now % self.cpu_divider == 0;
+    let mut result = Ok(cpu_cycle);
+    if cpu_cycle {
+      if cpu.cycles == 0 && cpu.ready() {
+        if self.nmi_pending {
+          self.nmi_pending = false;
+          cpu.nmi();
+        } else if self.irq() {
+          cpu.irq();
+        }
+      }
+      match cpu.clock() {
+        Ok(()) => {}
+        // the cycle runs again, nothing else moves on
+        Err(err) if !err.cycle_ran() => return Err(err),
+        // the cpu used the cycle, so the devices and the clock keep up with it
+        Err(err) => result = Err(err),
+      }
+    }
+
+    for clocked in &self.clocked {
+      if self.now % clocked.divider == 0 {
+        let mut device = clocked.device.borrow_mut();
+        device.tick();
+        if let Some(dma) = device.dma() {
+          cpu.mem.start_dma(dma);
+        }
+      }
+    }
+    let nmi = self
+      .clocked
+      .iter()
+      .any(|c| c.line == Some(Line::Nmi) && c.device.borrow().irq());
+    if nmi && !self.nmi {
+      self.nmi_pending = true;
+    }
+    self.nmi = nmi;
+
+    self.now += 1;
+    result
+  }
+
+  // runs master cycles up to and including the cpu's next cycle
+  pub fn step(&mut self, cpu: &mut CPU) -> Result<(), Error> {
+    while !self.clock(cpu)? {}
+    Ok(())
+  }
+}
@@ -0,0 +1,353 @@
+use alloc::{
+  collections::BTreeMap,
+  format,
+  string::{String, ToString},
+  vec::Vec,
+};
+use core::fmt;
+
+use super::defs::Word;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+  pub line: usize,
+  pub message: String,
+}
+
+impl fmt::Display for ParseError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "line {}: {}", self.line, self.message)
+  }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {}
+
+fn error(line: usize, message: impl Into<String>) -> ParseError {
+  ParseError {
+    line: line + 1,
+    message: message.into(),
+  }
+}
+
+// "$1234", "0x1234" or a bare "1234", all hex like the monitors expect
+fn parse_hex(text: &str) -> Option<Word> {
+  let text = text.trim();
+  let digits = text
+    .strip_prefix('$')
+    .or_else(|| text.strip_prefix("0x"))
+    .unwrap_or(text);
+  Word::from_str_radix(digits, 16).ok()
+}
+
+// `len` bytes from `addr` assembled from a line of source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLine {
+  pub file: String,
+  pub line: u32,
+  pub addr: Word,
+  pub len: Word,
+}
+
+// label names both ways, an address shows the first label defined for it.
+// only ca65 debug info has source lines
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Symbols {
+  addrs: BTreeMap<String, Word>,
+  labels: BTreeMap<Word, String>,
+  lines: Vec<SourceLine>,
+}
+
+impl Symbols {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn insert(&mut self, name: &str, addr: Word) {
+    self.addrs.insert(name.to_string(), addr);
+    self.labels.entry(addr).or_insert_with(|| name.to_string());
+  }
+
+  pub fn addr(&self, name: &str) -> Option<Word> {
+    self.addrs.get(name).copied()
+  }
+
+  pub fn label(&self, addr: Word) -> Option<&str> {
+    self.labels.get(&addr).map(|name| name.as_str())
+  }
+
+  // the closest label at or below `addr`, with its address
+  pub fn containing(&self, addr: Word) -> Option<(Word, &str)> {
+    self
+      .labels
+      .range(..=addr)
+      .next_back()
+      .map(|(&start, name)| (start, name.as_str()))
+  }
+
+  pub fn lines(&self) -> &[SourceLine] {
+    &self.lines
+  }
+
+  // adds `other`'s labels and lines, an address keeps the label it already had
+  pub fn merge(&mut self, other: &Symbols) {
+    self.lines.extend(other.lines.iter().cloned());
+    for (&addr, name) in &other.labels {
+      self.labels.entry(addr).or_insert_with(|| name.clone());
+    }
+    for (name, &addr) in &other.addrs {
+      self.addrs.insert(name.clone(), addr);
+      self.labels.entry(addr).or_insert_with(|| name.clone());
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    self.addrs.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.addrs.is_empty()
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = (&str, Word)> {
+    self.addrs.iter().map(|(name, &addr)| (name.as_str(), addr))
+  }
+
+  // an address from "label", "$1234", "1234" or sums like "table+3" and "end-$10",
+  // labels win over bare hex so a label called "add" isn't $0ADD
+  pub fn resolve(&self, expr: &str) -> Option<Word> {
+    let mut total: Word = 0;
+    let mut sign = '+';
+    let mut rest = expr.trim();
+
+    loop {
+      let end = rest.find(['+', '-']).unwrap_or(rest.len());
+      let term = rest[..end].trim();
+      let value = self.addr(term).or_else(|| parse_hex(term))?;
+      total = match sign {
+        '+' => total.wrapping_add(value),
+        _ => total.wrapping_sub(value),
+      };
+
+      if end == rest.len() {
+        return Some(total);
+      }
+      sign = rest[end..].chars().next()?;
+      rest = &rest[end + 1..];
+    }
+  }
+
+  /* ------- parsing -------- */
+  // guesses the format, ca65 debug info starts with a version line
+  // and VICE label files are all "al" commands
+  pub fn parse(text: &str) -> Result<Self, ParseError> {
+    let first = text.lines().map(str::trim).find(|line| !line.is_empty());
+    match first {
+      Some(line) if line.starts_with("version") => Self::parse_ca65_dbg(text),
+      Some(line) if line.starts_with("al ") => Self::parse_vice(text),
+      _ => Self::parse_sym(text),
+    }
+  }
+
+  // `al C:0801 .start`, as written by VICE and ld65 -Ln
+  pub fn parse_vice(text: &str) -> Result<Self, ParseError> {
+    let mut symbols = Self::new();
+    for (i, line) in text.lines().enumerate() {
+      let mut words = line.split_whitespace();
+      match words.next() {
+        Some("al") => {}
+        None => continue,
+        Some(_) => return Err(error(i, "expected an al command")),
+      }
+
+      let (addr, name) = match (words.next(), words.next()) {
+        (Some(addr), Some(name)) => (addr, name),
+        _ => return Err(error(i, "expected an address and a label")),
+      };
+      // the memory space prefix, C: is the computer's
+      let addr = addr.rsplit(':').next().unwrap_or(addr);
+      let addr = parse_hex(addr).ok_or_else(|| error(i, format!("bad address {}", addr)))?;
+      symbols.insert(name.trim_start_matches('.'), addr);
+    }
+
+    Ok(symbols)
+  }
+
+  // `label = $1234`, `label := $1234` or `label $1234`, comments start with ;
+  pub fn parse_sym(text: &str) -> Result<Self, ParseError> {
+    let mut symbols = Self::new();
+    for (i, line) in text.lines().enumerate() {
+      let line = line.split(';').next().unwrap_or("").trim();
+      if line.is_empty() {
+        continue;
+      }
+
+      let (name, addr) = match line.split_once('=') {
+        Some((name, addr)) => (name.trim_end_matches(':').trim(), addr),
+        None => line
+          .split_once(char::is_whitespace)
+          .ok_or_else(|| error(i, "expected a label and an address"))?,
+      };
+      let addr = parse_hex(addr).ok_or_else(|| error(i, format!("bad address {}", addr.trim())))?;
+      symbols.insert(name, addr);
+    }
+
+    Ok(symbols)
+  }
+
+  // ld65 --dbgfile output, the labels of its sym lines qualified by their scopes,
+  // cheap locals like @loop are qualified by the label before them. line records
+  // become source lines through their spans
+  pub fn parse_ca65_dbg(text: &str) -> Result<Self, ParseError> {
+    let mut scopes: BTreeMap<String, (String, Option<String>)> = BTreeMap::new();
+    let mut syms = Vec::new();
+    let mut files = BTreeMap::new();
+    let mut segs = BTreeMap::new();
+    let mut spans = BTreeMap::new();
+    let mut lines = Vec::new();
+
+    for (i, line) in text.lines().enumerate() {
+      let (kind, fields) = match line.split_once(char::is_whitespace) {
+        Some((kind, fields)) => (kind, dbg_fields(fields)),
+        None => continue,
+      };
+
+      let field = |key: &str| fields.get(key).cloned();
+      let id = || field("id").ok_or_else(|| error(i, format!("{} without an id", kind)));
+      match kind {
+        "scope" => {
+          let name = field("name").unwrap_or_default();
+          scopes.insert(id()?, (name, field("parent")));
+        }
+        "sym" if field("type").as_deref() == Some("lab") => syms.push((i, fields)),
+        "file" => {
+          files.insert(id()?, field("name").unwrap_or_default());
+        }
+        "seg" => {
+          let start = field("start").and_then(|start| dbg_number(&start));
+          segs.insert(id()?, start);
+        }
+        "span" => {
+          let start = field("start").and_then(|start| dbg_number(&start));
+          let size = field("size").and_then(|size| dbg_number(&size));
+          match (field("seg"), start, size) {
+            (Some(seg), Some(start), Some(size)) => {
+              spans.insert(id()?, (seg, start, size));
+            }
+            _ => return Err(error(i, "span without a segment, start or size")),
+          }
+        }
+        "line" if fields.contains_key("span") => lines.push((i, fields)),
+        _ => {}
+      }
+    }
+
+    // a scope's name with its parents', the global scope has none
+    let qualified = |mut id: Option<String>| {
+      let mut names = Vec::new();
+      while let Some((name, parent)) = id.and_then(|id| scopes.get(&id)) {
+        if !name.is_empty() {
+          names.push(name.as_str());
+        }
+        id = parent.clone();
+      }
+      names.reverse();
+      names
+    };
+
+    let names: BTreeMap<&str, &str> = syms
+      .iter()
+      .filter_map(|(_, f)| Some((f.get("id")?.as_str(), f.get("name")?.as_str())))
+      .collect();
+
+    let mut symbols = Self::new();
+    for (i, fields) in &syms {
+      let name = fields
+        .get("name")
+        .ok_or_else(|| error(*i, "sym without a name"))?;
+      let val = fields
+        .get("val")
+        .ok_or_else(|| error(*i, "sym without a value"))?;
+      let addr = match parse_hex(val) {
+        Some(addr) => addr,
+        // far labels don't fit the 6502's address space
+        None if val.starts_with("0x") => continue,
+        None => return Err(error(*i, format!("bad value {}", val))),
+      };
+
+      let mut path = qualified(fields.get("scope").cloned());
+      let parent = fields.get("parent").and_then(|id| names.get(id.as_str()));
+      let local = match parent {
+        Some(parent) => format!("{}{}", parent, name),
+        None => name.clone(),
+      };
+      path.push(&local);
+      symbols.insert(&path.join("::"), addr);
+    }
+
+    for (i, fields) in &lines {
+      let file = fields
+        .get("file")
+        .and_then(|id| files.get(id))
+        .ok_or_else(|| error(*i, "line in an unknown file"))?;
+      let line = fields
+        .get("line")
+        .and_then(|line| line.parse().ok())
+        .ok_or_else(|| error(*i, "line without a line number"))?;
+
+      for id in fields["span"].split('+') {
+        let (seg, start, size) = spans
+          .get(id)
+          .ok_or_else(|| error(*i, format!("unknown span {}", id)))?;
+        let seg_start = segs
+          .get(seg)
+          .ok_or_else(|| error(*i, format!("unknown segment {}", seg)))?;
+        // far segments again
+        let addr = match seg_start.map(|seg_start| seg_start + start) {
+          Some(addr) if addr <= 0xFFFF => addr as Word,
+          _ => continue,
+        };
+        symbols.lines.push(SourceLine {
+          file: file.clone(),
+          line,
+          addr,
+          len: (*size).min(0x10000 - addr as u32) as Word,
+        });
+      }
+    }
+
+    Ok(symbols)
+  }
+  /* ------- parsing -------- */
+}
+
+// debug info numbers are hex with 0x or decimal
+fn dbg_number(text: &str) -> Option<u32> {
+  match text.strip_prefix("0x") {
+    Some(hex) => u32::from_str_radix(hex, 16).ok(),
+    None => text.parse().ok(),
+  }
+}
+
+// key=value pairs split on commas outside of quotes, the quotes are removed
+fn dbg_fields(text: &str) -> BTreeMap<String, String> {
+  let mut fields = BTreeMap::new();
+  let mut quoted = false;
+  let mut start = 0;
+  let text = text.trim();
+
+  for (i, ch) in text.char_indices().chain([(text.len(), ',')]) {
+    match ch {
+      '"' => quoted = !quoted,
+      ',' if !quoted => {
+        if let Some((key, value)) = text[start..i].split_once('=') {
+          fields.insert(key.to_string(), value.trim_matches('"').to_string());
+        }
+        start = i + 1;
+      }
+      _ => {}
+    }
+  }
+
+  fields
+}
@@ -0,0 +1,816 @@
+#[cfg(all(test, feature = "std"))]
+mod tests {
+  use std::{
+    cell::RefCell,
+    rc::Rc,
+    thread,
+    time::{Duration, Instant},
+  };
+
+  use crate::vm::{
+    devices::{Device, DeviceRef, Via},
+    disassemble_with, Access, Bank, BankWindow, BusEvent, Dma, Error, Event, IllegalOpcodePolicy,
+    Line, Mem, Observer, ObserverRef, SourceLine, Speed, StackPolicy, Symbols, Throttle, Vm, CPU,
+  };
+
+  #[test]
+  fn bank_register_switches_window() {
+    let mut mem = Mem::new();
+    mem.write(0x8000, 0x11);
+
+    let window = mem
+      .add_window(
+        0x8000,
+        0x4000,
+        vec![Bank::ram(0x4000), Bank::rom(&[0xAA; 0x4000])],
+        Some(0),
+      )
+      .unwrap();
+    mem.add_bank_register(0xFFF0, window, 0x01).unwrap();
+
+    mem.write(0x8000, 0x22);
+    assert_eq!(
+      mem.read(0x8000),
+      0x22,
+      "RAM bank didn't keep the written value"
+    );
+    assert_eq!(
+      mem.peek_ram(0x8000),
+      0x11,
+      "write to a RAM bank reached flat memory"
+    );
+
+    mem.write(0xFFF0, 0x03);
+    assert_eq!(
+      mem.selected_bank(window),
+      Some(1),
+      "bank register didn't mask the bank number"
+    );
+    assert_eq!(
+      mem.read(0x8000),
+      0xAA,
+      "ROM bank isn't visible after switching"
+    );
+
+    mem.write(0x8000, 0x33);
+    assert_eq!(mem.read(0x8000), 0xAA, "ROM bank was overwritten");
+    assert_eq!(
+      mem.peek_ram(0x8000),
+      0x33,
+      "write to a ROM bank didn't fall through to RAM"
+    );
+
+    assert_eq!(
+      mem.peek_bank(window, 0, 0),
+      Ok(0x22),
+      "debugger can't see the unselected bank"
+    );
+  }
+
+  #[test]
+  fn reset_restores_initial_bank() {
+    let mut mem = Mem::new();
+    let window = mem
+      .add_window(
+        0xC000,
+        0x1000,
+        vec![Bank::ram(0x1000), Bank::rom(&[0x55; 0x1000])],
+        None,
+      )
+      .unwrap();
+
+    mem.select_bank(window, Some(0)).unwrap();
+    mem.write(0xC010, 0x42);
+    mem.poke_bank(window, 1, 0x10, 0x66).unwrap();
+    mem.reset();
+
+    assert_eq!(
+      mem.selected_bank(window),
+      None,
+      "reset didn't restore the initial bank"
+    );
+    assert_eq!(
+      mem.peek_bank(window, 0, 0x10),
+      Ok(0x00),
+      "reset didn't clear the RAM bank"
+    );
+    assert_eq!(
+      mem.peek_bank(window, 1, 0x10),
+      Ok(0x66),
+      "reset cleared the ROM bank"
+    );
+  }
+
+  #[test]
+  fn bad_banks_are_errors() {
+    let mut mem = Mem::new();
+    let window = mem
+      .add_window(0xC000, 0x1000, vec![Bank::ram(0x1000)], None)
+      .unwrap();
+
+    assert_eq!(
+      mem.select_bank(window, Some(1)),
+      Err(Error::NoBank { bank: 1, banks: 1 })
+    );
+    assert_eq!(mem.selected_bank(window), None, "a bad select was kept");
+    assert_eq!(
+      mem.select_bank(window + 1, Some(0)),
+      Err(Error::NoWindow { window: window + 1 })
+    );
+    assert_eq!(
+      mem.peek_bank(window, 0, 0x1000),
+      Err(Error::OutOfBounds {
+        addr: 0x1000,
+        len: 1
+      })
+    );
+    assert_eq!(
+      mem.poke_bank(window, 2, 0, 0x42),
+      Err(Error::NoBank { bank: 2, banks: 1 })
+    );
+    assert_eq!(
+      mem.add_bank_register(0xFFF0, window + 1, 0x01),
+      Err(Error::NoWindow { window: window + 1 })
+    );
+
+    // a window can't start out on a bank it doesn't have
+    assert_eq!(
+      mem.add_window(0x8000, 0x1000, vec![Bank::ram(0x1000)], Some(1)),
+      Err(Error::NoBank { bank: 1, banks: 1 })
+    );
+    assert_eq!(
+      mem.add_window(0x8000, 0x1000, Vec::new(), Some(0)),
+      Err(Error::NoBank { bank: 0, banks: 0 })
+    );
+    mem.write(0x8000, 0x42);
+    assert_eq!(mem.read(0x8000), 0x42, "a rejected window was mapped");
+
+    // and one made by hand falls through to flat memory instead of panicking
+    let mut window = BankWindow::new(0x8000, 0x1000, Vec::new(), Some(0));
+    assert_eq!(window.read(0x8000), None, "a missing bank was read");
+    assert!(!window.write(0x8000, 0x42), "a missing bank was written");
+  }
+
+  #[test]
+  fn vm_stops_at_breakpoints() {
+    let mut vm = Vm::new();
+    // LDX #$00 ; loop: INX ; JMP loop
+    vm.load(&[0xA2, 0x00, 0xE8, 0x4C, 0x02, 0x02], 0x0200)
+      .unwrap();
+    vm.load(&[0x00, 0x02], 0xFFFC).unwrap();
+    vm.reset();
+
+    vm.add_breakpoint(0x0203);
+    assert_eq!(
+      vm.run(1000).unwrap(),
+      Some(0x0203),
+      "didn't stop at the breakpoint"
+    );
+    assert_eq!(vm.cpu.reg_x, 1);
+
+    // stepping off it lets the next run go round the loop again
+    vm.step_instruction().unwrap();
+    assert_eq!(vm.cpu.pc, 0x0202, "JMP didn't run");
+    assert_eq!(vm.run(1000).unwrap(), Some(0x0203));
+    assert_eq!(vm.cpu.reg_x, 2, "loop didn't run once more");
+
+    assert!(!vm.toggle_breakpoint(0x0203), "breakpoint wasn't removed");
+    assert_eq!(vm.run(1000).unwrap(), None, "stopped without a breakpoint");
+
+    assert_eq!(
+      vm.load(&[0xEA; 4], 0xFFFE),
+      Err(Error::OutOfBounds {
+        addr: 0xFFFE,
+        len: 4
+      }),
+      "a load past the end of memory didn't fail"
+    );
+  }
+
+  #[test]
+  fn symbol_files_parse() {
+    let vice = Symbols::parse("al C:0801 .start\nal C:d020 .border\n").unwrap();
+    assert_eq!(vice.addr("start"), Some(0x0801), "VICE label missing");
+    assert_eq!(vice.label(0xD020), Some("border"));
+
+    let sym = Symbols::parse("; zero page\nptr = $fb\nbuffer := $0400\nreset $e000\n").unwrap();
+    assert_eq!(sym.addr("ptr"), Some(0x00FB));
+    assert_eq!(sym.addr("buffer"), Some(0x0400));
+    assert_eq!(sym.addr("reset"), Some(0xE000));
+    assert_eq!(Symbols::parse("ptr = $zz").unwrap_err().line, 1);
+
+    let dbg = r#"version major=2,minor=0
+scope id=0,name="",mod=0,size=16
+scope id=1,name="game",mod=0,type=scope,size=8,parent=0
+sym id=0,name="main",addrsize=absolute,scope=0,def=1,ref=2,val=0x200,seg=0,type=lab
+sym id=1,name="tick",addrsize=absolute,scope=1,def=3,val=0x208,seg=0,type=lab
+sym id=2,name="@loop",addrsize=absolute,scope=1,parent=1,def=4,val=0x20A,seg=0,type=lab
+sym id=3,name="SCREEN",addrsize=absolute,scope=0,def=5,val=0x400,type=equ
+"#;
+    let dbg = Symbols::parse(dbg).unwrap();
+    assert_eq!(dbg.addr("main"), Some(0x0200));
+    assert_eq!(
+      dbg.addr("game::tick"),
+      Some(0x0208),
+      "scope isn't in the name"
+    );
+    assert_eq!(
+      dbg.addr("game::tick@loop"),
+      Some(0x020A),
+      "cheap local isn't qualified"
+    );
+    assert_eq!(dbg.addr("SCREEN"), None, "equates aren't labels");
+  }
+
+  #[test]
+  fn symbols_resolve_and_label_disassembly() {
+    let mut symbols = Symbols::new();
+    symbols.insert("table", 0x0300);
+    symbols.insert("add", 0x0280);
+
+    assert_eq!(symbols.resolve("table+$10"), Some(0x0310));
+    assert_eq!(symbols.resolve("table - 1"), Some(0x02FF));
+    assert_eq!(symbols.resolve("add"), Some(0x0280), "hex beat the label");
+    assert_eq!(symbols.resolve("c000"), Some(0xC000));
+    assert_eq!(symbols.resolve("nowhere"), None);
+
+    let mut mem = Mem::new();
+    // LDA table,X ; JSR add
+    mem
+      .load(&[0xBD, 0x00, 0x03, 0x20, 0x80, 0x02], 0x0200)
+      .unwrap();
+    let lda = disassemble_with(&mem, 0x0200, Some(&symbols));
+    assert_eq!(lda.text, "LDA table,X");
+    assert_eq!(
+      disassemble_with(&mem, lda.next(), Some(&symbols)).text,
+      "JSR add"
+    );
+    assert_eq!(disassemble_with(&mem, 0x0200, None).text, "LDA $0300,X");
+  }
+
+  #[test]
+  fn profiler_times_subroutines() {
+    let mut vm = Vm::new();
+    // LDX #$03 ; loop: JSR work ; DEX ; BNE loop ; end: JMP end
+    // work: JSR inner ; NOP ; RTS ; inner: NOP ; RTS
+    vm.load(
+      &[
+        0xA2, 0x03, 0x20, 0x0C, 0x02, 0xCA, 0xD0, 0xFA, 0x4C, 0x08, 0x02, 0x00, 0x20, 0x11, 0x02,
+        0xEA, 0x60, 0xEA, 0x60,
+      ],
+      0x0200,
+    )
+    .unwrap();
+    vm.load(&[0x00, 0x02], 0xFFFC).unwrap();
+    vm.reset();
+    vm.cpu.start_profiling();
+    vm.add_breakpoint(0x0208);
+    assert_eq!(vm.run(1000).unwrap(), Some(0x0208));
+
+    let profiler = vm.cpu.stop_profiling().unwrap();
+    assert_eq!(profiler.pc(0x0205).executions, 3, "DEX didn't run 3 times");
+    assert_eq!(profiler.pc(0x0206).cycles, 3 + 3 + 2, "BNE cycles are off");
+
+    let routines = profiler.routines();
+    let work = routines.iter().find(|r| r.addr == 0x020C).unwrap();
+    let inner = routines.iter().find(|r| r.addr == 0x0211).unwrap();
+    assert_eq!(work.calls, 3);
+    // JSR, NOP and RTS in work, NOP and RTS in inner
+    assert_eq!(work.exclusive, 3 * (6 + 2 + 6), "work's own cycles are off");
+    assert_eq!(inner.inclusive, 3 * (2 + 6));
+    assert_eq!(work.inclusive, work.exclusive + inner.inclusive);
+
+    let mut symbols = Symbols::new();
+    symbols.insert("main", 0x0200);
+    symbols.insert("work", 0x020C);
+    symbols.insert("inner", 0x0211);
+    let folded = profiler.folded(Some(&symbols));
+    assert!(
+      folded.contains("main;work;inner 24\n"),
+      "missing the inner stack: {}",
+      folded
+    );
+    let report = profiler.report(&vm.cpu.mem, Some(&symbols), 10);
+    assert!(report.contains("JSR inner"), "{}", report);
+  }
+
+  #[test]
+  fn coverage_maps_to_source_lines() {
+    let dbg = "version\tmajor=2,minor=0
+file\tid=0,name=\"test.s\",size=100,mtime=0x60000000,mod=0
+seg\tid=0,name=\"CODE\",start=0x000200,size=0x000D,addrsize=absolute,type=ro
+span\tid=0,seg=0,start=0,size=2
+span\tid=1,seg=0,start=2,size=1
+span\tid=2,seg=0,start=3,size=2
+span\tid=3,seg=0,start=5,size=3
+span\tid=4,seg=0,start=8,size=3
+span\tid=5,seg=0,start=11,size=1
+span\tid=6,seg=0,start=12,size=1
+line\tid=0,file=0,line=1,span=0
+line\tid=1,file=0,line=2,span=1
+line\tid=2,file=0,line=3,span=2
+line\tid=3,file=0,line=4,span=3
+line\tid=4,file=0,line=5,span=4
+line\tid=5,file=0,line=6,span=5
+line\tid=6,file=0,line=7,span=6
+";
+    let symbols = Symbols::parse(dbg).unwrap();
+    assert_eq!(
+      symbols.lines()[3],
+      SourceLine {
+        file: "test.s".into(),
+        line: 4,
+        addr: 0x0205,
+        len: 3
+      }
+    );
+
+    let mut vm = Vm::new();
+    // LDX #$02 ; loop: DEX ; BNE loop ; LDA table ; end: JMP end ; NOP ; table: .byte 5
+    vm.load(
+      &[
+        0xA2, 0x02, 0xCA, 0xD0, 0xFD, 0xAD, 0x0C, 0x02, 0x4C, 0x08, 0x02, 0xEA, 0x05,
+      ],
+      0x0200,
+    )
+    .unwrap();
+    vm.load(&[0x00, 0x02], 0xFFFC).unwrap();
+    vm.reset();
+    vm.cpu.mem.start_coverage();
+    vm.add_breakpoint(0x0208);
+    assert_eq!(vm.run(1000).unwrap(), Some(0x0208));
+
+    let coverage = vm.cpu.mem.stop_coverage().unwrap();
+    assert_eq!(coverage.get(0x0200), Access::OPCODE);
+    assert_eq!(coverage.get(0x0201), Access::OPERAND);
+    assert_eq!(coverage.get(0x020C), Access::READ, "table wasn't read");
+    assert!(coverage.get(0x0208).is_empty(), "JMP never ran");
+
+    let lcov = coverage.lcov(&symbols, "test");
+    assert!(lcov.starts_with("TN:test\nSF:test.s\nDA:1,1\n"), "{}", lcov);
+    assert!(
+      lcov.contains("DA:5,0\nDA:6,0\nDA:7,1\nLF:7\nLH:5\n"),
+      "{}",
+      lcov
+    );
+  }
+
+  #[test]
+  fn cpu_runs_on_borrowed_ram() {
+    // 1K mirrored over the address space, like a board that doesn't decode the high lines
+    let ram: &'static mut [u8] = Box::leak(vec![0; 0x400].into_boxed_slice());
+    let mut mem = Mem::with_ram(ram).unwrap();
+    assert_eq!(
+      Mem::with_ram(Box::leak(Box::new([]))).err(),
+      Some(Error::RamSize {
+        machine: "the cpu",
+        kib: 0
+      }),
+      "the cpu got no RAM"
+    );
+    // LDA #$42 ; STA $10 ; end: JMP end
+    mem
+      .load(&[0xA9, 0x42, 0x85, 0x10, 0x4C, 0x04, 0x02], 0x0200)
+      .unwrap();
+    mem.load(&[0x00, 0x02], 0xFFFC).unwrap();
+    assert_eq!(mem.peek(0x03FC), 0x00, "reset vector wasn't mirrored");
+
+    let mut cpu = CPU::new(mem);
+    assert_eq!(
+      cpu.pc, 0x0200,
+      "reset vector wasn't read through the mirror"
+    );
+    while cpu.pc != 0x0204 {
+      cpu.clock().unwrap();
+    }
+    assert_eq!(
+      cpu.mem.peek(0x0410),
+      0x42,
+      "store didn't land in the mirrored RAM"
+    );
+  }
+
+  // counts its ticks, its irq output is set from outside
+  #[derive(Default)]
+  struct Ticks {
+    ticks: u64,
+    irq: bool,
+  }
+
+  impl Device for Ticks {
+    fn read(&mut self, _: u16) -> u8 {
+      0
+    }
+
+    fn write(&mut self, _: u16, _: u8) {}
+
+    fn peek(&self, _: u16) -> u8 {
+      0
+    }
+
+    fn tick(&mut self) {
+      self.ticks += 1;
+    }
+
+    fn irq(&self) -> bool {
+      self.irq
+    }
+  }
+
+  #[test]
+  fn scheduler_runs_devices_at_their_dividers() {
+    let mut vm = Vm::new();
+    let ticks = Rc::new(RefCell::new(Ticks::default()));
+    let device: DeviceRef = ticks.clone();
+    vm.cpu.mem.map_device(0xD000, 1, device.clone());
+    vm.scheduler.set_cpu_divider(2);
+    vm.scheduler
+      .attach(&mut vm.cpu.mem, device, 3, Some(Line::Nmi));
+
+    for _ in 0..6 {
+      vm.step().unwrap();
+    }
+    assert_eq!(
+      vm.scheduler.now(),
+      11,
+      "cpu cycles aren't every 2 master cycles"
+    );
+    assert_eq!(vm.cycles(), 6);
+    // master cycles 0, 3, 6 and 9, and never by the bus
+    assert_eq!(ticks.borrow().ticks, 4, "device didn't run at its divider");
+  }
+
+  #[test]
+  fn scheduler_delivers_interrupts() {
+    let mut vm = Vm::new();
+    // CLI ; loop: JMP loop
+    vm.load(&[0x58, 0x4C, 0x01, 0x02], 0x0200).unwrap();
+    // nmi: RTI ; irq: JMP irq
+    vm.load(&[0x40], 0x0300).unwrap();
+    vm.load(&[0x4C, 0x00, 0x04], 0x0400).unwrap();
+    // nmi at $0300, reset at $0200 and irq at $0400
+    vm.load(&[0x00, 0x03, 0x00, 0x02, 0x00, 0x04], 0xFFFA)
+      .unwrap();
+    vm.reset();
+
+    let ticks = Rc::new(RefCell::new(Ticks::default()));
+    let device: DeviceRef = ticks.clone();
+    vm.scheduler
+      .attach(&mut vm.cpu.mem, device, 1, Some(Line::Nmi));
+
+    let timer = vm.scheduler.schedule(100, Event::Irq(true));
+    vm.add_breakpoint(0x0400);
+    assert_eq!(
+      vm.run(1000).unwrap(),
+      Some(0x0400),
+      "timer irq wasn't taken"
+    );
+    assert!(vm.scheduler.now() >= 100, "irq was taken early");
+    assert_eq!(
+      vm.scheduler.remaining(timer),
+      None,
+      "one-shot timer is still waiting"
+    );
+
+    // the device's output falling is an nmi, once
+    vm.remove_breakpoint(0x0400);
+    vm.scheduler.set_irq(false);
+    ticks.borrow_mut().irq = true;
+    vm.add_breakpoint(0x0300);
+    assert_eq!(
+      vm.run(100).unwrap(),
+      Some(0x0300),
+      "device nmi wasn't taken"
+    );
+    vm.step_instruction().unwrap();
+    assert_eq!(vm.run(100).unwrap(), None, "a held nmi was taken twice");
+
+    // repeating timers keep their period, at 40 and 80 master cycles
+    let vblank = vm
+      .scheduler
+      .schedule_every(40, Event::Call(Box::new(|cpu| cpu.reg_x += 1)));
+    let start = vm.cpu.reg_x;
+    vm.run(100).unwrap();
+    assert_eq!(
+      vm.cpu.reg_x - start,
+      2,
+      "periodic timer didn't fire every period"
+    );
+    assert!(vm.scheduler.cancel(vblank), "periodic timer was gone");
+  }
+
+  #[test]
+  fn throttle_keeps_to_the_clock() {
+    assert_eq!("2x".parse(), Ok(Speed::Times(2.0)));
+    assert_eq!("0.5".parse(), Ok(Speed::Times(0.5)));
+    assert_eq!("unlimited".parse(), Ok(Speed::Unlimited));
+    assert!("0x".parse::<Speed>().is_err(), "a stopped clock parsed");
+    assert!(
+      "1e-300x".parse::<Speed>().is_err(),
+      "a too slow clock parsed"
+    );
+    assert!(
+      "1e300x".parse::<Speed>().is_err(),
+      "a too fast clock parsed"
+    );
+    assert!("NaNx".parse::<Speed>().is_err(), "NaN parsed");
+    assert_eq!("0.000001x".parse(), Ok(Speed::Times(1e-6)));
+
+    // 10kHz, a slice is 100 cycles or 10ms
+    for hz in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+      assert_eq!(
+        Throttle::new(hz).err(),
+        Some(Error::Frequency),
+        "a {} Hz clock was made",
+        hz
+      );
+    }
+    let mut throttle = Throttle::new(10_000.0).unwrap();
+    assert_eq!(throttle.slice(), 100);
+    let start = Instant::now();
+    for _ in 0..3 {
+      throttle.sync(throttle.slice());
+    }
+    assert!(
+      start.elapsed() >= Duration::from_millis(30),
+      "300 cycles at 10kHz took less than 30ms"
+    );
+
+    throttle.set_speed(Speed::Times(2.0));
+    assert_eq!(throttle.slice(), 200, "2x doesn't run bigger slices");
+
+    // a host that stalls gives up the time instead of racing to catch up
+    thread::sleep(Duration::from_millis(300));
+    throttle.sync(1);
+    assert!(
+      throttle.lost() >= Duration::from_millis(250),
+      "stall wasn't reported"
+    );
+    assert!(
+      throttle.drift().abs() < 0.1,
+      "throttle didn't resync after a stall"
+    );
+
+    // speeds made by hand that wouldn't parse are clamped, and never sleep long
+    for (times, clamped) in [(0.0, 1e-6), (-1.0, 1e-6), (1e300, 1e6), (f64::NAN, 1.0)] {
+      throttle.set_speed(Speed::Times(times));
+      assert_eq!(
+        throttle.speed(),
+        Speed::Times(clamped),
+        "{}x wasn't clamped",
+        times
+      );
+      let start = Instant::now();
+      throttle.sync(1_000);
+      assert!(
+        start.elapsed() < Duration::from_millis(100),
+        "{}x slept for {:?}",
+        times,
+        start.elapsed()
+      );
+    }
+
+    throttle.set_speed(Speed::Unlimited);
+    assert_eq!(throttle.target_hz(), None);
+    let start = Instant::now();
+    throttle.sync(1_000_000);
+    assert!(
+      start.elapsed() < Duration::from_millis(100),
+      "unlimited speed slept"
+    );
+  }
+
+  // $4014 starts the sprite DMA and $2004 collects what it writes
+  #[derive(Default)]
+  struct Ppu {
+    oam: Vec<u8>,
+    page: Option<u8>,
+  }
+
+  impl Device for Ppu {
+    fn read(&mut self, _: u16) -> u8 {
+      0
+    }
+
+    fn write(&mut self, addr: u16, data: u8) {
+      match addr {
+        0x0004 => self.oam.push(data),
+        0x2014 => self.page = Some(data),
+        _ => {}
+      }
+    }
+
+    fn peek(&self, _: u16) -> u8 {
+      0
+    }
+
+    fn dma(&mut self) -> Option<Dma> {
+      self.page.take().map(Dma::nes_oam)
+    }
+  }
+
+  #[test]
+  fn dma_steals_cycles() {
+    let mut vm = Vm::new();
+    let ppu = Rc::new(RefCell::new(Ppu::default()));
+    vm.cpu.mem.map_device(0x2000, 0x2020, ppu.clone());
+    // LDA #$03 ; STA $4014 ; LDX #$01
+    vm.load(&[0xA9, 0x03, 0x8D, 0x14, 0x40, 0xA2, 0x01], 0x0200)
+      .unwrap();
+    vm.load(&(0..=255).collect::<Vec<u8>>(), 0x0300).unwrap();
+    vm.load(&[0x00, 0x02], 0xFFFC).unwrap();
+    vm.reset();
+    vm.step_instruction().unwrap();
+
+    let start = vm.cycles();
+    while vm.cpu.reg_x == 0 {
+      vm.step().unwrap();
+    }
+    let stolen = vm.cpu.mem.dma_cycles();
+    assert!(
+      stolen == 513 || stolen == 514,
+      "OAM DMA took {} cycles",
+      stolen
+    );
+    // STA and LDX's first cycle around the transfer
+    assert_eq!(vm.cycles() - start, 4 + 1 + stolen);
+    assert_eq!(ppu.borrow().oam, (0..=255).collect::<Vec<u8>>());
+
+    // holding the bus keeps RDY low until it's done
+    vm.cpu.mem.start_dma(Dma::hold(3));
+    assert!(!vm.cpu.ready(), "DMA didn't take RDY");
+    for _ in 0..3 {
+      vm.step().unwrap();
+    }
+    assert!(vm.cpu.ready(), "the bus wasn't handed back");
+    assert_eq!(vm.cpu.mem.dma_cycles(), stolen + 3);
+  }
+
+  #[derive(Default)]
+  struct Log {
+    events: Vec<BusEvent>,
+  }
+
+  impl Observer for Log {
+    fn access(&mut self, event: &BusEvent) {
+      self.events.push(*event);
+    }
+  }
+
+  #[test]
+  fn observers_see_every_bus_cycle() {
+    let mut vm = Vm::new();
+    // LDA #$42 ; LDX #$01 ; INC $0F,X ; STA $03FF,X ; PHA ; end: JMP end
+    vm.load(
+      &[
+        0xA9, 0x42, 0xA2, 0x01, 0xF6, 0x0F, 0x9D, 0xFF, 0x03, 0x48, 0x4C, 0x0A, 0x02,
+      ],
+      0x0200,
+    )
+    .unwrap();
+    vm.load(&[0x07], 0x0010).unwrap();
+    vm.load(&[0x00, 0x02], 0xFFFC).unwrap();
+    vm.reset();
+
+    let log = Rc::new(RefCell::new(Log::default()));
+    let observer: ObserverRef = log.clone();
+    vm.cpu.mem.add_observer(observer.clone());
+    for _ in 0..5 {
+      vm.step_instruction().unwrap();
+    }
+
+    let events = log.borrow().events.clone();
+    assert_eq!(events.len(), 2 + 2 + 6 + 5 + 3, "{:#?}", events);
+    assert!(
+      events.windows(2).all(|e| e[1].cycle == e[0].cycle + 1),
+      "accesses didn't take a cycle each: {:#?}",
+      events
+    );
+
+    let accesses: Vec<(u16, u8, Access)> =
+      events.iter().map(|e| (e.addr, e.data, e.access)).collect();
+    assert_eq!(
+      accesses[4..10],
+      [
+        (0x0204, 0xF6, Access::OPCODE),
+        (0x0205, 0x0F, Access::OPERAND),
+        (0x000F, 0x00, Access::READ | Access::DUMMY),
+        (0x0010, 0x07, Access::READ),
+        (0x0010, 0x07, Access::WRITTEN | Access::DUMMY),
+        (0x0010, 0x08, Access::WRITTEN),
+      ],
+      "INC's cycles"
+    );
+    assert_eq!(
+      accesses[13..15],
+      [
+        (0x0300, 0x00, Access::READ | Access::DUMMY),
+        (0x0400, 0x42, Access::WRITTEN),
+      ],
+      "STA didn't read the wrong page before crossing"
+    );
+    assert_eq!(
+      accesses[16],
+      (0x020A, 0x4C, Access::READ | Access::DUMMY),
+      "PHA didn't read past its opcode"
+    );
+
+    vm.cpu.mem.remove_observer(&observer);
+    vm.step_instruction().unwrap();
+    assert_eq!(
+      log.borrow().events.len(),
+      events.len(),
+      "removed observer ran"
+    );
+  }
+
+  #[test]
+  fn rejected_opcodes_stay_off_the_bus() {
+    let mut vm = Vm::new();
+    // JAM, with the nmi handler at $0300
+    vm.load(&[0x02], 0x0200).unwrap();
+    vm.load(&[0x00, 0x03, 0x00, 0x02], 0xFFFA).unwrap();
+    vm.reset();
+    vm.cpu.on_illegal_opcode = IllegalOpcodePolicy::Error;
+    while vm.cpu.cycles > 0 {
+      vm.step().unwrap();
+    }
+
+    let log = Rc::new(RefCell::new(Log::default()));
+    vm.cpu.mem.add_observer(log.clone());
+    vm.cpu.mem.start_coverage();
+    let cycles = vm.cycles();
+    for _ in 0..3 {
+      assert_eq!(
+        vm.step(),
+        Err(Error::IllegalOpcode {
+          pc: 0x0200,
+          opcode: 0x02
+        }),
+        "the illegal opcode wasn't rejected"
+      );
+    }
+    assert!(
+      log.borrow().events.is_empty(),
+      "rejected opcode was fetched"
+    );
+    assert!(
+      vm.cpu.mem.stop_coverage().unwrap().get(0x0200).is_empty(),
+      "rejected opcode was covered"
+    );
+    assert_eq!(vm.cycles(), cycles, "rejected opcode took cycles");
+
+    // two dummy reads, three pushes and the vector
+    vm.cpu.nmi();
+    assert_eq!(vm.cpu.cycles, 7, "nmi didn't take 7 cycles");
+    assert_eq!(
+      log.borrow().events.len(),
+      7,
+      "nmi didn't use the bus 7 times"
+    );
+    while vm.cpu.cycles > 0 {
+      vm.step().unwrap();
+    }
+    assert_eq!(vm.cpu.pc, 0x0300, "nmi didn't jump through its vector");
+  }
+
+  #[test]
+  fn stack_errors_keep_the_clocks_together() {
+    let mut vm = Vm::new();
+    // PHA ; end: JMP end
+    vm.load(&[0x48, 0x4C, 0x01, 0x02], 0x0200).unwrap();
+    vm.load(&[0x00, 0x02], 0xFFFC).unwrap();
+    vm.reset();
+    vm.cpu.on_stack_wrap = StackPolicy::Error;
+    while vm.cpu.cycles > 0 {
+      vm.step().unwrap();
+    }
+
+    // T1 counting down from $FFFF on the scheduler's clock
+    let via = Rc::new(RefCell::new(Via::new()));
+    via.borrow_mut().write(0x4, 0xFF);
+    via.borrow_mut().write(0x5, 0xFF);
+    let device: DeviceRef = via.clone();
+    vm.scheduler.attach(&mut vm.cpu.mem, device, 1, None);
+    let t1 = || via.borrow().peek(0x4);
+    let (cycles, now, start) = (vm.cycles(), vm.scheduler.now(), t1());
+
+    vm.cpu.sp = 0x00;
+    assert_eq!(
+      vm.step(),
+      Err(Error::StackOverflow { pc: 0x0200 }),
+      "pushing at $0100 didn't overflow"
+    );
+    assert_eq!(vm.cycles(), cycles + 1, "the vm didn't count the cycle");
+    assert_eq!(vm.scheduler.now(), now + 1, "the scheduler didn't count it");
+    assert_eq!(start - t1(), 1, "the VIA didn't tick with the cpu");
+
+    // the rest of PHA
+    while vm.cpu.cycles > 0 {
+      vm.step().unwrap();
+    }
+    assert_eq!(vm.cycles(), cycles + 3, "PHA didn't take 3 cycles");
+    assert_eq!(vm.scheduler.now(), now + 3, "the scheduler fell behind");
+    assert_eq!(start - t1(), 3, "the VIA fell behind the cpu");
+  }
+}
@@ -0,0 +1,195 @@
+use std::{
+  fmt,
+  str::FromStr,
+  thread,
+  time::{Duration, Instant},
+};
+
+use super::error::Error;
+
+// how long a batch of cycles should take at the target speed
+const SLICE: Duration = Duration::from_millis(10);
+// further behind than this, like after the host was suspended, and the throttle
+// stops trying to catch up
+const MAX_LAG: Duration = Duration::from_millis(250);
+// the slowest and fastest multipliers a throttle runs at
+const MIN_SPEED: f64 = 1e-6;
+const MAX_SPEED: f64 = 1e6;
+
+// a multiple of the machine's clock, or as fast as the host can go
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Speed {
+  Times(f64),
+  Unlimited,
+}
+
+impl Default for Speed {
+  fn default() -> Self {
+    Speed::Times(1.0)
+  }
+}
+
+impl Speed {
+  fn in_range(times: f64) -> bool {
+    (MIN_SPEED..=MAX_SPEED).contains(&times)
+  }
+
+  // the nearest multiplier a throttle can run at, NaN is the machine's own clock
+  fn clamped(self) -> Self {
+    match self {
+      Speed::Times(times) if Speed::in_range(times) => self,
+      Speed::Times(times) if times.is_nan() => Speed::default(),
+      Speed::Times(times) => Speed::Times(times.clamp(MIN_SPEED, MAX_SPEED)),
+      Speed::Unlimited => self,
+    }
+  }
+}
+
+// "2", "2x", "0.5x" or "unlimited"
+impl FromStr for Speed {
+  type Err = String;
+
+  fn from_str(text: &str) -> Result<Self, Self::Err> {
+    if text == "unlimited" {
+      return Ok(Speed::Unlimited);
+    }
+
+    match text.strip_suffix('x').unwrap_or(text).parse::<f64>() {
+      Ok(times) if Speed::in_range(times) => Ok(Speed::Times(times)),
+      _ => Err(format!(
+        "bad speed {}, expected a multiplier from {}x to {}x or unlimited",
+        text, MIN_SPEED, MAX_SPEED
+      )),
+    }
+  }
+}
+
+// keeps a run loop at a clock frequency in wall-clock time. run `slice()` cycles,
+// then `sync` with how many ran and it sleeps off the difference
+#[derive(Debug, Clone)]
+pub struct Throttle {
+  hz: f64,
+  speed: Speed,
+  start: Instant,
+  cycles: u64,
+  // when the throttle last started counting, after a speed change or falling behind
+  epoch: Instant,
+  epoch_cycles: u64,
+  lost: Duration,
+}
+
+impl Throttle {
+  pub fn new(hz: f64) -> Result<Self, Error> {
+    if !(hz > 0.0 && hz.is_finite()) {
+      return Err(Error::Frequency);
+    }
+    let now = Instant::now();
+    Ok(Self {
+      hz,
+      speed: Speed::default(),
+      start: now,
+      cycles: 0,
+      epoch: now,
+      epoch_cycles: 0,
+      lost: Duration::ZERO,
+    })
+  }
+
+  pub fn hz(&self) -> f64 {
+    self.hz
+  }
+
+  pub fn speed(&self) -> Speed {
+    self.speed
+  }
+
+  // multipliers out of the range `Speed` parses are clamped to it
+  pub fn set_speed(&mut self, speed: Speed) {
+    self.speed = speed.clamped();
+    self.resync();
+  }
+
+  // the frequency it's aiming for, none when unlimited
+  pub fn target_hz(&self) -> Option<f64> {
+    match self.speed {
+      Speed::Times(times) => Some(self.hz * times),
+      Speed::Unlimited => None,
+    }
+  }
+
+  // cycles to run before the next sync, unlimited runs batches of the
+  // machine's own clock so input is still polled
+  pub fn slice(&self) -> u64 {
+    let hz = self.target_hz().unwrap_or(self.hz);
+    ((hz * SLICE.as_secs_f64()) as u64).max(1)
+  }
+
+  // counts `cycles` that just ran and sleeps until the wall clock catches up with them,
+  // for up to a slice, further ahead than that and the next syncs sleep off the rest
+  pub fn sync(&mut self, cycles: u64) {
+    self.cycles += cycles;
+    if self.target_hz().is_none() {
+      return;
+    }
+
+    let ahead = self.drift();
+    if ahead > 0.0 {
+      // a slow clock still hands back to the caller for input
+      thread::sleep(Duration::from_secs_f64(ahead.min(SLICE.as_secs_f64())));
+    } else if -ahead > MAX_LAG.as_secs_f64() {
+      self.lost += Duration::from_secs_f64(-ahead);
+      self.resync();
+    }
+  }
+
+  // starts counting again from now, forgetting how far ahead or behind it was
+  pub fn resync(&mut self) {
+    self.epoch = Instant::now();
+    self.epoch_cycles = self.cycles;
+  }
+
+  /* ------- drift -------- */
+  pub fn cycles(&self) -> u64 {
+    self.cycles
+  }
+
+  // seconds the emulated clock is ahead of the wall clock, negative when it's behind.
+  // zero when unlimited
+  pub fn drift(&self) -> f64 {
+    match self.target_hz() {
+      Some(hz) => {
+        let due = (self.cycles - self.epoch_cycles) as f64 / hz;
+        due - self.epoch.elapsed().as_secs_f64()
+      }
+      None => 0.0,
+    }
+  }
+
+  // the time it was too far behind to make up
+  pub fn lost(&self) -> Duration {
+    self.lost
+  }
+
+  // the clock frequency it actually ran at since it was made
+  pub fn effective_hz(&self) -> f64 {
+    self.cycles as f64 / self.start.elapsed().as_secs_f64().max(f64::EPSILON)
+  }
+  /* ------- drift -------- */
+}
+
+// "1.023 MHz (100% of 1.023 MHz), 0ms lost"
+impl fmt::Display for Throttle {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let hz = self.effective_hz();
+    write!(f, "{:.3} MHz", hz / 1e6)?;
+    if let Some(target) = self.target_hz() {
+      write!(
+        f,
+        " ({:.0}% of {:.3} MHz)",
+        hz * 100.0 / target,
+        target / 1e6
+      )?;
+    }
+    write!(f, ", {}ms lost", self.lost.as_millis())
+  }
+}
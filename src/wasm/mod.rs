@@ -0,0 +1,143 @@
+use wasm_bindgen::prelude::*;
+
+use crate::vm::{disassemble, Byte, Vm, Word};
+
+mod test;
+
+// the vm for javascript, `new Vm()` gives 64K of RAM with nothing mapped
+#[wasm_bindgen(js_name = Vm)]
+pub struct WasmVm {
+  vm: Vm,
+}
+
+impl Default for WasmVm {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+#[wasm_bindgen(js_class = Vm)]
+impl WasmVm {
+  #[wasm_bindgen(constructor)]
+  pub fn new() -> Self {
+    Self { vm: Vm::new() }
+  }
+
+  pub fn reset(&mut self) {
+    self.vm.reset();
+  }
+
+  // vm errors are thrown as javascript Errors
+  pub fn load(&mut self, data: &[u8], addr: Word) -> Result<(), JsError> {
+    Ok(self.vm.load(data, addr)?)
+  }
+
+  // loads `data` at `addr`, points the reset vector at it and resets
+  #[wasm_bindgen(js_name = loadProgram)]
+  pub fn load_program(&mut self, data: &[u8], addr: Word) -> Result<(), JsError> {
+    self.vm.load(data, addr)?;
+    self.vm.load(&[addr as Byte, (addr >> 8) as Byte], 0xFFFC)?;
+    self.vm.reset();
+    Ok(())
+  }
+
+  /* ------- execution -------- */
+  // a single clock cycle
+  pub fn step(&mut self) -> Result<(), JsError> {
+    Ok(self.vm.step()?)
+  }
+
+  #[wasm_bindgen(js_name = stepInstruction)]
+  pub fn step_instruction(&mut self) -> Result<(), JsError> {
+    Ok(self.vm.step_instruction()?)
+  }
+
+  // the breakpoint address it stopped at, or undefined once `max_cycles` have run
+  pub fn run(&mut self, max_cycles: u32) -> Result<Option<Word>, JsError> {
+    Ok(self.vm.run(max_cycles as u64)?)
+  }
+
+  #[wasm_bindgen(js_name = toggleBreakpoint)]
+  pub fn toggle_breakpoint(&mut self, addr: Word) -> bool {
+    self.vm.toggle_breakpoint(addr)
+  }
+
+  pub fn breakpoints(&self) -> Vec<Word> {
+    self.vm.breakpoints().collect()
+  }
+  /* ------- execution -------- */
+
+  /* ------- registers -------- */
+  #[wasm_bindgen(getter)]
+  pub fn pc(&self) -> Word {
+    self.vm.cpu.pc
+  }
+
+  #[wasm_bindgen(setter)]
+  pub fn set_pc(&mut self, pc: Word) {
+    self.vm.cpu.pc = pc;
+  }
+
+  #[wasm_bindgen(getter)]
+  pub fn a(&self) -> Byte {
+    self.vm.cpu.reg_a
+  }
+
+  #[wasm_bindgen(getter)]
+  pub fn x(&self) -> Byte {
+    self.vm.cpu.reg_x
+  }
+
+  #[wasm_bindgen(getter)]
+  pub fn y(&self) -> Byte {
+    self.vm.cpu.reg_y
+  }
+
+  #[wasm_bindgen(getter)]
+  pub fn sp(&self) -> Byte {
+    self.vm.cpu.sp
+  }
+
+  #[wasm_bindgen(getter)]
+  pub fn status(&self) -> Byte {
+    self.vm.cpu.status.bits()
+  }
+
+  // a number rather than a BigInt, exact for the first 2^53 cycles
+  #[wasm_bindgen(getter)]
+  pub fn cycles(&self) -> f64 {
+    self.vm.cycles() as f64
+  }
+  /* ------- registers -------- */
+
+  /* ------- memory -------- */
+  pub fn peek(&self, addr: Word) -> Byte {
+    self.vm.cpu.mem.peek(addr)
+  }
+
+  pub fn write(&mut self, addr: Word, data: Byte) {
+    self.vm.cpu.mem.write(addr, data);
+  }
+
+  // `len` bytes from `start` as a Uint8Array, wrapping at the top of memory
+  pub fn memory(&self, start: Word, len: usize) -> Vec<Byte> {
+    (0..len)
+      .map(|i| self.vm.cpu.mem.peek(start.wrapping_add(i as Word)))
+      .collect()
+  }
+
+  // `count` lines of "ADDR  BYTES  TEXT" from `addr`
+  pub fn disassemble(&self, addr: Word, count: usize) -> String {
+    let mut addr = addr;
+    let mut lines = Vec::with_capacity(count);
+    for _ in 0..count {
+      let ins = disassemble(&self.vm.cpu.mem, addr);
+      let bytes: Vec<String> = ins.bytes.iter().map(|b| format!("{:02X}", b)).collect();
+      lines.push(format!("{:04X}  {:<9} {}", addr, bytes.join(" "), ins.text));
+      addr = ins.next();
+    }
+
+    lines.join("\n")
+  }
+  /* ------- memory -------- */
+}
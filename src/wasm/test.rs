@@ -0,0 +1,32 @@
+// runs natively with `cargo test --features wasm` and under node with
+// `cargo test --target wasm32-unknown-unknown --no-default-features --features wasm`
+#[cfg(test)]
+mod tests {
+  use crate::wasm::WasmVm;
+
+  #[cfg(target_arch = "wasm32")]
+  use wasm_bindgen_test::wasm_bindgen_test as test;
+
+  #[test]
+  fn runs_to_a_breakpoint() {
+    let mut vm = WasmVm::new();
+    // LDA #$42 ; STA $10 ; loop: JMP loop
+    vm.load_program(&[0xA9, 0x42, 0x85, 0x10, 0x4C, 0x04, 0x02], 0x0200)
+      .unwrap();
+
+    assert!(vm.toggle_breakpoint(0x0204), "breakpoint wasn't set");
+    assert_eq!(
+      vm.run(1000).unwrap(),
+      Some(0x0204),
+      "didn't stop at the breakpoint"
+    );
+    assert_eq!(vm.a(), 0x42);
+    assert_eq!(vm.peek(0x0010), 0x42, "STA didn't reach memory");
+    assert_eq!(vm.memory(0x0200, 2), [0xA9, 0x42]);
+    assert_eq!(
+      vm.disassemble(0x0200, 2),
+      "0200  A9 42     LDA #$42\n0202  85 10     STA $10",
+      "disassembly doesn't match the program"
+    );
+  }
+}
@@ -0,0 +1,2 @@
+// the javascript bindings live in g6502::wasm, this only links them into a cdylib
+pub use g6502::wasm::*;